@@ -1,6 +1,14 @@
 use clap::Parser;
 use std::path::PathBuf;
 
+use crate::image::{
+    Adjustments, BilateralStage, BlurKind, BlurStage, BorderMode, CellShape, ConvertStage,
+    DenoiseMode, DenoiseStage, DespeckleMode, DespeckleStage, Dithering, EdgeDetector, FitMetric,
+    HalftoneShape, HalftoneStage, KuwaharaKind, KuwaharaStage, OutlineStage, RecolorStage,
+    RetroEffect, RetroStage, ToneRemapStage, MAX_RADIUS, MAX_SIGMA,
+};
+use crate::palette::{ExporterRegistry, ExtractMethod, ThemeFormat, ThemeVariant};
+
 #[derive(Parser, Debug, Clone)]
 #[command(
     name = "rustbucket",
@@ -8,13 +16,18 @@ use std::path::PathBuf;
     about = "RustBucket - High-performance image palette converter\n\nConverts RGB images to themed color palettes with support for 17+ built-in themes.",
     long_about = "RustBucket is a high-performance CLI tool that converts RGB images to themed color palettes.\n\nSupported palettes include Nord, Dracula, Gruvbox, Monokai, Solarized, and many more.\nThe tool offers various optimization options including blur effects, average pixel calculations, and custom pixel area processing."
 )]
+#[command(group(
+    clap::ArgGroup::new("resnap")
+        .args(["blur_resnap", "retro_resnap"])
+        .multiple(true)
+))]
 pub struct Args {
     #[arg(
         short = 'i',
         long = "img",
         help = "Input image path",
         value_name = "PATH",
-        required_unless_present_any = ["list_palettes", "create_palette", "export_palette"]
+        required_unless_present_any = ["list_palettes", "create_palette", "export_palette", "import_palette", "save_recipe", "theme", "show_palette", "swatch_sheet"]
     )]
     pub input_path: Option<PathBuf>,
 
@@ -30,7 +43,7 @@ pub struct Args {
     #[arg(
         short = 'p',
         long = "palette",
-        help = "Color palette to use",
+        help = "Color palette to use, or 'auto' for the best fit to the input image",
         value_name = "PALETTE",
         default_value = "nord"
     )]
@@ -39,7 +52,7 @@ pub struct Args {
     #[arg(
         short = 'c',
         long = "colors",
-        help = "Specific colors to use from palette (comma-separated)",
+        help = "Specific colors to use from palette (comma-separated names, aliases or @group)",
         value_name = "COLORS",
         value_delimiter = ','
     )]
@@ -48,11 +61,457 @@ pub struct Args {
     #[arg(
         short = 'b',
         long = "blur",
-        help = "Apply Gaussian blur to the final result",
+        help = "Apply a blur (see --blur-kind) to the final result",
         action = clap::ArgAction::SetTrue
     )]
     pub enable_blur: bool,
 
+    #[arg(
+        long = "blur-sigma",
+        help = "Standard deviation of the blur kernel (default: 1.2, or 0.8 for pixel areas above 2)",
+        value_name = "SIGMA",
+        value_parser = parse_sigma
+    )]
+    pub blur_sigma: Option<f32>,
+
+    #[arg(
+        long = "blur-radius",
+        help = "Blur kernel radius in pixels (default: derived from sigma)",
+        value_name = "RADIUS",
+        value_parser = clap::value_parser!(u32).range(..=i64::from(MAX_RADIUS))
+    )]
+    pub blur_radius: Option<u32>,
+
+    #[arg(
+        long = "blur-kind",
+        help = "Blur algorithm",
+        value_name = "KIND",
+        value_enum,
+        default_value_t = BlurStage::default().kind
+    )]
+    pub blur_kind: BlurKind,
+
+    #[arg(
+        long = "pre-blur",
+        help = "Blur the input image before palette conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub pre_blur: bool,
+
+    #[arg(
+        long = "blur-resnap",
+        help = "Re-snap the blurred result to palette colors (requires --blur)",
+        requires = "enable_blur",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub blur_resnap: bool,
+
+    #[arg(
+        long = "dither",
+        help = "Dithering used when re-snapping blurred or retro output to the palette (requires --blur-resnap or --retro-resnap)",
+        value_name = "MODE",
+        requires = "resnap",
+        value_enum,
+        default_value_t = Dithering::default()
+    )]
+    pub dithering: Dithering,
+
+    #[arg(
+        long = "denoise",
+        help = "Reduce noise in the input image before palette conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub denoise: bool,
+
+    #[arg(
+        long = "denoise-threshold",
+        help = "Maximum color distance of neighbours averaged by selective denoise",
+        value_name = "DISTANCE",
+        default_value_t = DenoiseStage::default().threshold,
+        value_parser = parse_non_negative
+    )]
+    pub denoise_threshold: f32,
+
+    #[arg(
+        long = "denoise-strength",
+        help = "Blend between original (0.0) and filtered (1.0) pixels",
+        value_name = "STRENGTH",
+        default_value_t = DenoiseStage::default().strength,
+        value_parser = parse_unit_interval
+    )]
+    pub denoise_strength: f32,
+
+    #[arg(
+        long = "denoise-radius",
+        help = "Denoise neighbourhood radius in pixels",
+        value_name = "RADIUS",
+        default_value_t = DenoiseStage::default().radius,
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_RADIUS))
+    )]
+    pub denoise_radius: u32,
+
+    #[arg(
+        long = "denoise-mode",
+        help = "Denoise filter",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = DenoiseStage::default().mode
+    )]
+    pub denoise_mode: DenoiseMode,
+
+    #[arg(
+        long = "border-mode",
+        help = "How neighbourhood filters treat pixels beyond the image edges",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = BorderMode::default()
+    )]
+    pub border_mode: BorderMode,
+
+    #[arg(
+        long = "brightness",
+        help = "Brightness offset applied before conversion (-1.0 to 1.0)",
+        value_name = "AMOUNT",
+        default_value_t = Adjustments::default().brightness,
+        allow_negative_numbers = true,
+        value_parser = parse_signed_unit_interval
+    )]
+    pub brightness: f32,
+
+    #[arg(
+        long = "contrast",
+        help = "Contrast multiplier applied before conversion (1.0 = unchanged)",
+        value_name = "FACTOR",
+        default_value_t = Adjustments::default().contrast,
+        value_parser = parse_non_negative
+    )]
+    pub contrast: f32,
+
+    #[arg(
+        long = "gamma",
+        help = "Gamma correction applied before conversion (>1.0 brightens midtones)",
+        value_name = "GAMMA",
+        default_value_t = Adjustments::default().gamma,
+        value_parser = parse_positive
+    )]
+    pub gamma: f32,
+
+    #[arg(
+        long = "saturation",
+        help = "Saturation multiplier applied before conversion (0.0 = grayscale)",
+        value_name = "FACTOR",
+        default_value_t = Adjustments::default().saturation,
+        value_parser = parse_non_negative
+    )]
+    pub saturation: f32,
+
+    #[arg(
+        long = "hue-rotate",
+        help = "Rotate hues by the given number of degrees before conversion",
+        value_name = "DEGREES",
+        default_value_t = Adjustments::default().hue_rotate,
+        allow_negative_numbers = true,
+        value_parser = parse_finite
+    )]
+    pub hue_rotate: f32,
+
+    #[arg(
+        long = "levels",
+        help = "Input black and white points mapped to full range (format: BLACK,WHITE)",
+        value_name = "LEVELS",
+        value_parser = parse_levels
+    )]
+    pub levels: Option<(u8, u8)>,
+
+    #[arg(
+        long = "auto-levels",
+        help = "Stretch the image's tonal range to full scale before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub auto_levels: bool,
+
+    #[arg(
+        long = "auto-white-balance",
+        help = "Neutralize color casts (gray world) before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub auto_white_balance: bool,
+
+    #[arg(
+        long = "equalize",
+        help = "Equalize the luminance histogram before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub equalize: bool,
+
+    #[arg(
+        long = "bilateral",
+        help = "Apply an edge-preserving bilateral filter before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub bilateral: bool,
+
+    #[arg(
+        long = "bilateral-sigma-space",
+        help = "Bilateral spatial falloff in pixels",
+        value_name = "SIGMA",
+        default_value_t = BilateralStage::default().sigma_space,
+        value_parser = parse_sigma
+    )]
+    pub bilateral_sigma_space: f32,
+
+    #[arg(
+        long = "bilateral-sigma-color",
+        help = "Bilateral color falloff in RGB distance; lower keeps more edges",
+        value_name = "SIGMA",
+        default_value_t = BilateralStage::default().sigma_color,
+        value_parser = parse_positive
+    )]
+    pub bilateral_sigma_color: f32,
+
+    #[arg(
+        long = "kuwahara",
+        help = "Apply a painterly Kuwahara filter before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub kuwahara: bool,
+
+    #[arg(
+        long = "kuwahara-radius",
+        help = "Kuwahara window radius",
+        value_name = "RADIUS",
+        default_value_t = KuwaharaStage::default().radius,
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_RADIUS))
+    )]
+    pub kuwahara_radius: u32,
+
+    #[arg(
+        long = "kuwahara-kind",
+        help = "Kuwahara variant",
+        value_name = "KIND",
+        value_enum,
+        default_value_t = KuwaharaStage::default().kind
+    )]
+    pub kuwahara_kind: KuwaharaKind,
+
+    #[arg(
+        long = "tone-remap",
+        help = "Remap image lightness into the palette's lightness range before conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub tone_remap: bool,
+
+    #[arg(
+        long = "tone-remap-strength",
+        help = "How far tones move towards the palette's range (0.0-1.0)",
+        value_name = "STRENGTH",
+        default_value_t = ToneRemapStage::default().strength,
+        value_parser = parse_unit_interval
+    )]
+    pub tone_remap_strength: f32,
+
+    #[arg(
+        long = "halftone",
+        help = "Render the image as a halftone dot screen using palette colors as inks",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub halftone: bool,
+
+    #[arg(
+        long = "halftone-shape",
+        help = "Halftone dot shape",
+        value_name = "SHAPE",
+        value_enum,
+        default_value_t = HalftoneStage::default().shape
+    )]
+    pub halftone_shape: HalftoneShape,
+
+    #[arg(
+        long = "halftone-angle",
+        help = "Halftone screen angle in degrees",
+        value_name = "DEGREES",
+        default_value_t = HalftoneStage::default().angle,
+        allow_negative_numbers = true,
+        value_parser = parse_finite
+    )]
+    pub halftone_angle: f32,
+
+    #[arg(
+        long = "halftone-cell",
+        help = "Halftone cell size in pixels",
+        value_name = "PIXELS",
+        default_value_t = HalftoneStage::default().cell_size,
+        value_parser = parse_positive
+    )]
+    pub halftone_cell_size: f32,
+
+    #[arg(
+        long = "halftone-background",
+        help = "Halftone paper color: palette color name, or hex code snapped to the selected colors (default: first palette color)",
+        value_name = "COLOR"
+    )]
+    pub halftone_background: Option<String>,
+
+    #[arg(
+        long = "recolor",
+        help = "Give each dominant image color its own palette color instead of nearest-color matching (for flat art and logos)",
+        action = clap::ArgAction::SetTrue,
+        conflicts_with = "halftone"
+    )]
+    pub recolor: bool,
+
+    #[arg(
+        long = "recolor-clusters",
+        help = "Dominant colors to extract for --recolor (capped at the palette size)",
+        value_name = "N",
+        default_value_t = RecolorStage::default().clusters as u32,
+        value_parser = clap::value_parser!(u32).range(1..=256)
+    )]
+    pub recolor_clusters: u32,
+
+    #[arg(
+        long = "recolor-lightness",
+        help = "How strongly --recolor keeps the dark-to-light order of the image colors (0.0-1.0)",
+        value_name = "WEIGHT",
+        default_value_t = RecolorStage::default().lightness_weight,
+        value_parser = parse_unit_interval
+    )]
+    pub recolor_lightness_weight: f32,
+
+    #[arg(
+        long = "recolor-shading",
+        help = "Share of the shading inside each color region kept by --recolor; above 0.0 the output leaves the palette",
+        value_name = "STRENGTH",
+        default_value_t = RecolorStage::default().shading,
+        value_parser = parse_unit_interval
+    )]
+    pub recolor_shading: f32,
+
+    #[arg(
+        long = "despeckle",
+        help = "Clean up isolated specks after palette conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub despeckle: bool,
+
+    #[arg(
+        long = "despeckle-min-region",
+        help = "Merge connected regions smaller than this many pixels",
+        value_name = "PIXELS",
+        default_value_t = DespeckleStage::default().min_region,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub despeckle_min_region: u32,
+
+    #[arg(
+        long = "despeckle-mode",
+        help = "Despeckle strategy",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = DespeckleStage::default().mode
+    )]
+    pub despeckle_mode: DespeckleMode,
+
+    #[arg(
+        long = "despeckle-radius",
+        help = "Window radius for the majority despeckle mode",
+        value_name = "RADIUS",
+        default_value_t = DespeckleStage::default().radius,
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_RADIUS))
+    )]
+    pub despeckle_radius: u32,
+
+    #[arg(
+        long = "outline",
+        help = "Draw cartoon outlines along edges after conversion",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub outline: bool,
+
+    #[arg(
+        long = "outline-color",
+        help = "Outline color: palette color name, or hex code snapped to the palette (default: darkest palette color)",
+        value_name = "COLOR"
+    )]
+    pub outline_color: Option<String>,
+
+    #[arg(
+        long = "outline-detector",
+        help = "Edge detector used for outlines",
+        value_name = "DETECTOR",
+        value_enum,
+        default_value_t = OutlineStage::default().detector
+    )]
+    pub outline_detector: EdgeDetector,
+
+    #[arg(
+        long = "outline-threshold",
+        help = "Minimum luminance step (0-255) that counts as an edge",
+        value_name = "THRESHOLD",
+        default_value_t = OutlineStage::default().threshold,
+        value_parser = parse_positive
+    )]
+    pub outline_threshold: f32,
+
+    #[arg(
+        long = "outline-thickness",
+        help = "Outline thickness in pixels",
+        value_name = "PIXELS",
+        default_value_t = OutlineStage::default().thickness,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub outline_thickness: u32,
+
+    #[arg(
+        long = "retro",
+        help = "Simulate retro display hardware after conversion (comma-separated, applied in order)",
+        value_name = "EFFECTS",
+        value_enum,
+        value_delimiter = ','
+    )]
+    pub retro: Vec<RetroEffect>,
+
+    #[arg(
+        long = "retro-strength",
+        help = "Retro effect intensity, 0.0-1.0",
+        value_name = "STRENGTH",
+        default_value_t = RetroStage::default().strength,
+        value_parser = parse_unit_interval
+    )]
+    pub retro_strength: f32,
+
+    #[arg(
+        long = "retro-size",
+        help = "Scanline period, mask triad, LCD cell or bloom radius in pixels",
+        value_name = "PIXELS",
+        default_value_t = RetroStage::default().size,
+        value_parser = clap::value_parser!(u32).range(2..)
+    )]
+    pub retro_size: u32,
+
+    #[arg(
+        long = "retro-resnap",
+        help = "Re-snap the retro effect output to palette colors (requires --retro)",
+        requires = "retro",
+        action = clap::ArgAction::SetTrue
+    )]
+    pub retro_resnap: bool,
+
+    #[arg(
+        long = "recipe",
+        help = "Run the stages from a TOML recipe file instead of the effect flags",
+        value_name = "PATH"
+    )]
+    pub recipe: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "save-recipe",
+        help = "Write the recipe equivalent to the given effect flags to PATH and exit",
+        value_name = "PATH",
+        conflicts_with = "recipe"
+    )]
+    pub save_recipe: Option<std::path::PathBuf>,
+
     #[arg(
         short = 'q',
         long = "quiet",
@@ -76,6 +535,48 @@ pub struct Args {
     )]
     pub pixels_area: Option<(u32, u32)>,
 
+    #[arg(
+        long = "cell-shape",
+        help = "Shape of the averaged pixel cells (size set by --pixels-area, default 8)",
+        value_name = "SHAPE",
+        value_enum,
+        default_value_t = ConvertStage::default().cell_shape
+    )]
+    pub cell_shape: CellShape,
+
+    #[arg(
+        long = "cell-jitter",
+        help = "How far Voronoi cell seeds stray from a regular grid, 0.0-1.0",
+        value_name = "AMOUNT",
+        default_value_t = ConvertStage::default().cell_jitter,
+        value_parser = parse_unit_interval
+    )]
+    pub cell_jitter: f32,
+
+    #[arg(
+        long = "cell-seed",
+        help = "Random seed for Voronoi cell placement",
+        value_name = "SEED",
+        default_value_t = ConvertStage::default().cell_seed
+    )]
+    pub cell_seed: u64,
+
+    #[arg(
+        long = "grout-color",
+        help = "Draw lines between cells in this palette color (hex codes are snapped to the palette)",
+        value_name = "COLOR"
+    )]
+    pub grout_color: Option<String>,
+
+    #[arg(
+        long = "grout-width",
+        help = "Grout line width in pixels",
+        value_name = "PIXELS",
+        default_value_t = ConvertStage::default().grout_width,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub grout_width: u32,
+
     #[arg(
         long = "benchmark",
         help = "Run performance benchmarks and show optimization suggestions",
@@ -90,6 +591,28 @@ pub struct Args {
     )]
     pub list_palettes: bool,
 
+    #[arg(
+        long = "show-palette",
+        help = "Print a palette's colors as truecolor blocks with names and hex values",
+        value_name = "PALETTE"
+    )]
+    pub show_palette: Option<String>,
+
+    #[arg(
+        long = "swatch-sheet",
+        help = "Render a labelled PNG swatch sheet of one palette, or of all palettes grouped by source: --swatch-sheet all swatches.png",
+        value_names = ["PALETTE", "PATH"],
+        num_args = 2
+    )]
+    pub swatch_sheet: Option<Vec<String>>,
+
+    #[arg(
+        long = "base16-dir",
+        help = "Directory of base16/base24 scheme YAML files (repeatable; default: $RTBT_BASE16_DIR, ~/.config/rtbt/base16)",
+        value_name = "PATH"
+    )]
+    pub base16_dirs: Vec<PathBuf>,
+
     #[arg(
         long = "create-palette",
         help = "Create a skeleton palette file at the specified path",
@@ -99,11 +622,190 @@ pub struct Args {
 
     #[arg(
         long = "export-palette",
-        help = "Export a built-in palette to TOML format: --export-palette nord output.toml",
+        help = "Export a palette, format chosen by extension (.toml, .gpl, .ase, .json, .css, .scss, .js, .svg): --export-palette nord output.css",
         value_names = ["PALETTE", "PATH"],
         num_args = 2
     )]
     pub export_palette: Option<Vec<String>>,
+
+    #[arg(
+        long = "export-format",
+        help = "Export format when the extension doesn't decide it",
+        value_name = "FORMAT",
+        value_parser = clap::builder::PossibleValuesParser::new(ExporterRegistry::new().names()),
+        ignore_case = true,
+        requires = "export_palette"
+    )]
+    pub export_format: Option<String>,
+
+    #[arg(
+        long = "import-palette",
+        help = "Convert a swatch file (.gpl, .ase, .aco, .pal, .txt, .hex) or terminal color scheme to TOML: --import-palette swatches.gpl output.toml",
+        value_names = ["SOURCE", "PATH"],
+        num_args = 2
+    )]
+    pub import_palette: Option<Vec<PathBuf>>,
+
+    #[arg(
+        long = "import-scheme",
+        help = "Scheme to import when the source holds several (Windows Terminal settings.json)",
+        value_name = "NAME",
+        requires = "import_palette"
+    )]
+    pub import_scheme: Option<String>,
+
+    #[arg(
+        long = "extract-palette",
+        help = "Extract a palette from the input image into a TOML file at PATH and exit",
+        value_name = "PATH"
+    )]
+    pub extract_palette: Option<PathBuf>,
+
+    #[arg(
+        long = "extract-method",
+        help = "Color quantization used by --extract-palette",
+        value_name = "METHOD",
+        value_enum,
+        default_value_t = ExtractMethod::MedianCut,
+        requires = "extract_palette"
+    )]
+    pub extract_method: ExtractMethod,
+
+    #[arg(
+        long = "extract-colors",
+        help = "Number of colors to extract",
+        value_name = "COUNT",
+        default_value_t = 8,
+        value_parser = clap::value_parser!(u32).range(1..=256),
+        requires = "extract_palette"
+    )]
+    pub extract_colors: u32,
+
+    #[arg(
+        long = "extract-name",
+        help = "Name of the extracted palette (default: output file name)",
+        value_name = "NAME",
+        requires = "extract_palette"
+    )]
+    pub extract_name: Option<String>,
+
+    #[arg(
+        long = "extract-min-distance",
+        help = "Drop extracted colors closer than this OKLab distance to a more common one",
+        value_name = "DISTANCE",
+        value_parser = parse_positive,
+        requires = "extract_palette"
+    )]
+    pub extract_min_distance: Option<f32>,
+
+    #[arg(
+        long = "extract-keep-transparent",
+        help = "Include fully transparent pixels when extracting a palette",
+        action = clap::ArgAction::SetTrue,
+        requires = "extract_palette"
+    )]
+    pub extract_keep_transparent: bool,
+
+    #[arg(
+        long = "theme",
+        help = "Write a terminal theme built from the palette (or from the input image's colors when -i is given) to PATH and exit",
+        value_name = "PATH"
+    )]
+    pub theme: Option<PathBuf>,
+
+    #[arg(
+        long = "theme-format",
+        help = "Theme format (default: from the file name)",
+        value_name = "FORMAT",
+        value_enum,
+        requires = "theme"
+    )]
+    pub theme_format: Option<ThemeFormat>,
+
+    #[arg(
+        long = "theme-variant",
+        help = "Dark or light theme",
+        value_name = "VARIANT",
+        value_enum,
+        default_value_t = ThemeVariant::Auto
+    )]
+    pub theme_variant: ThemeVariant,
+
+    #[arg(
+        long = "theme-min-contrast",
+        help = "Minimum foreground/background WCAG contrast ratio",
+        value_name = "RATIO",
+        default_value_t = 4.5,
+        value_parser = parse_contrast_ratio
+    )]
+    pub theme_min_contrast: f32,
+
+    #[arg(
+        long = "theme-name",
+        help = "Name written into the theme (default: file name)",
+        value_name = "NAME"
+    )]
+    pub theme_name: Option<String>,
+
+    #[arg(
+        long = "contact-sheet",
+        help = "Convert the input with every palette (or --contact-palettes) and write a labelled grid to PATH",
+        value_name = "PATH",
+        requires = "input_path"
+    )]
+    pub contact_sheet: Option<PathBuf>,
+
+    #[arg(
+        long = "contact-palettes",
+        help = "Comma-separated palettes for --contact-sheet (default: all)",
+        value_name = "PALETTES",
+        value_delimiter = ',',
+        requires = "contact_sheet"
+    )]
+    pub contact_palettes: Vec<String>,
+
+    #[arg(
+        long = "contact-columns",
+        help = "Tiles per row in the contact sheet (default: square grid)",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "contact_sheet"
+    )]
+    pub contact_columns: Option<u32>,
+
+    #[arg(
+        long = "contact-tile-width",
+        help = "Downscale the input to this width before converting, for faster contact sheets",
+        value_name = "PX",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "contact_sheet"
+    )]
+    pub contact_tile_width: Option<u32>,
+
+    #[arg(
+        long = "rank-palettes",
+        help = "Rank all palettes by how closely they reproduce the input image and exit",
+        action = clap::ArgAction::SetTrue,
+        requires = "input_path"
+    )]
+    pub rank_palettes: bool,
+
+    #[arg(
+        long = "fit-metric",
+        help = "Ranking used by --rank-palettes and -p auto",
+        value_name = "METRIC",
+        value_enum,
+        default_value_t = FitMetric::DeltaE
+    )]
+    pub fit_metric: FitMetric,
+
+    #[arg(
+        long = "fit-samples",
+        help = "Pixels sampled when ranking palettes (0 = every pixel)",
+        value_name = "N",
+        default_value_t = 10_000
+    )]
+    pub fit_samples: usize,
 }
 
 fn parse_pixels_area(value: &str) -> Result<(u32, u32), String> {
@@ -132,9 +834,116 @@ fn parse_pixels_area(value: &str) -> Result<(u32, u32), String> {
         width
     };
 
+    if width == 0 || height == 0 {
+        return Err(format!("Pixels area must be at least 1x1: {}", value));
+    }
+
     Ok((width, height))
 }
 
+fn parse_positive(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !number.is_finite() || number <= 0.0 {
+        return Err(format!("Value must be a positive number: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_finite(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !number.is_finite() {
+        return Err(format!("Value must be a finite number: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_sigma(value: &str) -> Result<f32, String> {
+    let number = parse_positive(value)?;
+
+    if number > MAX_SIGMA {
+        return Err(format!("Sigma must be at most {:.1}: {}", MAX_SIGMA, value));
+    }
+
+    Ok(number)
+}
+
+fn parse_contrast_ratio(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !(1.0..=21.0).contains(&number) {
+        return Err(format!("Value must be between 1.0 and 21.0: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_unit_interval(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !(0.0..=1.0).contains(&number) {
+        return Err(format!("Value must be between 0.0 and 1.0: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_signed_unit_interval(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !(-1.0..=1.0).contains(&number) {
+        return Err(format!("Value must be between -1.0 and 1.0: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_non_negative(value: &str) -> Result<f32, String> {
+    let number = value
+        .parse::<f32>()
+        .map_err(|_| format!("Invalid number: {}", value))?;
+
+    if !number.is_finite() || number < 0.0 {
+        return Err(format!("Value must not be negative: {}", value));
+    }
+
+    Ok(number)
+}
+
+fn parse_levels(value: &str) -> Result<(u8, u8), String> {
+    let (black, white) = value
+        .split_once(',')
+        .ok_or_else(|| format!("Invalid levels (expected BLACK,WHITE): {}", value))?;
+
+    let black = black
+        .trim()
+        .parse::<u8>()
+        .map_err(|_| format!("Invalid black point: {}", black))?;
+    let white = white
+        .trim()
+        .parse::<u8>()
+        .map_err(|_| format!("Invalid white point: {}", white))?;
+
+    if black >= white {
+        return Err(format!("Black point must be below white point: {}", value));
+    }
+
+    Ok((black, white))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,9 +968,125 @@ mod tests {
         assert!(parse_pixels_area("1,2,3").is_err());
     }
 
+    #[test]
+    fn test_parse_pixels_area_rejects_zero() {
+        assert!(parse_pixels_area("0").is_err());
+        assert!(parse_pixels_area("4,0").is_err());
+    }
+
     #[test]
     fn test_parse_pixels_area_invalid_non_numeric() {
         assert!(parse_pixels_area("abc").is_err());
         assert!(parse_pixels_area("1,abc").is_err());
     }
+
+    #[test]
+    fn test_parse_positive() {
+        assert_eq!(parse_positive("1.5"), Ok(1.5));
+        assert!(parse_positive("0").is_err());
+        assert!(parse_positive("-2").is_err());
+        assert!(parse_positive("abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_unit_interval() {
+        assert_eq!(parse_unit_interval("0.25"), Ok(0.25));
+        assert_eq!(parse_unit_interval("1"), Ok(1.0));
+        assert!(parse_unit_interval("1.5").is_err());
+        assert!(parse_unit_interval("-0.1").is_err());
+    }
+
+    #[test]
+    fn test_parse_levels() {
+        assert_eq!(parse_levels("16,235"), Ok((16, 235)));
+        assert!(parse_levels("200,100").is_err());
+        assert!(parse_levels("16").is_err());
+        assert!(parse_levels("0,300").is_err());
+    }
+
+    #[test]
+    fn test_parse_adjustment_ranges() {
+        assert_eq!(parse_signed_unit_interval("-0.5"), Ok(-0.5));
+        assert!(parse_signed_unit_interval("2").is_err());
+        assert_eq!(parse_non_negative("0"), Ok(0.0));
+        assert!(parse_non_negative("-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_finite() {
+        assert_eq!(parse_finite("-90").unwrap(), -90.0);
+        assert!(parse_finite("nan").is_err());
+        assert!(parse_finite("inf").is_err());
+        assert!(parse_finite("abc").is_err());
+    }
+
+    #[test]
+    fn test_radius_flags_are_capped() {
+        let parse =
+            |flag: &str, value: &str| Args::try_parse_from(["rtbt", "-i", "in.png", flag, value]);
+        assert!(parse("--denoise-radius", "128").is_ok());
+        assert!(parse("--denoise-radius", "129").is_err());
+        assert!(parse("--kuwahara-radius", "0").is_err());
+        assert!(parse("--blur-radius", "100000").is_err());
+        assert!(parse("--denoise-threshold", "-1").is_err());
+        assert!(parse("--bilateral-sigma-space", "42").is_ok());
+        assert!(parse("--bilateral-sigma-space", "1e9").is_err());
+        assert!(parse("--blur-sigma", "1e9").is_err());
+        assert!(parse("--halftone-angle", "nan").is_err());
+        assert!(parse("--halftone-angle", "-15").is_ok());
+    }
+
+    #[test]
+    fn test_dither_requires_resnap() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(
+                ["rtbt", "-i", "in.png", "--dither", "ordered"]
+                    .iter()
+                    .chain(args),
+            )
+        };
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--blur", "--blur-resnap"]).is_ok());
+        assert!(parse(&["--retro", "scanlines", "--retro-resnap"]).is_ok());
+    }
+
+    #[test]
+    fn test_export_format_is_a_registered_exporter() {
+        let parse = |format: &str| {
+            Args::try_parse_from([
+                "rtbt",
+                "--export-palette",
+                "nord",
+                "out.txt",
+                "--export-format",
+                format,
+            ])
+        };
+        for name in ExporterRegistry::new().names() {
+            assert_eq!(parse(name).unwrap().export_format.as_deref(), Some(name));
+        }
+        assert!(parse("CSS").is_ok());
+        assert!(parse("bmp").is_err());
+    }
+
+    #[test]
+    fn test_extract_options_require_extract_palette() {
+        for option in [
+            &["--extract-method", "kmeans"][..],
+            &["--extract-colors", "4"],
+            &["--extract-name", "mine"],
+            &["--extract-min-distance", "0.05"],
+            &["--extract-keep-transparent"],
+        ] {
+            let args = |extra: &[&str]| {
+                Args::try_parse_from(["rtbt", "-i", "in.png"].iter().chain(extra).chain(option))
+            };
+            assert!(args(&[]).is_err(), "{:?}", option);
+            assert!(
+                args(&["--extract-palette", "out.toml"]).is_ok(),
+                "{:?}",
+                option
+            );
+        }
+    }
 }
@@ -18,11 +18,17 @@ pub enum RustBucketError {
     #[error("Invalid pixel area: {0}")]
     InvalidPixelArea(String),
 
+    #[error("Invalid image: {0}")]
+    InvalidImage(String),
+
     #[error("Color not found in palette: {0}")]
     ColorNotFound(String),
 
     #[error("Palette parse error: {0}")]
     PaletteParseError(String),
+
+    #[error("Recipe error: {0}")]
+    RecipeError(String),
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,418 @@
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// Fraction of pixels clipped at each end of the histogram by auto-levels
+const AUTO_LEVELS_CLIP: f32 = 0.005;
+
+/// Tone and color corrections applied before palette conversion.
+///
+/// All fields default to neutral values, so an `Adjustments::default()` is a
+/// no-op. Corrections run in a fixed order: white balance, levels
+/// (automatic, then manual), histogram equalization, brightness/contrast,
+/// gamma, and finally saturation and hue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Adjustments {
+    /// Added to every channel, as a fraction of full range (-1.0 to 1.0)
+    pub brightness: f32,
+    /// Contrast multiplier around mid gray (1.0 = unchanged)
+    pub contrast: f32,
+    /// Gamma correction; values above 1.0 brighten midtones
+    pub gamma: f32,
+    /// Saturation multiplier (0.0 = grayscale, 1.0 = unchanged)
+    pub saturation: f32,
+    /// Hue rotation in degrees
+    pub hue_rotate: f32,
+    /// Manual input black and white points
+    pub levels: Option<(u8, u8)>,
+    /// Stretch the luminance range to full scale, clipping outliers
+    pub auto_levels: bool,
+    /// Gray-world white balance
+    pub auto_white_balance: bool,
+    /// Luminance histogram equalization
+    pub equalize: bool,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            hue_rotate: 0.0,
+            levels: None,
+            auto_levels: false,
+            auto_white_balance: false,
+            equalize: false,
+        }
+    }
+}
+
+impl Adjustments {
+    /// True when applying these adjustments would not change the image
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+
+        let mut pixels: Vec<[f32; 4]> = rgba_img.pixels().map(|p| p.0.map(f32::from)).collect();
+
+        if self.auto_white_balance {
+            white_balance(&mut pixels);
+        }
+
+        if self.auto_levels {
+            if let Some((black, white)) = luma_percentiles(&pixels, AUTO_LEVELS_CLIP) {
+                stretch_levels(&mut pixels, black, white);
+            }
+        }
+
+        if let Some((black, white)) = self.levels {
+            stretch_levels(&mut pixels, f32::from(black), f32::from(white));
+        }
+
+        if self.equalize {
+            equalize_luma(&mut pixels);
+        }
+
+        let color_matrix = self.color_matrix();
+        for pixel in &mut pixels {
+            for c in pixel.iter_mut().take(3) {
+                *c = (*c - 128.0) * self.contrast + 128.0 + self.brightness * 255.0;
+                if self.gamma != 1.0 {
+                    *c = 255.0 * (c.clamp(0.0, 255.0) / 255.0).powf(1.0 / self.gamma);
+                }
+            }
+
+            if let Some(matrix) = &color_matrix {
+                let [r, g, b, _] = *pixel;
+                for (c, row) in matrix.iter().enumerate() {
+                    pixel[c] = row[0] * r + row[1] * g + row[2] * b;
+                }
+            }
+        }
+
+        let output: RgbaImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = pixels[(y * width + x) as usize];
+            Rgba(pixel.map(|v| v.round().clamp(0.0, 255.0) as u8))
+        });
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+
+    /// Combined saturation and hue-rotation matrix (the same luma-preserving
+    /// matrices CSS filters use), or `None` when both are neutral
+    fn color_matrix(&self) -> Option<[[f32; 3]; 3]> {
+        if self.saturation == 1.0 && self.hue_rotate == 0.0 {
+            return None;
+        }
+
+        let s = self.saturation;
+        let saturate = [
+            [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+        ];
+
+        let (sin, cos) = self.hue_rotate.to_radians().sin_cos();
+        let rotate = [
+            [
+                0.213 + cos * 0.787 - sin * 0.213,
+                0.715 - cos * 0.715 - sin * 0.715,
+                0.072 - cos * 0.072 + sin * 0.928,
+            ],
+            [
+                0.213 - cos * 0.213 + sin * 0.143,
+                0.715 + cos * 0.285 + sin * 0.140,
+                0.072 - cos * 0.072 - sin * 0.283,
+            ],
+            [
+                0.213 - cos * 0.213 - sin * 0.787,
+                0.715 - cos * 0.715 + sin * 0.715,
+                0.072 + cos * 0.928 + sin * 0.072,
+            ],
+        ];
+
+        let mut combined = [[0.0f32; 3]; 3];
+        for (i, row) in combined.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| rotate[i][k] * saturate[k][j]).sum();
+            }
+        }
+        Some(combined)
+    }
+}
+
+fn luma(pixel: &[f32; 4]) -> f32 {
+    0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]
+}
+
+/// Pixels that contribute to image statistics (fully transparent ones don't)
+fn visible(pixels: &[[f32; 4]]) -> impl Iterator<Item = &[f32; 4]> {
+    pixels.iter().filter(|p| p[3] > 0.0)
+}
+
+/// Gray-world assumption: scale each channel so its mean matches the
+/// overall mean
+fn white_balance(pixels: &mut [[f32; 4]]) {
+    let mut sums = [0.0f64; 3];
+    let mut count = 0usize;
+    for pixel in visible(pixels) {
+        for (c, sum) in sums.iter_mut().enumerate() {
+            *sum += f64::from(pixel[c]);
+        }
+        count += 1;
+    }
+
+    if count == 0 || sums.contains(&0.0) {
+        return;
+    }
+
+    let gray = (sums[0] + sums[1] + sums[2]) / 3.0;
+    let gains = sums.map(|s| (gray / s) as f32);
+    for pixel in pixels.iter_mut() {
+        for (c, gain) in gains.iter().enumerate() {
+            pixel[c] *= gain;
+        }
+    }
+}
+
+/// Luma values below which `clip` and above which `1 - clip` of the
+/// visible pixels fall
+fn luma_percentiles(pixels: &[[f32; 4]], clip: f32) -> Option<(f32, f32)> {
+    let histogram = luma_histogram(pixels);
+    let total: u32 = histogram.iter().sum();
+    if total == 0 {
+        return None;
+    }
+
+    let limit = (total as f32 * clip) as u32;
+    let mut seen = 0;
+    let black = histogram
+        .iter()
+        .position(|&n| {
+            seen += n;
+            seen > limit
+        })
+        .unwrap_or(0);
+    seen = 0;
+    let white = 255
+        - histogram
+            .iter()
+            .rev()
+            .position(|&n| {
+                seen += n;
+                seen > limit
+            })
+            .unwrap_or(0);
+
+    Some((black as f32, white as f32))
+}
+
+fn luma_histogram(pixels: &[[f32; 4]]) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for pixel in visible(pixels) {
+        histogram[luma(pixel).round().clamp(0.0, 255.0) as usize] += 1;
+    }
+    histogram
+}
+
+/// Map `black..white` onto the full `0..255` range
+fn stretch_levels(pixels: &mut [[f32; 4]], black: f32, white: f32) {
+    if white <= black {
+        return;
+    }
+
+    let scale = 255.0 / (white - black);
+    for pixel in pixels.iter_mut() {
+        for c in pixel.iter_mut().take(3) {
+            *c = (*c - black) * scale;
+        }
+    }
+}
+
+/// Equalize the luma histogram, shifting all channels by the same amount
+/// so hues are kept
+fn equalize_luma(pixels: &mut [[f32; 4]]) {
+    let histogram = luma_histogram(pixels);
+    let total: u32 = histogram.iter().sum();
+    let Some(first) = histogram.iter().position(|&n| n > 0) else {
+        return;
+    };
+
+    let first_count = histogram[first];
+    if total == first_count {
+        return;
+    }
+
+    let mut cdf = [0u32; 256];
+    let mut running = 0;
+    for (i, count) in histogram.iter().enumerate() {
+        running += count;
+        cdf[i] = running;
+    }
+
+    let mapping: Vec<f32> = cdf
+        .iter()
+        .map(|&c| 255.0 * c.saturating_sub(first_count) as f32 / (total - first_count) as f32)
+        .collect();
+
+    for pixel in pixels.iter_mut() {
+        let y = luma(pixel);
+        let shift = mapping[y.round().clamp(0.0, 255.0) as usize] - y;
+        for c in pixel.iter_mut().take(3) {
+            *c += shift;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn gradient(low: u8, high: u8) -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(16, 4, |x, _y| {
+            let v = low + ((high - low) as u32 * x / 15) as u8;
+            Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn luma_range(img: &DynamicImage) -> (u8, u8) {
+        let rgb = img.to_rgb8();
+        let values: Vec<u8> = rgb.pixels().map(|p| p[0]).collect();
+        (*values.iter().min().unwrap(), *values.iter().max().unwrap())
+    }
+
+    #[test]
+    fn test_default_is_identity() {
+        let adjustments = Adjustments::default();
+        assert!(adjustments.is_identity());
+
+        let img = gradient(10, 200);
+        assert_eq!(adjustments.apply(&img).to_rgb8(), img.to_rgb8());
+    }
+
+    #[test]
+    fn test_brightness_and_gamma_lighten() {
+        let img = gradient(40, 80);
+        let brighter = Adjustments {
+            brightness: 0.2,
+            ..Default::default()
+        };
+        assert!(luma_range(&brighter.apply(&img)).0 > 40);
+
+        let gamma = Adjustments {
+            gamma: 2.0,
+            ..Default::default()
+        };
+        assert!(luma_range(&gamma.apply(&img)).0 > 40);
+    }
+
+    #[test]
+    fn test_auto_levels_stretches_dark_image() {
+        let img = gradient(20, 70);
+        let adjustments = Adjustments {
+            auto_levels: true,
+            ..Default::default()
+        };
+
+        let (low, high) = luma_range(&adjustments.apply(&img));
+        assert!(low < 5);
+        assert!(high > 250);
+    }
+
+    #[test]
+    fn test_manual_levels() {
+        let img = gradient(50, 150);
+        let adjustments = Adjustments {
+            levels: Some((50, 150)),
+            ..Default::default()
+        };
+        assert_eq!(luma_range(&adjustments.apply(&img)), (0, 255));
+    }
+
+    #[test]
+    fn test_white_balance_removes_cast() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_fn(4, 4, |x, _y| {
+            let v = 60 + x as u8 * 20;
+            Rgb([v + 40, v, v.saturating_sub(20)])
+        }));
+        let adjustments = Adjustments {
+            auto_white_balance: true,
+            ..Default::default()
+        };
+
+        let balanced = adjustments.apply(&img).to_rgb8();
+        let means: Vec<f32> = (0..3)
+            .map(|c| balanced.pixels().map(|p| f32::from(p[c])).sum::<f32>() / 16.0)
+            .collect();
+        assert!((means[0] - means[2]).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_equalize_spreads_histogram() {
+        let img = gradient(100, 130);
+        let adjustments = Adjustments {
+            equalize: true,
+            ..Default::default()
+        };
+
+        let (low, high) = luma_range(&adjustments.apply(&img));
+        assert!(low < 20);
+        assert!(high > 240);
+    }
+
+    #[test]
+    fn test_saturation_and_hue() {
+        let red = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(2, 2, Rgb([200, 30, 30])));
+
+        let gray = Adjustments {
+            saturation: 0.0,
+            ..Default::default()
+        }
+        .apply(&red)
+        .to_rgb8();
+        let p = gray.get_pixel(0, 0);
+        assert!(p[0].abs_diff(p[1]) <= 1 && p[1].abs_diff(p[2]) <= 1);
+
+        let rotated = Adjustments {
+            hue_rotate: 120.0,
+            ..Default::default()
+        }
+        .apply(&red)
+        .to_rgb8();
+        let p = rotated.get_pixel(0, 0);
+        assert!(p[1] > p[0] && p[1] > p[2]);
+    }
+
+    #[test]
+    fn test_alpha_preserved_and_ignored_in_statistics() {
+        let img: RgbaImage = ImageBuffer::from_fn(4, 1, |x, _y| match x {
+            0 => Rgba([255, 255, 255, 0]),
+            1 => Rgba([20, 20, 20, 255]),
+            _ => Rgba([60, 60, 60, 255]),
+        });
+        let adjustments = Adjustments {
+            auto_levels: true,
+            ..Default::default()
+        };
+
+        let DynamicImage::ImageRgba8(result) = adjustments.apply(&DynamicImage::ImageRgba8(img))
+        else {
+            panic!("Expected RGBA output format");
+        };
+        assert_eq!(result.get_pixel(0, 0)[3], 0);
+        // The hidden white pixel must not count as the white point
+        assert_eq!(result.get_pixel(3, 0)[0], 255);
+    }
+}
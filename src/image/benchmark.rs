@@ -2,6 +2,7 @@ use crate::{Config, Palette};
 use image::DynamicImage;
 use std::time::{Duration, Instant};
 
+use super::pipeline::ProcessingDiagnostics;
 use super::processor::ImageProcessor;
 
 #[derive(Debug, Clone)]
@@ -13,6 +14,7 @@ pub struct BenchmarkResult {
     pub save_time: Duration,
     pub pixels_processed: u64,
     pub pixels_per_second: f64,
+    pub diagnostics: ProcessingDiagnostics,
 }
 
 impl BenchmarkResult {
@@ -26,11 +28,24 @@ impl BenchmarkResult {
         );
         println!("    - Effects:      {:.2}ms", self.effects_time.as_millis());
         println!("    - Save time:    {:.2}ms", self.save_time.as_millis());
+        if !self.diagnostics.stage_timings.is_empty() {
+            println!("  Stages:");
+            for (name, time) in &self.diagnostics.stage_timings {
+                println!(
+                    "    - {:<14}{:.2}ms",
+                    format!("{}:", name),
+                    time.as_secs_f64() * 1000.0
+                );
+            }
+        }
         println!(
             "  Pixels processed: {} ({:.1}K pixels/s)",
             self.pixels_processed,
             self.pixels_per_second / 1000.0
         );
+        if let Some(report) = &self.diagnostics.tone_remap {
+            println!("  Tone remap: {}", report);
+        }
     }
 
     pub fn is_performance_good(&self) -> bool {
@@ -69,8 +84,10 @@ impl ImageBenchmark {
         // Time the conversion process
         let conversion_start = Instant::now();
         let processor = ImageProcessor::new(self.config.clone());
-        let _processed_img = processor.process_image(img.clone(), palette)?;
-        let conversion_time = conversion_start.elapsed();
+        let (_processed_img, diagnostics) =
+            processor.process_image_with_diagnostics(img.clone(), palette)?;
+        let effects_time = diagnostics.effects_time();
+        let conversion_time = conversion_start.elapsed().saturating_sub(effects_time);
 
         let total_time = total_start.elapsed();
         let pixels_per_second = pixels_processed as f64 / total_time.as_secs_f64();
@@ -79,10 +96,11 @@ impl ImageBenchmark {
             total_time,
             load_time: Duration::from_millis(0), // Would need to measure separately
             conversion_time,
-            effects_time: Duration::from_millis(0), // Would need to measure separately
-            save_time: Duration::from_millis(0),    // Would need to measure separately
+            effects_time,
+            save_time: Duration::from_millis(0), // Would need to measure separately
             pixels_processed,
             pixels_per_second,
+            diagnostics,
         })
     }
 
@@ -104,8 +122,10 @@ impl ImageBenchmark {
         // Time processing
         let process_start = Instant::now();
         let processor = ImageProcessor::new(self.config.clone());
-        let processed_img = processor.process_image(img, palette)?;
-        let conversion_time = process_start.elapsed();
+        let (processed_img, diagnostics) =
+            processor.process_image_with_diagnostics(img, palette)?;
+        let effects_time = diagnostics.effects_time();
+        let conversion_time = process_start.elapsed().saturating_sub(effects_time);
 
         // Time saving
         let save_start = Instant::now();
@@ -119,10 +139,11 @@ impl ImageBenchmark {
             total_time,
             load_time,
             conversion_time,
-            effects_time: Duration::from_millis(0), // Effects timing included in conversion_time
+            effects_time,
             save_time,
             pixels_processed,
             pixels_per_second,
+            diagnostics,
         })
     }
 
@@ -197,6 +218,16 @@ impl OptimizationSuggestions {
             }
         }
 
+        if let Some(report) = &result.diagnostics.tone_remap {
+            let (palette_low, palette_high) = report.palette_range;
+            if palette_high - palette_low < 0.2 {
+                suggestions.push(
+                    "Palette covers a narrow lightness range - tone remapping will flatten detail; try a lower --tone-remap-strength"
+                        .to_string(),
+                );
+            }
+        }
+
         if !config.colors.is_empty() && config.colors.len() < 3 {
             suggestions.push(
                 "Using very few colors may not provide significant speed benefits".to_string(),
@@ -254,6 +285,7 @@ mod tests {
             pixels_area: None,
             quiet_mode: false,
             benchmark: false,
+            ..Default::default()
         }
     }
 
@@ -278,12 +310,41 @@ mod tests {
             save_time: Duration::from_millis(5),
             pixels_processed: 10000,
             pixels_per_second: 150_000.0, // Above the threshold for good performance
+            diagnostics: ProcessingDiagnostics::default(),
         };
 
         assert!(result.is_performance_good());
         assert_eq!(result.get_performance_grade(), "Good");
     }
 
+    #[test]
+    fn test_tone_remap_reported_in_benchmark() {
+        use crate::{Color, Palette};
+
+        let mut config = create_test_config();
+        config.tone_remap = true;
+        let benchmark = ImageBenchmark::new(config);
+        let palette = Palette {
+            name: "test".to_string(),
+            path: PathBuf::from("test"),
+            colors: vec![
+                Color {
+                    name: "dark".to_string(),
+                    hex: "#202020".to_string(),
+                },
+                Color {
+                    name: "light".to_string(),
+                    hex: "#A0A0A0".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let img = benchmark.create_test_image(20, 20).unwrap();
+        let result = benchmark.benchmark_processing(&img, &palette).unwrap();
+        assert!(result.diagnostics.tone_remap.is_some());
+    }
+
     #[test]
     fn test_optimization_suggestions() {
         let mut config = create_test_config();
@@ -298,6 +359,7 @@ mod tests {
             save_time: Duration::from_millis(5),
             pixels_processed: 10000,
             pixels_per_second: 50_000.0, // Fair performance
+            diagnostics: ProcessingDiagnostics::default(),
         };
 
         let suggestions = OptimizationSuggestions::analyze_config(&config, &result);
@@ -0,0 +1,103 @@
+use image::Rgb;
+
+/// Convert an 8-bit sRGB channel to linear light (0.0 to 1.0)
+pub fn srgb_to_linear(value: u8) -> f32 {
+    let v = f32::from(value) / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert linear light back to an 8-bit sRGB channel, clamping out-of-gamut values
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let encoded = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Color in the OKLab perceptual space. `l` is perceived lightness from
+/// 0.0 (black) to 1.0 (white); `a` and `b` are the green-red and blue-yellow
+/// opponent axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Oklab {
+    pub fn from_rgb(rgb: Rgb<u8>) -> Self {
+        let r = srgb_to_linear(rgb[0]);
+        let g = srgb_to_linear(rgb[1]);
+        let b = srgb_to_linear(rgb[2]);
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    pub fn to_rgb(self) -> Rgb<u8> {
+        let l = self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b;
+        let m = self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b;
+        let s = self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b;
+
+        let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+
+        Rgb([
+            linear_to_srgb(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            linear_to_srgb(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+            linear_to_srgb(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        ])
+    }
+
+    /// Euclidean distance in OKLab, a perceptual color difference
+    pub fn distance(&self, other: &Oklab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_linear_round_trip() {
+        for value in [0u8, 1, 10, 100, 128, 200, 255] {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+        }
+    }
+
+    #[test]
+    fn test_oklab_reference_values() {
+        let white = Oklab::from_rgb(Rgb([255, 255, 255]));
+        assert!((white.l - 1.0).abs() < 1e-3);
+        assert!(white.a.abs() < 1e-3 && white.b.abs() < 1e-3);
+
+        let black = Oklab::from_rgb(Rgb([0, 0, 0]));
+        assert!(black.l.abs() < 1e-6);
+
+        let red = Oklab::from_rgb(Rgb([255, 0, 0]));
+        assert!((red.l - 0.628).abs() < 1e-2);
+        assert!(red.a > 0.2);
+    }
+
+    #[test]
+    fn test_oklab_round_trip() {
+        for rgb in [Rgb([46, 52, 64]), Rgb([191, 97, 106]), Rgb([136, 192, 208])] {
+            assert_eq!(Oklab::from_rgb(rgb).to_rgb(), rgb);
+        }
+    }
+}
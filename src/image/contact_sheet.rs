@@ -0,0 +1,178 @@
+use crate::palette::{draw_text, GLYPH_HEIGHT};
+use crate::Palette;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgb, RgbImage};
+
+use super::processor::ImageProcessor;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const TEXT: Rgb<u8> = Rgb([30, 30, 30]);
+const MARGIN: u32 = 16;
+const GAP: u32 = 12;
+
+/// Converts one image with several palettes and lays the results out as a
+/// labelled grid. The input is decoded (and optionally downscaled) once and
+/// every tile goes through the same processor configuration.
+pub struct ContactSheet {
+    processor: ImageProcessor,
+    columns: Option<u32>,
+    tile_width: Option<u32>,
+}
+
+impl ContactSheet {
+    pub fn new(processor: ImageProcessor) -> Self {
+        Self {
+            processor,
+            columns: None,
+            tile_width: None,
+        }
+    }
+
+    /// Tiles per row; by default the grid is as square as possible
+    pub fn with_columns(mut self, columns: Option<u32>) -> Self {
+        self.columns = columns.map(|c| c.max(1));
+        self
+    }
+
+    /// Downscale the input to this width before converting, which is much
+    /// faster for large images. `None` converts at full resolution.
+    pub fn with_tile_width(mut self, width: Option<u32>) -> Self {
+        self.tile_width = width.map(|w| w.max(1));
+        self
+    }
+
+    fn columns_for(&self, count: u32) -> u32 {
+        self.columns
+            .unwrap_or_else(|| (f64::from(count)).sqrt().ceil() as u32)
+            .clamp(1, count.max(1))
+    }
+
+    /// The image every palette is applied to
+    fn tile_source(&self, img: &DynamicImage) -> DynamicImage {
+        match self.tile_width {
+            Some(width) if width < img.width() => {
+                let height = (u64::from(img.height()) * u64::from(width) / u64::from(img.width()))
+                    .max(1) as u32;
+                img.resize_exact(width, height, FilterType::Triangle)
+            }
+            _ => img.clone(),
+        }
+    }
+
+    pub fn render(&self, img: &DynamicImage, palettes: &[&Palette]) -> crate::Result<RgbImage> {
+        let source = self.tile_source(img);
+
+        let mut tiles = Vec::with_capacity(palettes.len());
+        for palette in palettes {
+            log::info!("Contact sheet: converting with '{}'", palette.name);
+            let tile = self.processor.process_image(source.clone(), palette)?;
+            tiles.push((palette.name.as_str(), tile.to_rgb8()));
+        }
+
+        Ok(self.compose(&tiles))
+    }
+
+    fn compose(&self, tiles: &[(&str, RgbImage)]) -> RgbImage {
+        let count = tiles.len() as u32;
+        let columns = self.columns_for(count);
+        let rows = (count.max(1) + columns - 1) / columns;
+
+        let cell_width = tiles.iter().map(|(_, t)| t.width()).max().unwrap_or(1);
+        let cell_height = tiles.iter().map(|(_, t)| t.height()).max().unwrap_or(1);
+        // Labels grow with the tiles so they stay legible on full-size images
+        let scale = (cell_width / 240).clamp(1, 8);
+        let label_height = (GLYPH_HEIGHT + 6) * scale;
+        let max_chars = (cell_width / (6 * scale)) as usize;
+
+        let width = 2 * MARGIN + columns * (cell_width + GAP) - GAP;
+        let height = 2 * MARGIN + rows * (cell_height + label_height + GAP) - GAP;
+        let mut sheet = RgbImage::from_pixel(width, height, BACKGROUND);
+
+        for (index, (name, tile)) in tiles.iter().enumerate() {
+            let index = index as u32;
+            let x = MARGIN + (index % columns) * (cell_width + GAP);
+            let y = MARGIN + (index / columns) * (cell_height + label_height + GAP);
+
+            imageops::replace(&mut sheet, tile, i64::from(x), i64::from(y));
+            draw_text(
+                &mut sheet,
+                x,
+                y + cell_height + 3 * scale,
+                name,
+                max_chars,
+                scale,
+                TEXT,
+            );
+        }
+
+        sheet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::BuiltinPalettes;
+    use crate::Config;
+    use image::RgbImage;
+
+    fn processor() -> ImageProcessor {
+        ImageProcessor::new(Config {
+            disable_avg_pixels: true,
+            ..Config::default()
+        })
+    }
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+        }))
+    }
+
+    #[test]
+    fn test_columns_default_to_square_grid() {
+        let sheet = ContactSheet::new(processor());
+        assert_eq!(sheet.columns_for(1), 1);
+        assert_eq!(sheet.columns_for(4), 2);
+        assert_eq!(sheet.columns_for(17), 5);
+        assert_eq!(sheet.with_columns(Some(10)).columns_for(3), 3);
+    }
+
+    #[test]
+    fn test_render_grid_with_downscaled_tiles() {
+        let nord = BuiltinPalettes::get_palette("nord").unwrap();
+        let dracula = BuiltinPalettes::get_palette("dracula").unwrap();
+        let gruvbox = BuiltinPalettes::get_palette("gruvbox").unwrap();
+
+        let sheet = ContactSheet::new(processor())
+            .with_tile_width(Some(40))
+            .render(&gradient(80, 60), &[&nord, &dracula, &gruvbox])
+            .unwrap();
+
+        // 2x2 grid of 40x30 tiles with a one-line label under each
+        let label = GLYPH_HEIGHT + 6;
+        assert_eq!(sheet.width(), 2 * MARGIN + 2 * 40 + GAP);
+        assert_eq!(sheet.height(), 2 * MARGIN + 2 * (30 + label) + GAP);
+
+        // Tiles only contain their own palette's colors
+        let nord_tile = imageops::crop_imm(&sheet, MARGIN, MARGIN, 40, 30).to_image();
+        let nord_colors: Vec<Rgb<u8>> = nord
+            .colors
+            .iter()
+            .map(|c| {
+                let (r, g, b) = crate::palette::hex_to_rgb(&c.hex).unwrap();
+                Rgb([r, g, b])
+            })
+            .collect();
+        assert!(nord_tile.pixels().all(|p| nord_colors.contains(p)));
+    }
+
+    #[test]
+    fn test_full_resolution_without_tile_width() {
+        let nord = BuiltinPalettes::get_palette("nord").unwrap();
+        let sheet = ContactSheet::new(processor())
+            .render(&gradient(30, 20), &[&nord])
+            .unwrap();
+        assert_eq!(sheet.width(), 2 * MARGIN + 30);
+    }
+}
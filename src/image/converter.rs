@@ -1,13 +1,117 @@
 use crate::Palette;
 use image::{DynamicImage, ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::mosaic::{grout_mask, CellShape, Mosaic};
 use super::{hex_to_rgb, rgb_distance};
 
+/// Error diffusion / threshold pattern used when snapping pixels to a palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dithering {
+    /// Plain nearest-color matching
+    #[default]
+    None,
+    /// Floyd-Steinberg error diffusion
+    FloydSteinberg,
+    /// 4x4 Bayer ordered dithering
+    Ordered,
+}
+
 pub trait PaletteConverter {
     fn convert_pixel(&self, rgb: Rgb<u8>) -> Rgb<u8>;
     fn convert_image(&self, img: &DynamicImage) -> DynamicImage;
     fn convert_image_with_alpha(&self, img: &DynamicImage) -> DynamicImage;
+
+    /// The colors this converter maps onto
+    fn palette_colors(&self) -> &[Rgb<u8>];
+
+    /// Snap every pixel to the palette individually (no block averaging),
+    /// spreading the quantization error according to `dithering`.
+    /// Alpha is preserved.
+    fn convert_image_dithered(&self, img: &DynamicImage, dithering: Dithering) -> DynamicImage {
+        dither_image(img, dithering, |rgb| self.convert_pixel(rgb))
+    }
+}
+
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// Amplitude of the ordered dither offsets, in 8-bit channel units
+const ORDERED_DITHER_SPREAD: f32 = 48.0;
+
+fn dither_image<F>(img: &DynamicImage, dithering: Dithering, snap: F) -> DynamicImage
+where
+    F: Fn(Rgb<u8>) -> Rgb<u8>,
+{
+    let mut rgba_img = img.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+
+    log::info!(
+        "Snapping {}x{} image to palette ({:?} dithering)",
+        width,
+        height,
+        dithering
+    );
+
+    // Pending diffused error for each pixel, only used by Floyd-Steinberg
+    let mut errors = match dithering {
+        Dithering::FloydSteinberg => vec![[0.0f32; 3]; (width * height) as usize],
+        _ => Vec::new(),
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = *rgba_img.get_pixel(x, y);
+            let offset = match dithering {
+                Dithering::None => [0.0; 3],
+                Dithering::FloydSteinberg => errors[(y * width + x) as usize],
+                Dithering::Ordered => {
+                    let threshold = (BAYER_4X4[(y % 4) as usize][(x % 4) as usize] + 0.5) / 16.0;
+                    [(threshold - 0.5) * ORDERED_DITHER_SPREAD; 3]
+                }
+            };
+
+            let wanted = [
+                f32::from(pixel[0]) + offset[0],
+                f32::from(pixel[1]) + offset[1],
+                f32::from(pixel[2]) + offset[2],
+            ];
+            let snapped = snap(Rgb([to_u8(wanted[0]), to_u8(wanted[1]), to_u8(wanted[2])]));
+            rgba_img.put_pixel(x, y, Rgba([snapped[0], snapped[1], snapped[2], pixel[3]]));
+
+            if dithering == Dithering::FloydSteinberg {
+                let error = [
+                    wanted[0] - f32::from(snapped[0]),
+                    wanted[1] - f32::from(snapped[1]),
+                    wanted[2] - f32::from(snapped[2]),
+                ];
+                let neighbours = [(1i64, 0i64, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
+                for (dx, dy, weight) in neighbours {
+                    let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+                    if nx < 0 || nx >= i64::from(width) || ny >= i64::from(height) {
+                        continue;
+                    }
+                    let pending = &mut errors[(ny as u32 * width + nx as u32) as usize];
+                    for c in 0..3 {
+                        pending[c] += error[c] * weight / 16.0;
+                    }
+                }
+            }
+        }
+    }
+
+    if img.color().has_alpha() {
+        DynamicImage::ImageRgba8(rgba_img)
+    } else {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba_img).to_rgb8())
+    }
 }
 
 pub struct NearestColorConverter {
@@ -52,17 +156,9 @@ impl NearestColorConverter {
             return Self::new(palette);
         }
 
-        for color_name in selected_colors {
-            if let Some(color) = palette.colors.iter().find(|c| c.name == *color_name) {
-                let rgb = hex_to_rgb(&color.hex)?;
-                palette_colors.push(rgb);
-            } else {
-                log::warn!(
-                    "Selected color '{}' not found in palette '{}'",
-                    color_name,
-                    palette.name
-                );
-            }
+        // Names, aliases and @groups
+        for color in palette.select_colors(selected_colors) {
+            palette_colors.push(hex_to_rgb(&color.hex)?);
         }
 
         if palette_colors.is_empty() {
@@ -108,6 +204,10 @@ impl NearestColorConverter {
 }
 
 impl PaletteConverter for NearestColorConverter {
+    fn palette_colors(&self) -> &[Rgb<u8>] {
+        &self.palette_colors
+    }
+
     fn convert_pixel(&self, rgb: Rgb<u8>) -> Rgb<u8> {
         // Use mutable reference to self through interior mutability pattern
         // For now, we'll compute without caching to avoid mutability issues
@@ -161,6 +261,8 @@ pub struct OptimizedConverter {
     converter: NearestColorConverter,
     pixel_area: (u32, u32),
     use_averaging: bool,
+    mosaic: Option<Mosaic>,
+    grout: Option<(Rgb<u8>, u32)>,
 }
 
 impl OptimizedConverter {
@@ -182,9 +284,97 @@ impl OptimizedConverter {
             converter,
             pixel_area,
             use_averaging,
+            mosaic: None,
+            grout: None,
         })
     }
 
+    /// Average over mosaic cells instead of rectangular `pixel_area` blocks
+    pub fn with_mosaic(mut self, mosaic: Mosaic) -> Self {
+        self.mosaic = Some(mosaic);
+        self
+    }
+
+    /// Draw `width` pixel lines in `color` between cells
+    pub fn with_grout(mut self, color: Rgb<u8>, width: u32) -> Self {
+        self.grout = Some((color, width.max(1)));
+        self
+    }
+
+    fn uses_cells(&self) -> bool {
+        let shaped = self
+            .mosaic
+            .as_ref()
+            .is_some_and(|mosaic| mosaic.shape() != CellShape::Square);
+        shaped || self.grout.is_some()
+    }
+
+    /// Average each mosaic cell, weighting colors by alpha so transparent
+    /// pixels don't darken their cell, and fill it with the nearest palette
+    /// color
+    fn convert_with_cells(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        let mosaic = self
+            .mosaic
+            .clone()
+            .unwrap_or_else(|| Mosaic::new(CellShape::Square, self.pixel_area));
+
+        log::info!(
+            "Converting {}x{} image with {:?} mosaic cells",
+            width,
+            height,
+            mosaic.shape()
+        );
+
+        let (cells, cell_count) = mosaic.cells(width, height);
+
+        // Alpha-weighted color sums, alpha sum and pixel count per cell
+        let mut totals = vec![([0u64; 3], 0u64, 0u64); cell_count];
+        for (pixel, &cell) in rgba_img.pixels().zip(&cells) {
+            let alpha = u64::from(pixel[3]);
+            let total = &mut totals[cell];
+            for c in 0..3 {
+                total.0[c] += u64::from(pixel[c]) * alpha;
+            }
+            total.1 += alpha;
+            total.2 += 1;
+        }
+
+        let colors: Vec<Rgba<u8>> = totals
+            .iter()
+            .map(|(sums, alpha, count)| {
+                if *alpha == 0 {
+                    return Rgba([0, 0, 0, 0]);
+                }
+                let rgb = self.convert_pixel(Rgb(sums.map(|sum| (sum / alpha) as u8)));
+                Rgba([rgb[0], rgb[1], rgb[2], (alpha / count) as u8])
+            })
+            .collect();
+
+        let grout = self
+            .grout
+            .map(|(color, grout_width)| (color, grout_mask(&cells, width, height, grout_width)));
+
+        let output_img: RgbaImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize;
+            let color = colors[cells[index]];
+            match &grout {
+                Some((grout_color, mask)) if mask[index] => {
+                    Rgba([grout_color[0], grout_color[1], grout_color[2], color[3]])
+                }
+                _ => color,
+            }
+        });
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output_img)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output_img).to_rgb8())
+        }
+    }
+
+    #[allow(clippy::manual_checked_ops)]
     fn average_pixels(&self, img: &RgbImage, start_x: u32, start_y: u32) -> Rgb<u8> {
         let (width, height) = img.dimensions();
         let (area_w, area_h) = self.pixel_area;
@@ -281,6 +471,7 @@ impl OptimizedConverter {
         DynamicImage::ImageRgba8(output_img)
     }
 
+    #[allow(clippy::manual_checked_ops)]
     fn average_rgba_pixels(&self, img: &RgbaImage, start_x: u32, start_y: u32) -> Rgba<u8> {
         let (width, height) = img.dimensions();
         let (area_w, area_h) = self.pixel_area;
@@ -316,11 +507,19 @@ impl OptimizedConverter {
 }
 
 impl PaletteConverter for OptimizedConverter {
+    fn palette_colors(&self) -> &[Rgb<u8>] {
+        self.converter.palette_colors()
+    }
+
     fn convert_pixel(&self, rgb: Rgb<u8>) -> Rgb<u8> {
         self.converter.convert_pixel(rgb)
     }
 
     fn convert_image(&self, img: &DynamicImage) -> DynamicImage {
+        if self.uses_cells() {
+            return self.convert_with_cells(img);
+        }
+
         if !self.use_averaging || self.pixel_area == (1, 1) {
             return self.converter.convert_image(img);
         }
@@ -337,6 +536,9 @@ impl PaletteConverter for OptimizedConverter {
     }
 
     fn convert_image_with_alpha(&self, img: &DynamicImage) -> DynamicImage {
+        if self.uses_cells() {
+            return DynamicImage::ImageRgba8(self.convert_with_cells(img).to_rgba8());
+        }
         self.converter.convert_image_with_alpha(img)
     }
 }
@@ -365,6 +567,7 @@ mod tests {
                     hex: "#0000FF".to_string(),
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -406,6 +609,39 @@ mod tests {
         assert!(result == Rgb([255, 0, 0]) || result == Rgb([0, 0, 255]));
     }
 
+    #[test]
+    fn test_selected_groups_and_aliases() {
+        let mut palette = create_test_palette();
+        palette.groups.insert(
+            "primary".to_string(),
+            vec!["Red".to_string(), "Blue".to_string()],
+        );
+        palette
+            .aliases
+            .insert("leaf".to_string(), "Green".to_string());
+
+        // Overlapping selectors only add each color once
+        let selected = vec![
+            "@primary".to_string(),
+            "LEAF".to_string(),
+            "red".to_string(),
+        ];
+        let converter =
+            NearestColorConverter::new_with_selected_colors(&palette, &selected).unwrap();
+        assert_eq!(
+            converter.palette_colors,
+            vec![Rgb([255, 0, 0]), Rgb([0, 0, 255]), Rgb([0, 255, 0])]
+        );
+
+        let nord = crate::palette::BuiltinPalettes::get_palette("nord").unwrap();
+        let frost = vec!["@frost".to_string()];
+        let converter = NearestColorConverter::new_with_selected_colors(&nord, &frost).unwrap();
+        assert_eq!(converter.palette_colors.len(), 4);
+
+        let unknown = vec!["@nope".to_string()];
+        assert!(NearestColorConverter::new_with_selected_colors(&palette, &unknown).is_err());
+    }
+
     #[test]
     fn test_empty_selected_colors() {
         let palette = create_test_palette();
@@ -414,6 +650,123 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn palette_rgb(palette: &Palette) -> Vec<Rgb<u8>> {
+        palette
+            .colors
+            .iter()
+            .map(|c| hex_to_rgb(&c.hex).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_dithered_output_stays_in_palette() {
+        let palette = create_test_palette();
+        let converter = NearestColorConverter::new(&palette).unwrap();
+        let allowed = palette_rgb(&palette);
+
+        let gradient: RgbImage =
+            ImageBuffer::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+        let img = DynamicImage::ImageRgb8(gradient);
+
+        for dithering in [
+            Dithering::None,
+            Dithering::FloydSteinberg,
+            Dithering::Ordered,
+        ] {
+            let result = converter.convert_image_dithered(&img, dithering).to_rgb8();
+            assert!(result.pixels().all(|p| allowed.contains(p)));
+        }
+    }
+
+    #[test]
+    fn test_floyd_steinberg_mixes_colors() {
+        let palette = create_test_palette();
+        let selected = vec!["Red".to_string(), "Blue".to_string()];
+        let converter =
+            NearestColorConverter::new_with_selected_colors(&palette, &selected).unwrap();
+
+        // Purple halfway between red and blue should dither into both
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(8, 8, Rgb([128, 0, 128])));
+        let result = converter
+            .convert_image_dithered(&img, Dithering::FloydSteinberg)
+            .to_rgb8();
+
+        let reds = result.pixels().filter(|p| **p == Rgb([255, 0, 0])).count();
+        assert!(reds > 16 && reds < 48);
+    }
+
+    #[test]
+    fn test_dithering_preserves_alpha() {
+        let palette = create_test_palette();
+        let converter = NearestColorConverter::new(&palette).unwrap();
+        let img = DynamicImage::ImageRgba8(ImageBuffer::from_pixel(4, 4, Rgba([200, 10, 10, 90])));
+
+        let result = converter.convert_image_dithered(&img, Dithering::Ordered);
+        match result {
+            DynamicImage::ImageRgba8(ref rgba_result) => {
+                assert!(rgba_result.pixels().all(|p| p[3] == 90));
+            }
+            _ => panic!("Expected RGBA output format"),
+        }
+    }
+
+    #[test]
+    fn test_mosaic_cells_are_flat() {
+        let palette = create_test_palette();
+        let img: RgbImage =
+            ImageBuffer::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 64]));
+        let img = DynamicImage::ImageRgb8(img);
+
+        let mosaic = Mosaic::new(CellShape::Hexagon, (8, 8));
+        let (cells, _) = mosaic.cells(32, 32);
+        let converter = OptimizedConverter::new(&palette, &[], None, true)
+            .unwrap()
+            .with_mosaic(mosaic);
+        let result = converter.convert_image(&img).to_rgb8();
+
+        let mut cell_colors = HashMap::new();
+        for (pixel, cell) in result.pixels().zip(cells) {
+            assert_eq!(*cell_colors.entry(cell).or_insert(*pixel), *pixel);
+        }
+    }
+
+    #[test]
+    fn test_grout_lines() {
+        let palette = create_test_palette();
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(8, 8, Rgb([250, 10, 10])));
+
+        let converter = OptimizedConverter::new(&palette, &[], Some((4, 4)), true)
+            .unwrap()
+            .with_grout(Rgb([0, 0, 255]), 1);
+        let result = converter.convert_image(&img).to_rgb8();
+
+        assert_eq!(*result.get_pixel(3, 0), Rgb([0, 0, 255]));
+        assert_eq!(*result.get_pixel(0, 3), Rgb([0, 0, 255]));
+        assert_eq!(*result.get_pixel(1, 1), Rgb([255, 0, 0]));
+        assert_eq!(*result.get_pixel(7, 7), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_mosaic_ignores_transparent_pixels() {
+        let palette = create_test_palette();
+        let img: RgbaImage = ImageBuffer::from_fn(8, 8, |x, _y| {
+            if x % 2 == 0 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([0, 250, 0, 255])
+            }
+        });
+
+        let converter = OptimizedConverter::new(&palette, &[], Some((8, 8)), true)
+            .unwrap()
+            .with_mosaic(Mosaic::new(CellShape::Voronoi, (8, 8)));
+        let result = converter
+            .convert_image(&DynamicImage::ImageRgba8(img))
+            .to_rgba8();
+
+        assert!(result.pixels().all(|p| p[1] == 255 && p[0] == 0));
+    }
+
     #[test]
     fn test_transparency_preservation() {
         use image::{ImageBuffer, Rgba};
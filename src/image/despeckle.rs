@@ -0,0 +1,377 @@
+use image::{DynamicImage, ImageBuffer, Rgb, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use super::rgb_distance;
+
+/// Cleanup strategy used by [`Despeckle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DespeckleMode {
+    /// Merge connected regions smaller than the minimum size into the
+    /// neighbour they share the longest border with
+    #[default]
+    Merge,
+    /// Replace each pixel with the most common palette color around it
+    Majority,
+}
+
+/// Removes isolated specks from a palette-mapped image.
+///
+/// Works on palette indices rather than colors, so the output only ever
+/// contains palette colors. Pixels that aren't exact palette colors are
+/// snapped to the nearest one first; fully transparent pixels are left
+/// alone and never count as neighbours.
+pub struct Despeckle {
+    min_region: u32,
+    radius: u32,
+    mode: DespeckleMode,
+}
+
+impl Despeckle {
+    /// Merge regions smaller than `min_region` pixels
+    pub fn new(min_region: u32) -> Self {
+        Self {
+            min_region,
+            radius: 1,
+            mode: DespeckleMode::Merge,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: DespeckleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Majority filter window radius (1 = 3x3 window)
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = radius.max(1);
+        self
+    }
+
+    pub fn apply(&self, img: &DynamicImage, palette_colors: &[Rgb<u8>]) -> DynamicImage {
+        if palette_colors.is_empty() {
+            return img.clone();
+        }
+
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        let mut index_map = IndexMap::from_image(&rgba_img, palette_colors);
+
+        let changed = match self.mode {
+            DespeckleMode::Merge => index_map.merge_small_regions(self.min_region, palette_colors),
+            DespeckleMode::Majority => index_map.majority_filter(self.radius),
+        };
+        log::info!("Despeckle ({:?}) changed {} pixels", self.mode, changed);
+
+        let output: RgbaImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let alpha = rgba_img.get_pixel(x, y)[3];
+            match index_map.get(x, y) {
+                Some(index) => {
+                    let rgb = palette_colors[index];
+                    Rgba([rgb[0], rgb[1], rgb[2], alpha])
+                }
+                None => *rgba_img.get_pixel(x, y),
+            }
+        });
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+}
+
+/// Palette index per pixel; `None` marks fully transparent pixels
+struct IndexMap {
+    width: u32,
+    height: u32,
+    indices: Vec<Option<usize>>,
+}
+
+impl IndexMap {
+    fn from_image(img: &RgbaImage, palette_colors: &[Rgb<u8>]) -> Self {
+        let mut lookup: HashMap<[u8; 3], usize> = HashMap::new();
+        for (index, color) in palette_colors.iter().enumerate() {
+            lookup.entry(color.0).or_insert(index);
+        }
+
+        let indices = img
+            .pixels()
+            .map(|p| {
+                if p[3] == 0 {
+                    return None;
+                }
+                let rgb = [p[0], p[1], p[2]];
+                let index = *lookup
+                    .entry(rgb)
+                    .or_insert_with(|| nearest_index(&Rgb(rgb), palette_colors));
+                Some(index)
+            })
+            .collect();
+
+        Self {
+            width: img.width(),
+            height: img.height(),
+            indices,
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> Option<usize> {
+        self.indices[(y * self.width + x) as usize]
+    }
+
+    /// 4-connected neighbours of a flat pixel position
+    fn neighbors(&self, pos: usize) -> impl Iterator<Item = usize> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let (x, y) = (pos % width, pos / width);
+
+        [
+            (x > 0).then(|| pos - 1),
+            (x + 1 < width).then(|| pos + 1),
+            (y > 0).then(|| pos - width),
+            (y + 1 < height).then(|| pos + width),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Label 4-connected regions of equal index, returning each region's
+    /// pixel positions in ascending order
+    fn regions(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.indices.len()];
+        let mut regions = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for start in 0..self.indices.len() {
+            let Some(index) = self.indices[start] else {
+                continue;
+            };
+            if visited[start] {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            visited[start] = true;
+            queue.push_back(start);
+            while let Some(pos) = queue.pop_front() {
+                region.push(pos);
+                for neighbor in self.neighbors(pos) {
+                    if !visited[neighbor] && self.indices[neighbor] == Some(index) {
+                        visited[neighbor] = true;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+            region.sort_unstable();
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    /// Recolor every region smaller than `min_region` with the index it
+    /// shares the most border pixels with. Smallest regions go first, so a
+    /// cluster of specks settles into the surrounding color.
+    fn merge_small_regions(&mut self, min_region: u32, palette_colors: &[Rgb<u8>]) -> usize {
+        let mut small: Vec<Vec<usize>> = self
+            .regions()
+            .into_iter()
+            .filter(|region| region.len() < min_region as usize)
+            .collect();
+        small.sort_by_key(|region| region.len());
+
+        let mut changed = 0;
+        let mut border_counts: HashMap<usize, usize> = HashMap::new();
+        for region in small {
+            let Some(current) = self.indices[region[0]] else {
+                continue;
+            };
+
+            // An earlier merge may have joined this region to a larger one
+            // of the same color, in which case it's no longer a speck
+            let mut grown = false;
+            border_counts.clear();
+            for &pos in &region {
+                for neighbor in self.neighbors(pos) {
+                    match self.indices[neighbor] {
+                        Some(index) if index != current => {
+                            *border_counts.entry(index).or_insert(0) += 1;
+                        }
+                        Some(_) => grown |= region.binary_search(&neighbor).is_err(),
+                        None => {}
+                    }
+                }
+            }
+            if grown {
+                continue;
+            }
+
+            // Ties go to the neighbour closest in color
+            let target = border_counts.iter().max_by(|(a, count_a), (b, count_b)| {
+                count_a.cmp(count_b).then_with(|| {
+                    let distance_a = rgb_distance(&palette_colors[current], &palette_colors[**a]);
+                    let distance_b = rgb_distance(&palette_colors[current], &palette_colors[**b]);
+                    distance_b.total_cmp(&distance_a)
+                })
+            });
+
+            if let Some((&target, _)) = target {
+                for &pos in &region {
+                    self.indices[pos] = Some(target);
+                }
+                changed += region.len();
+            }
+        }
+
+        changed
+    }
+
+    /// Mode filter over a square window; ties keep the current index
+    fn majority_filter(&mut self, radius: u32) -> usize {
+        let source = self.indices.clone();
+        let radius = i64::from(radius);
+        let (width, height) = (i64::from(self.width), i64::from(self.height));
+
+        let mut changed = 0;
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for y in 0..height {
+            for x in 0..width {
+                let pos = (y * width + x) as usize;
+                let Some(current) = source[pos] else {
+                    continue;
+                };
+
+                counts.clear();
+                for ny in (y - radius).max(0)..=(y + radius).min(height - 1) {
+                    for nx in (x - radius).max(0)..=(x + radius).min(width - 1) {
+                        if let Some(index) = source[(ny * width + nx) as usize] {
+                            *counts.entry(index).or_insert(0) += 1;
+                        }
+                    }
+                }
+
+                let current_count = counts[&current];
+                let winner = counts
+                    .iter()
+                    .filter(|&(_, &count)| count > current_count)
+                    .max_by_key(|&(&index, &count)| (count, std::cmp::Reverse(index)));
+
+                if let Some((&index, _)) = winner {
+                    self.indices[pos] = Some(index);
+                    changed += 1;
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+fn nearest_index(target: &Rgb<u8>, palette_colors: &[Rgb<u8>]) -> usize {
+    palette_colors
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| rgb_distance(target, a).total_cmp(&rgb_distance(target, b)))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbImage};
+
+    const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+    const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+    const RED: Rgb<u8> = Rgb([255, 0, 0]);
+
+    fn palette() -> Vec<Rgb<u8>> {
+        vec![BLACK, WHITE, RED]
+    }
+
+    /// White background with a 1px red speck, a 2px black speck and a
+    /// 4x4 black block
+    fn speckled_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(12, 12, |x, y| match (x, y) {
+            (2, 2) => RED,
+            (5, 2) | (6, 2) => BLACK,
+            (6..=9, 6..=9) => BLACK,
+            _ => WHITE,
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_merge_removes_small_regions() {
+        let result = Despeckle::new(4)
+            .apply(&speckled_image(), &palette())
+            .to_rgb8();
+
+        assert_eq!(*result.get_pixel(2, 2), WHITE);
+        assert_eq!(*result.get_pixel(5, 2), WHITE);
+        // Regions at or above the threshold survive
+        assert_eq!(*result.get_pixel(7, 7), BLACK);
+    }
+
+    #[test]
+    fn test_majority_filter() {
+        let result = Despeckle::new(4)
+            .with_mode(DespeckleMode::Majority)
+            .apply(&speckled_image(), &palette())
+            .to_rgb8();
+
+        assert_eq!(*result.get_pixel(2, 2), WHITE);
+        assert_eq!(*result.get_pixel(7, 7), BLACK);
+    }
+
+    #[test]
+    fn test_output_stays_in_palette() {
+        // Off-palette input is snapped before cleaning
+        let img: RgbImage = ImageBuffer::from_fn(8, 8, |x, y| {
+            let v = ((x * 37 + y * 11) % 256) as u8;
+            Rgb([v, v / 2, 255 - v])
+        });
+        let img = DynamicImage::ImageRgb8(img);
+
+        for mode in [DespeckleMode::Merge, DespeckleMode::Majority] {
+            let result = Despeckle::new(3).with_mode(mode).apply(&img, &palette());
+            assert!(result.to_rgb8().pixels().all(|p| palette().contains(p)));
+        }
+    }
+
+    #[test]
+    fn test_transparent_pixels_untouched() {
+        let mut img = RgbaImage::from_pixel(5, 5, Rgba([255, 255, 255, 255]));
+        img.put_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        img.put_pixel(0, 0, Rgba([12, 34, 56, 0]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let result = Despeckle::new(2).apply(&img, &palette()).to_rgba8();
+        assert_eq!(*result.get_pixel(2, 2), Rgba([255, 255, 255, 255]));
+        assert_eq!(*result.get_pixel(0, 0), Rgba([12, 34, 56, 0]));
+    }
+
+    #[test]
+    fn test_region_without_neighbours_is_kept() {
+        let mut img = RgbaImage::from_pixel(3, 3, Rgba([0, 0, 0, 0]));
+        img.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let result = Despeckle::new(4).apply(&img, &palette()).to_rgba8();
+        assert_eq!(*result.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_degenerate_images() {
+        for (w, h) in [(0, 0), (1, 1), (1, 5), (5, 1)] {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, RED));
+            for mode in [DespeckleMode::Merge, DespeckleMode::Majority] {
+                let result = Despeckle::new(4).with_mode(mode).apply(&img, &palette());
+                assert_eq!(result.dimensions(), (w, h));
+            }
+        }
+    }
+}
@@ -1,15 +1,86 @@
-use image::{DynamicImage, ImageBuffer, Rgb, RgbImage};
+use image::{DynamicImage, ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
 
+use super::MAX_RADIUS;
+
+/// How neighbourhood filters sample pixels that fall outside the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BorderMode {
+    /// Repeat the nearest edge pixel
+    #[default]
+    Clamp,
+    /// Reflect the image at its edges (edge pixel included)
+    Mirror,
+    /// Tile the image
+    Wrap,
+    /// Treat outside pixels as transparent black
+    Constant,
+}
+
+impl BorderMode {
+    /// Map a possibly out-of-range coordinate onto `0..len`.
+    ///
+    /// Returns `None` when the sample lies outside and should take the
+    /// constant border value, or when the axis is empty.
+    pub fn resolve(self, index: i64, len: u32) -> Option<u32> {
+        let len = i64::from(len);
+        if len == 0 {
+            return None;
+        }
+        if (0..len).contains(&index) {
+            return Some(index as u32);
+        }
+
+        let resolved = match self {
+            BorderMode::Clamp => index.clamp(0, len - 1),
+            BorderMode::Mirror => {
+                let period = 2 * len;
+                let folded = index.rem_euclid(period);
+                if folded < len {
+                    folded
+                } else {
+                    period - 1 - folded
+                }
+            }
+            BorderMode::Wrap => index.rem_euclid(len),
+            BorderMode::Constant => return None,
+        };
+        Some(resolved as u32)
+    }
+}
+
+/// Blur algorithm used by [`apply_blur`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlurKind {
+    /// Separable Gaussian kernel
+    #[default]
+    Gaussian,
+    /// Running-sum box filter
+    Box,
+    /// Stack blur (triangular kernel), close to Gaussian at box-blur cost
+    Stack,
+}
+
+#[derive(Debug, Clone)]
 pub struct BlurConfig {
     pub sigma: f32,
     pub radius: u32,
+    pub kind: BlurKind,
+    pub border: BorderMode,
 }
 
 impl BlurConfig {
     pub fn new(sigma: f32) -> Self {
         // Calculate radius from sigma (typically 3*sigma is sufficient)
-        let radius = (sigma * 3.0).ceil() as u32;
-        Self { sigma, radius }
+        let radius = radius_for_sigma(BlurKind::Gaussian, sigma);
+        Self {
+            sigma,
+            radius,
+            kind: BlurKind::Gaussian,
+            border: BorderMode::Clamp,
+        }
     }
 
     pub fn light() -> Self {
@@ -23,6 +94,25 @@ impl BlurConfig {
     pub fn heavy() -> Self {
         Self::new(2.0)
     }
+
+    /// Switch the blur algorithm, picking the radius whose kernel has the
+    /// same standard deviation as the configured sigma
+    pub fn with_kind(mut self, kind: BlurKind) -> Self {
+        self.kind = kind;
+        self.radius = radius_for_sigma(kind, self.sigma);
+        self
+    }
+
+    /// Override the kernel radius derived from sigma
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = radius.min(MAX_RADIUS);
+        self
+    }
+
+    pub fn with_border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
 }
 
 impl Default for BlurConfig {
@@ -31,84 +121,227 @@ impl Default for BlurConfig {
     }
 }
 
+/// Kernel radius matching `sigma`, capped at [`MAX_RADIUS`]
+fn radius_for_sigma(kind: BlurKind, sigma: f32) -> u32 {
+    let variance = sigma * sigma;
+    let radius = match kind {
+        BlurKind::Gaussian => (sigma * 3.0).ceil() as u32,
+        // Box of radius r has variance r(r+1)/3
+        BlurKind::Box => (((1.0 + 12.0 * variance).sqrt() - 1.0) / 2.0)
+            .round()
+            .max(1.0) as u32,
+        // Triangular kernel of radius r has variance r(r+2)/6
+        BlurKind::Stack => ((1.0 + 6.0 * variance).sqrt() - 1.0).round().max(1.0) as u32,
+    };
+    radius.min(MAX_RADIUS)
+}
+
 #[allow(dead_code)]
 pub fn apply_gaussian_blur(img: &DynamicImage, config: &BlurConfig) -> DynamicImage {
     // Use the built-in blur for now, but we can implement custom Gaussian if needed
     img.blur(config.sigma)
 }
 
-#[allow(dead_code)]
-pub fn apply_custom_gaussian_blur(img: &DynamicImage, config: &BlurConfig) -> DynamicImage {
-    let rgb_img = img.to_rgb8();
-    let (width, height) = rgb_img.dimensions();
+/// Blur an image with the algorithm selected in `config`
+pub fn apply_blur(img: &DynamicImage, config: &BlurConfig) -> DynamicImage {
+    match config.kind {
+        BlurKind::Gaussian => apply_custom_gaussian_blur(img, config),
+        BlurKind::Box => apply_box_blur(img, config.radius, config.border),
+        BlurKind::Stack => apply_stack_blur(img, config.radius, config.border),
+    }
+}
 
-    // Generate Gaussian kernel
+pub fn apply_custom_gaussian_blur(img: &DynamicImage, config: &BlurConfig) -> DynamicImage {
     let kernel = generate_gaussian_kernel(config.sigma, config.radius);
-    let kernel_size = (2 * config.radius + 1) as i32;
+    blur_separable(img, |src, dst| {
+        convolve_line(src, dst, &kernel, config.border)
+    })
+}
+
+pub fn apply_box_blur(img: &DynamicImage, radius: u32, border: BorderMode) -> DynamicImage {
+    blur_separable(img, |src, dst| {
+        box_blur_line(src, dst, radius as usize, border)
+    })
+}
+
+pub fn apply_stack_blur(img: &DynamicImage, radius: u32, border: BorderMode) -> DynamicImage {
+    blur_separable(img, |src, dst| {
+        stack_blur_line(src, dst, radius as usize, border)
+    })
+}
+
+/// Run a 1D line filter horizontally, then vertically, over every channel.
+///
+/// Images with an alpha channel are blurred in premultiplied space so that
+/// transparent pixels don't bleed their (invisible) color into neighbours.
+fn blur_separable<F>(img: &DynamicImage, line_filter: F) -> DynamicImage
+where
+    F: Fn(&[f32], &mut [f32]),
+{
+    let has_alpha = img.color().has_alpha();
+    let (width, height) = (img.width() as usize, img.height() as usize);
+
+    let mut planes = to_planes(img, has_alpha);
+    filter_planes(&mut planes, width, height, line_filter);
+    from_planes(&planes, width as u32, height as u32, has_alpha)
+}
+
+/// Run a 1D line filter horizontally, then vertically, over row-major planes
+fn filter_planes<F>(planes: &mut [Vec<f32>], width: usize, height: usize, line_filter: F)
+where
+    F: Fn(&[f32], &mut [f32]),
+{
+    if width == 0 || height == 0 {
+        return;
+    }
 
-    let mut output_img: RgbImage = ImageBuffer::new(width, height);
+    let mut src = vec![0.0f32; width.max(height)];
+    let mut dst = vec![0.0f32; width.max(height)];
+
+    for plane in planes.iter_mut() {
+        for y in 0..height {
+            let row = &mut plane[y * width..(y + 1) * width];
+            src[..width].copy_from_slice(row);
+            line_filter(&src[..width], &mut dst[..width]);
+            row.copy_from_slice(&dst[..width]);
+        }
 
-    for y in 0..height {
         for x in 0..width {
-            let mut r_sum = 0.0f32;
-            let mut g_sum = 0.0f32;
-            let mut b_sum = 0.0f32;
-            let mut weight_sum = 0.0f32;
-
-            for ky in 0..kernel_size {
-                for kx in 0..kernel_size {
-                    let px = x as i32 + kx - config.radius as i32;
-                    let py = y as i32 + ky - config.radius as i32;
-
-                    if px >= 0 && px < width as i32 && py >= 0 && py < height as i32 {
-                        let pixel = rgb_img.get_pixel(px as u32, py as u32);
-                        let weight = kernel[(ky * kernel_size + kx) as usize];
-
-                        r_sum += f32::from(pixel[0]) * weight;
-                        g_sum += f32::from(pixel[1]) * weight;
-                        b_sum += f32::from(pixel[2]) * weight;
-                        weight_sum += weight;
-                    }
-                }
+            for y in 0..height {
+                src[y] = plane[y * width + x];
             }
+            line_filter(&src[..height], &mut dst[..height]);
+            for y in 0..height {
+                plane[y * width + x] = dst[y];
+            }
+        }
+    }
+}
 
-            if weight_sum > 0.0 {
-                let r = (r_sum / weight_sum).round().clamp(0.0, 255.0) as u8;
-                let g = (g_sum / weight_sum).round().clamp(0.0, 255.0) as u8;
-                let b = (b_sum / weight_sum).round().clamp(0.0, 255.0) as u8;
-
-                output_img.put_pixel(x, y, Rgb([r, g, b]));
+/// Split an image into per-channel f32 planes (premultiplied when `has_alpha`)
+fn to_planes(img: &DynamicImage, has_alpha: bool) -> Vec<Vec<f32>> {
+    if has_alpha {
+        let rgba = img.to_rgba8();
+        let len = rgba.width() as usize * rgba.height() as usize;
+        let mut planes: Vec<Vec<f32>> = (0..4).map(|_| Vec::with_capacity(len)).collect();
+        for pixel in rgba.pixels() {
+            let alpha = f32::from(pixel[3]) / 255.0;
+            for c in 0..3 {
+                planes[c].push(f32::from(pixel[c]) * alpha);
+            }
+            planes[3].push(f32::from(pixel[3]));
+        }
+        planes
+    } else {
+        let rgb = img.to_rgb8();
+        let len = rgb.width() as usize * rgb.height() as usize;
+        let mut planes: Vec<Vec<f32>> = (0..3).map(|_| Vec::with_capacity(len)).collect();
+        for pixel in rgb.pixels() {
+            for c in 0..3 {
+                planes[c].push(f32::from(pixel[c]));
             }
         }
+        planes
     }
+}
+
+fn from_planes(planes: &[Vec<f32>], width: u32, height: u32, has_alpha: bool) -> DynamicImage {
+    let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
 
-    DynamicImage::ImageRgb8(output_img)
+    if has_alpha {
+        let img = ImageBuffer::from_fn(width, height, |x, y| {
+            let i = (y * width + x) as usize;
+            let alpha = planes[3][i];
+            let unpremultiply = |v: f32| if alpha > 0.0 { v * 255.0 / alpha } else { 0.0 };
+            Rgba([
+                to_u8(unpremultiply(planes[0][i])),
+                to_u8(unpremultiply(planes[1][i])),
+                to_u8(unpremultiply(planes[2][i])),
+                to_u8(alpha),
+            ])
+        });
+        DynamicImage::ImageRgba8(img)
+    } else {
+        let img: RgbImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let i = (y * width + x) as usize;
+            Rgb([
+                to_u8(planes[0][i]),
+                to_u8(planes[1][i]),
+                to_u8(planes[2][i]),
+            ])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
 }
 
-#[allow(dead_code)]
+/// Sample a line, resolving out-of-range indices with the border mode.
+/// Constant borders read as zero (transparent black in premultiplied space).
+fn sample(line: &[f32], index: isize, border: BorderMode) -> f32 {
+    border
+        .resolve(index as i64, line.len() as u32)
+        .map_or(0.0, |i| line[i as usize])
+}
+
+fn convolve_line(src: &[f32], dst: &mut [f32], kernel: &[f32], border: BorderMode) {
+    let radius = (kernel.len() / 2) as isize;
+    for (i, out) in dst.iter_mut().enumerate() {
+        *out = kernel
+            .iter()
+            .enumerate()
+            .map(|(k, weight)| sample(src, i as isize + k as isize - radius, border) * weight)
+            .sum();
+    }
+}
+
+fn box_blur_line(src: &[f32], dst: &mut [f32], radius: usize, border: BorderMode) {
+    let r = radius as isize;
+    let norm = 1.0 / (2 * radius + 1) as f32;
+    let mut sum: f32 = (-r..=r).map(|k| sample(src, k, border)).sum();
+
+    for (i, out) in dst.iter_mut().enumerate() {
+        *out = sum * norm;
+        let i = i as isize;
+        sum += sample(src, i + r + 1, border) - sample(src, i - r, border);
+    }
+}
+
+/// Stack blur keeps two running sums: the pixels entering the kernel on the
+/// right (`sum_in`) and the pixels on the left half including the centre
+/// (`sum_out`). Stepping the triangular kernel by one adds `sum_in` and
+/// removes `sum_out` from the weighted total.
+fn stack_blur_line(src: &[f32], dst: &mut [f32], radius: usize, border: BorderMode) {
+    let r = radius as isize;
+    let norm = 1.0 / ((radius + 1) * (radius + 1)) as f32;
+
+    let mut total: f32 = (-r..=r)
+        .map(|k| sample(src, k, border) * (r + 1 - k.abs()) as f32)
+        .sum();
+    let mut sum_in: f32 = (1..=r + 1).map(|k| sample(src, k, border)).sum();
+    let mut sum_out: f32 = (-r..=0).map(|k| sample(src, k, border)).sum();
+
+    for (i, out) in dst.iter_mut().enumerate() {
+        *out = total * norm;
+        let i = i as isize;
+        total += sum_in - sum_out;
+        sum_in += sample(src, i + r + 2, border) - sample(src, i + 1, border);
+        sum_out += sample(src, i + 1, border) - sample(src, i - r, border);
+    }
+}
+
+/// Normalized 1D Gaussian kernel of length `2 * radius + 1`
 fn generate_gaussian_kernel(sigma: f32, radius: u32) -> Vec<f32> {
     let size = (2 * radius + 1) as usize;
-    let mut kernel = Vec::with_capacity(size * size);
-    let sigma_sq = sigma * sigma;
-    let two_sigma_sq = 2.0 * sigma_sq;
-    let norm_factor = 1.0 / (std::f32::consts::PI * two_sigma_sq);
-
+    let mut kernel = Vec::with_capacity(size);
+    let two_sigma_sq = (2.0 * sigma * sigma).max(f32::EPSILON);
     let center = radius as i32;
-    let mut sum = 0.0f32;
 
-    for y in 0..size {
-        for x in 0..size {
-            let dx = x as i32 - center;
-            let dy = y as i32 - center;
-            let distance_sq = (dx * dx + dy * dy) as f32;
-
-            let weight = norm_factor * (-distance_sq / two_sigma_sq).exp();
-            kernel.push(weight);
-            sum += weight;
-        }
+    for x in 0..size {
+        let dx = (x as i32 - center) as f32;
+        kernel.push((-dx * dx / two_sigma_sq).exp());
     }
 
     // Normalize kernel so weights sum to 1
+    let sum: f32 = kernel.iter().sum();
     for weight in &mut kernel {
         *weight /= sum;
     }
@@ -116,9 +349,23 @@ fn generate_gaussian_kernel(sigma: f32, radius: u32) -> Vec<f32> {
     kernel
 }
 
+/// Neighbourhood filter used by [`NoiseReduction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DenoiseMode {
+    /// Average only the neighbours within the color threshold
+    #[default]
+    Selective,
+    /// Per-channel median of the neighbourhood, good for salt-and-pepper noise
+    Median,
+}
+
 pub struct NoiseReduction {
     threshold: f32,
     strength: f32,
+    radius: u32,
+    mode: DenoiseMode,
+    border: BorderMode,
 }
 
 impl NoiseReduction {
@@ -126,81 +373,751 @@ impl NoiseReduction {
         Self {
             threshold,
             strength,
+            radius: 1,
+            mode: DenoiseMode::Selective,
+            border: BorderMode::Clamp,
         }
     }
 
+    pub fn with_mode(mut self, mode: DenoiseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Neighbourhood radius (1 = 3x3 window)
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = radius.max(1);
+        self
+    }
+
+    pub fn with_border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// Filter the image, keeping its alpha channel untouched.
+    ///
+    /// Fully transparent neighbours are ignored so hidden colors don't leak
+    /// into visible pixels; with [`BorderMode::Constant`] the window is
+    /// effectively cropped at the image edges.
     pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
-        let rgb_img = img.to_rgb8();
-        let (width, height) = rgb_img.dimensions();
-        let mut output_img: RgbImage = ImageBuffer::new(width, height);
-
-        for y in 1..height - 1 {
-            for x in 1..width - 1 {
-                let center_pixel = rgb_img.get_pixel(x, y);
-                let mut r_sum = f32::from(center_pixel[0]);
-                let mut g_sum = f32::from(center_pixel[1]);
-                let mut b_sum = f32::from(center_pixel[2]);
-                let mut count = 1;
-
-                // Check surrounding pixels
-                for dy in -1i32..=1 {
-                    for dx in -1i32..=1 {
-                        if dx == 0 && dy == 0 {
-                            continue;
-                        }
-
-                        let px = (x as i32 + dx) as u32;
-                        let py = (y as i32 + dy) as u32;
-                        let neighbor = rgb_img.get_pixel(px, py);
-
-                        let diff = color_difference(center_pixel, neighbor);
-                        if diff < self.threshold {
-                            r_sum += f32::from(neighbor[0]);
-                            g_sum += f32::from(neighbor[1]);
-                            b_sum += f32::from(neighbor[2]);
-                            count += 1;
-                        }
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        let mut output_img: RgbaImage = ImageBuffer::new(width, height);
+        let radius = self.radius as i64;
+
+        let mut window: Vec<Rgb<u8>> = Vec::with_capacity(((2 * radius + 1).pow(2)) as usize);
+
+        for (x, y, pixel) in rgba_img.enumerate_pixels() {
+            let center_pixel = Rgb([pixel[0], pixel[1], pixel[2]]);
+
+            window.clear();
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+
+                    let Some(neighbor) =
+                        sample_pixel(&rgba_img, i64::from(x) + dx, i64::from(y) + dy, self.border)
+                    else {
+                        continue;
+                    };
+                    if neighbor[3] > 0 {
+                        window.push(Rgb([neighbor[0], neighbor[1], neighbor[2]]));
                     }
                 }
+            }
 
-                if count > 1 {
-                    let avg_r = r_sum / count as f32;
-                    let avg_g = g_sum / count as f32;
-                    let avg_b = b_sum / count as f32;
-
-                    let final_r = lerp(f32::from(center_pixel[0]), avg_r, self.strength);
-                    let final_g = lerp(f32::from(center_pixel[1]), avg_g, self.strength);
-                    let final_b = lerp(f32::from(center_pixel[2]), avg_b, self.strength);
-
-                    output_img.put_pixel(
-                        x,
-                        y,
-                        Rgb([
-                            final_r.round() as u8,
-                            final_g.round() as u8,
-                            final_b.round() as u8,
-                        ]),
-                    );
-                } else {
-                    output_img.put_pixel(x, y, *center_pixel);
+            let filtered = match self.mode {
+                DenoiseMode::Selective => self.selective_average(&center_pixel, &window),
+                DenoiseMode::Median => {
+                    window.push(center_pixel);
+                    Some(channel_median(&mut window))
+                }
+            };
+
+            let final_pixel = match filtered {
+                Some(target) => {
+                    let blend = |c: usize| {
+                        lerp(f32::from(center_pixel[c]), target[c], self.strength)
+                            .round()
+                            .clamp(0.0, 255.0) as u8
+                    };
+                    Rgba([blend(0), blend(1), blend(2), pixel[3]])
+                }
+                None => *pixel,
+            };
+            output_img.put_pixel(x, y, final_pixel);
+        }
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output_img)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output_img).to_rgb8())
+        }
+    }
+
+    /// Mean of the center pixel and every neighbour closer than the
+    /// threshold, or `None` when no neighbour qualifies
+    fn selective_average(&self, center_pixel: &Rgb<u8>, neighbors: &[Rgb<u8>]) -> Option<[f32; 3]> {
+        let mut sums = [
+            f32::from(center_pixel[0]),
+            f32::from(center_pixel[1]),
+            f32::from(center_pixel[2]),
+        ];
+        let mut count = 1;
+
+        for neighbor in neighbors {
+            if color_difference(center_pixel, neighbor) < self.threshold {
+                for (c, sum) in sums.iter_mut().enumerate() {
+                    *sum += f32::from(neighbor[c]);
                 }
+                count += 1;
             }
         }
 
-        // Copy border pixels
-        for y in 0..height {
-            if y == 0 || y == height - 1 {
-                for x in 0..width {
-                    output_img.put_pixel(x, y, *rgb_img.get_pixel(x, y));
+        if count > 1 {
+            Some(sums.map(|sum| sum / count as f32))
+        } else {
+            None
+        }
+    }
+}
+
+/// Variant of the [`Kuwahara`] filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KuwaharaKind {
+    /// Four square quadrants; takes the mean of the least varied one
+    #[default]
+    Classic,
+    /// Eight sectors of an ellipse aligned with the local edge direction,
+    /// blended by their variance
+    Anisotropic,
+}
+
+/// Sectors used by the anisotropic Kuwahara filter
+const KUWAHARA_SECTORS: usize = 8;
+
+/// Gaussian sigma used to smooth the structure tensor
+const STRUCTURE_TENSOR_SIGMA: f32 = 2.0;
+
+/// Edge-preserving smoothing that flattens regions into painterly patches
+/// while keeping boundaries sharp. Alpha is preserved and fully transparent
+/// pixels are ignored.
+pub struct Kuwahara {
+    radius: u32,
+    kind: KuwaharaKind,
+    sharpness: f32,
+    border: BorderMode,
+}
+
+impl Kuwahara {
+    pub fn new(radius: u32) -> Self {
+        Self {
+            radius: radius.max(1),
+            kind: KuwaharaKind::Classic,
+            sharpness: 8.0,
+            border: BorderMode::Clamp,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: KuwaharaKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// How strongly the anisotropic variant favours low-variance sectors;
+    /// higher values give crisper edges
+    pub fn with_sharpness(mut self, sharpness: f32) -> Self {
+        self.sharpness = sharpness.max(0.0);
+        self
+    }
+
+    pub fn with_border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+
+        let output: RgbaImage = match self.kind {
+            KuwaharaKind::Classic => {
+                ImageBuffer::from_fn(width, height, |x, y| self.classic_pixel(&rgba_img, x, y))
+            }
+            KuwaharaKind::Anisotropic => {
+                let tensor = StructureTensor::new(&rgba_img, self.border);
+                ImageBuffer::from_fn(width, height, |x, y| {
+                    self.anisotropic_pixel(&rgba_img, &tensor, x, y)
+                })
+            }
+        };
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+
+    fn classic_pixel(&self, img: &RgbaImage, x: u32, y: u32) -> Rgba<u8> {
+        let pixel = *img.get_pixel(x, y);
+        if pixel[3] == 0 {
+            return pixel;
+        }
+
+        let r = i64::from(self.radius);
+        let mut best: Option<(f32, [f32; 3])> = None;
+
+        for (sx, sy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let mut stats = ColorStats::default();
+            for dy in 0..=r {
+                for dx in 0..=r {
+                    let px = i64::from(x) + sx * dx;
+                    let py = i64::from(y) + sy * dy;
+                    if let Some(sample) = visible_sample(img, px, py, self.border) {
+                        stats.add(sample, 1.0);
+                    }
                 }
-            } else {
-                output_img.put_pixel(0, y, *rgb_img.get_pixel(0, y));
-                output_img.put_pixel(width - 1, y, *rgb_img.get_pixel(width - 1, y));
             }
+
+            if let Some((mean, variance)) = stats.finish() {
+                if best.map_or(true, |(lowest, _)| variance < lowest) {
+                    best = Some((variance, mean));
+                }
+            }
+        }
+
+        match best {
+            Some((_, mean)) => with_alpha(mean, pixel[3]),
+            None => pixel,
+        }
+    }
+
+    fn anisotropic_pixel(
+        &self,
+        img: &RgbaImage,
+        tensor: &StructureTensor,
+        x: u32,
+        y: u32,
+    ) -> Rgba<u8> {
+        let pixel = *img.get_pixel(x, y);
+        if pixel[3] == 0 {
+            return pixel;
+        }
+
+        // Stretch the window along edges and squeeze it across them
+        let (angle, anisotropy) = tensor.orientation(x, y);
+        let radius = self.radius as f32;
+        let major = radius * (1.0 + anisotropy);
+        let minor = radius / (1.0 + anisotropy);
+        let (sin, cos) = angle.sin_cos();
+        let reach = major.ceil() as i64;
+
+        let mut sectors = [ColorStats::default(); KUWAHARA_SECTORS];
+        let sector_angle = std::f32::consts::TAU / KUWAHARA_SECTORS as f32;
+
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let (fx, fy) = (dx as f32, dy as f32);
+                let u = (fx * cos + fy * sin) / major;
+                let v = (-fx * sin + fy * cos) / minor;
+                let distance_sq = u * u + v * v;
+                if distance_sq > 1.0 {
+                    continue;
+                }
+
+                let Some(sample) =
+                    visible_sample(img, i64::from(x) + dx, i64::from(y) + dy, self.border)
+                else {
+                    continue;
+                };
+
+                let weight = (-2.0 * distance_sq).exp();
+                if dx == 0 && dy == 0 {
+                    for sector in &mut sectors {
+                        sector.add(sample, weight);
+                    }
+                    continue;
+                }
+
+                let theta = v.atan2(u) + std::f32::consts::PI;
+                let index = ((theta / sector_angle) as usize).min(KUWAHARA_SECTORS - 1);
+                sectors[index].add(sample, weight);
+            }
+        }
+
+        let mut blended = [0.0f32; 3];
+        let mut total_weight = 0.0;
+        for (mean, variance) in sectors.iter().filter_map(ColorStats::finish) {
+            let weight = 1.0 / (1.0 + (variance / 255.0).powf(self.sharpness / 2.0));
+            for (out, value) in blended.iter_mut().zip(mean) {
+                *out += value * weight;
+            }
+            total_weight += weight;
+        }
+
+        if total_weight > 0.0 {
+            with_alpha(blended.map(|v| v / total_weight), pixel[3])
+        } else {
+            pixel
+        }
+    }
+}
+
+/// Weighted running mean and variance of RGB samples
+#[derive(Debug, Clone, Copy, Default)]
+struct ColorStats {
+    sum: [f32; 3],
+    sum_sq: [f32; 3],
+    weight: f32,
+}
+
+impl ColorStats {
+    fn add(&mut self, sample: Rgba<u8>, weight: f32) {
+        for c in 0..3 {
+            let value = f32::from(sample[c]);
+            self.sum[c] += value * weight;
+            self.sum_sq[c] += value * value * weight;
+        }
+        self.weight += weight;
+    }
+
+    /// Mean color and the variance summed over channels
+    fn finish(&self) -> Option<([f32; 3], f32)> {
+        if self.weight <= 0.0 {
+            return None;
+        }
+
+        let mean = self.sum.map(|sum| sum / self.weight);
+        let variance = (0..3)
+            .map(|c| (self.sum_sq[c] / self.weight - mean[c] * mean[c]).max(0.0))
+            .sum();
+        Some((mean, variance))
+    }
+}
+
+/// Smoothed structure tensor of image luminance, used to find the local
+/// edge direction
+struct StructureTensor {
+    width: u32,
+    /// (E, F, G) = smoothed (gx*gx, gx*gy, gy*gy)
+    components: [Vec<f32>; 3],
+}
+
+impl StructureTensor {
+    fn new(img: &RgbaImage, border: BorderMode) -> Self {
+        let (width, height) = img.dimensions();
+
+        let len = width as usize * height as usize;
+        let mut components = [vec![0.0; len], vec![0.0; len], vec![0.0; len]];
+        for y in 0..i64::from(height) {
+            for x in 0..i64::from(width) {
+                let (gx, gy) = sobel_gradient(img, x, y, border);
+                let (gx, gy) = (gx / 8.0, gy / 8.0);
+
+                let i = (y * i64::from(width) + x) as usize;
+                components[0][i] = gx * gx;
+                components[1][i] = gx * gy;
+                components[2][i] = gy * gy;
+            }
+        }
+
+        let kernel = generate_gaussian_kernel(
+            STRUCTURE_TENSOR_SIGMA,
+            radius_for_sigma(BlurKind::Gaussian, STRUCTURE_TENSOR_SIGMA),
+        );
+        filter_planes(
+            &mut components,
+            width as usize,
+            height as usize,
+            |src, dst| convolve_line(src, dst, &kernel, border),
+        );
+
+        Self { width, components }
+    }
+
+    /// Angle of the edge tangent and anisotropy (0 = flat, 1 = strong edge)
+    fn orientation(&self, x: u32, y: u32) -> (f32, f32) {
+        let i = (y * self.width + x) as usize;
+        let [e, f, g] = [
+            self.components[0][i],
+            self.components[1][i],
+            self.components[2][i],
+        ];
+
+        let root = ((e - g) * (e - g) + 4.0 * f * f).sqrt();
+        let lambda1 = (e + g + root) / 2.0;
+        let lambda2 = (e + g - root) / 2.0;
+
+        let (tx, ty) = (lambda1 - e, -f);
+        let angle = if tx.abs() + ty.abs() > f32::EPSILON {
+            ty.atan2(tx)
+        } else {
+            std::f32::consts::FRAC_PI_2
+        };
+        let anisotropy = if lambda1 + lambda2 > f32::EPSILON {
+            (lambda1 - lambda2) / (lambda1 + lambda2)
+        } else {
+            0.0
+        };
+
+        (angle, anisotropy)
+    }
+}
+
+/// Edge-preserving blur that averages only neighbours that are both close
+/// and similar in color. Alpha is preserved and fully transparent pixels
+/// are ignored.
+pub struct BilateralFilter {
+    sigma_space: f32,
+    sigma_color: f32,
+    radius: u32,
+    border: BorderMode,
+}
+
+impl BilateralFilter {
+    /// `sigma_space` is in pixels, `sigma_color` in 8-bit RGB distance
+    pub fn new(sigma_space: f32, sigma_color: f32) -> Self {
+        let sigma_space = sigma_space.max(0.1);
+        Self {
+            sigma_space,
+            sigma_color: sigma_color.max(0.1),
+            radius: radius_for_sigma(BlurKind::Gaussian, sigma_space),
+            border: BorderMode::Clamp,
+        }
+    }
+
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = radius.clamp(1, MAX_RADIUS);
+        self
+    }
+
+    pub fn with_border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        let radius = i64::from(self.radius);
+        let side = (2 * radius + 1) as usize;
+
+        let space_denominator = 2.0 * self.sigma_space * self.sigma_space;
+        let color_denominator = 2.0 * self.sigma_color * self.sigma_color;
+        // Color weights indexed by squared RGB distance, which is an integer
+        let color_weights: Vec<f32> = (0..=3 * 255 * 255)
+            .map(|distance_sq| (-(distance_sq as f32) / color_denominator).exp())
+            .collect();
+        let space_weights: Vec<f32> = (-radius..=radius)
+            .flat_map(|dy| (-radius..=radius).map(move |dx| (dx * dx + dy * dy) as f32))
+            .map(|distance_sq| (-distance_sq / space_denominator).exp())
+            .collect();
+
+        let output: RgbaImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = *rgba_img.get_pixel(x, y);
+            if pixel[3] == 0 {
+                return pixel;
+            }
+            let mut sums = [0.0f32; 3];
+            let mut total_weight = 0.0;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let Some(sample) = visible_sample(
+                        &rgba_img,
+                        i64::from(x) + dx,
+                        i64::from(y) + dy,
+                        self.border,
+                    ) else {
+                        continue;
+                    };
+
+                    let distance_sq: usize = (0..3)
+                        .map(|c| (i32::from(pixel[c]) - i32::from(sample[c])).pow(2) as usize)
+                        .sum();
+                    let weight = space_weights
+                        [(dy + radius) as usize * side + (dx + radius) as usize]
+                        * color_weights[distance_sq];
+
+                    for (c, sum) in sums.iter_mut().enumerate() {
+                        *sum += f32::from(sample[c]) * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+
+            with_alpha(sums.map(|sum| sum / total_weight), pixel[3])
+        });
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+}
+
+/// Display hardware simulated by [`RetroDisplay`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RetroEffect {
+    /// Dark gaps between CRT scanlines
+    #[default]
+    Scanlines,
+    /// Aperture-grille stripes of red, green and blue phosphors
+    RgbMask,
+    /// Curved CRT glass that bulges the picture and darkens the corners
+    Barrel,
+    /// Glow bleeding out of bright areas
+    Bloom,
+    /// Visible gaps between the cells of a handheld LCD
+    LcdGrid,
+}
+
+/// Luminance above which pixels contribute to bloom
+const BLOOM_THRESHOLD: f32 = 0.6;
+
+/// CRT and LCD look applied after palette conversion. The output leaves the
+/// palette (scanlines darken, bloom brightens); re-snap afterwards to keep
+/// only palette colors. Alpha is preserved.
+pub struct RetroDisplay {
+    effect: RetroEffect,
+    strength: f32,
+    size: u32,
+}
+
+impl RetroDisplay {
+    pub fn new(effect: RetroEffect) -> Self {
+        Self {
+            effect,
+            strength: 0.5,
+            size: 3,
         }
+    }
 
-        DynamicImage::ImageRgb8(output_img)
+    /// Effect intensity from 0.0 (no change) to 1.0
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength.clamp(0.0, 1.0);
+        self
     }
+
+    /// Scanline period, mask triad width, LCD cell size or bloom radius in
+    /// pixels
+    pub fn with_size(mut self, size: u32) -> Self {
+        self.size = size.max(2);
+        self
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba_img = img.to_rgba8();
+
+        log::info!(
+            "Applying {:?} display effect (strength {}, size {})",
+            self.effect,
+            self.strength,
+            self.size
+        );
+
+        let output = match self.effect {
+            RetroEffect::Scanlines => self.scanlines(&rgba_img),
+            RetroEffect::RgbMask => self.rgb_mask(&rgba_img),
+            RetroEffect::Barrel => self.barrel(&rgba_img),
+            RetroEffect::Bloom => self.bloom(&rgba_img),
+            RetroEffect::LcdGrid => self.lcd_grid(&rgba_img),
+        };
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+
+    /// Scale colors by a per-pixel factor for each channel
+    fn modulate<F>(img: &RgbaImage, factors: F) -> RgbaImage
+    where
+        F: Fn(u32, u32) -> [f32; 3],
+    {
+        ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+            let pixel = img.get_pixel(x, y);
+            let factor = factors(x, y);
+            with_alpha([0, 1, 2].map(|c| f32::from(pixel[c]) * factor[c]), pixel[3])
+        })
+    }
+
+    fn scanlines(&self, img: &RgbaImage) -> RgbaImage {
+        // The lower part of every period is the gap between lines
+        let bright_rows = (self.size + 1) / 2;
+        let dim = 1.0 - self.strength;
+        Self::modulate(img, |_x, y| {
+            if y % self.size < bright_rows {
+                [1.0; 3]
+            } else {
+                [dim; 3]
+            }
+        })
+    }
+
+    fn rgb_mask(&self, img: &RgbaImage) -> RgbaImage {
+        let dim = 1.0 - self.strength;
+        Self::modulate(img, |x, _y| {
+            // Split each triad into red, green and blue stripes
+            let phosphor = (x % self.size * 3 / self.size) as usize;
+            let mut factor = [dim; 3];
+            factor[phosphor] = 1.0;
+            factor
+        })
+    }
+
+    fn barrel(&self, img: &RgbaImage) -> RgbaImage {
+        let (width, height) = img.dimensions();
+        let (half_w, half_h) = (width as f32 / 2.0, height as f32 / 2.0);
+        let curvature = 0.25 * self.strength;
+
+        ImageBuffer::from_fn(width, height, |x, y| {
+            // Normalized position from the center, -1.0 to 1.0
+            let nx = (x as f32 + 0.5 - half_w) / half_w;
+            let ny = (y as f32 + 0.5 - half_h) / half_h;
+            let scale = 1.0 + curvature * (nx * nx + ny * ny);
+            let (sx, sy) = (nx * scale, ny * scale);
+
+            if sx.abs() > 1.0 || sy.abs() > 1.0 {
+                return Rgba([0, 0, 0, 0]);
+            }
+            bilinear_sample(img, sx * half_w + half_w - 0.5, sy * half_h + half_h - 0.5)
+        })
+    }
+
+    fn bloom(&self, rgba_img: &RgbaImage) -> RgbaImage {
+        let bright: RgbaImage =
+            ImageBuffer::from_fn(rgba_img.width(), rgba_img.height(), |x, y| {
+                let pixel = rgba_img.get_pixel(x, y);
+                let luma = (0.2126 * f32::from(pixel[0])
+                    + 0.7152 * f32::from(pixel[1])
+                    + 0.0722 * f32::from(pixel[2]))
+                    / 255.0
+                    * (f32::from(pixel[3]) / 255.0);
+                if luma > BLOOM_THRESHOLD {
+                    *pixel
+                } else {
+                    Rgba([0, 0, 0, pixel[3]])
+                }
+            });
+
+        let sigma = self.size as f32;
+        let glow = apply_blur(
+            &DynamicImage::ImageRgba8(bright),
+            &BlurConfig::new(sigma).with_kind(BlurKind::Stack),
+        )
+        .to_rgba8();
+
+        ImageBuffer::from_fn(rgba_img.width(), rgba_img.height(), |x, y| {
+            let pixel = rgba_img.get_pixel(x, y);
+            let light = glow.get_pixel(x, y);
+            // Screen blend the glow over the image
+            let rgb = [0, 1, 2].map(|c| {
+                let base = f32::from(pixel[c]) / 255.0;
+                let add = f32::from(light[c]) / 255.0 * self.strength;
+                (1.0 - (1.0 - base) * (1.0 - add)) * 255.0
+            });
+            with_alpha(rgb, pixel[3])
+        })
+    }
+
+    fn lcd_grid(&self, img: &RgbaImage) -> RgbaImage {
+        let dim = 1.0 - self.strength;
+        Self::modulate(img, |x, y| {
+            if x % self.size == self.size - 1 || y % self.size == self.size - 1 {
+                [dim; 3]
+            } else {
+                [1.0; 3]
+            }
+        })
+    }
+}
+
+/// Bilinearly interpolated pixel at a fractional position, clamped to the
+/// image
+fn bilinear_sample(img: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (width, height) = img.dimensions();
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+    let corners = [
+        img.get_pixel(x0, y0),
+        img.get_pixel(x1, y0),
+        img.get_pixel(x0, y1),
+        img.get_pixel(x1, y1),
+    ];
+    let channel = |c: usize| {
+        let top = lerp(f32::from(corners[0][c]), f32::from(corners[1][c]), tx);
+        let bottom = lerp(f32::from(corners[2][c]), f32::from(corners[3][c]), tx);
+        lerp(top, bottom, ty).round().clamp(0.0, 255.0) as u8
+    };
+    Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+/// Sample a pixel for neighbourhood statistics, skipping samples outside a
+/// constant border and fully transparent ones
+fn visible_sample(img: &RgbaImage, x: i64, y: i64, border: BorderMode) -> Option<Rgba<u8>> {
+    sample_pixel(img, x, y, border).filter(|p| p[3] > 0)
+}
+
+fn with_alpha(rgb: [f32; 3], alpha: u8) -> Rgba<u8> {
+    let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    Rgba([to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2]), alpha])
+}
+
+/// Fetch a pixel at a possibly out-of-range position, or `None` for
+/// constant borders
+pub(crate) fn sample_pixel(
+    img: &RgbaImage,
+    x: i64,
+    y: i64,
+    border: BorderMode,
+) -> Option<Rgba<u8>> {
+    let (width, height) = img.dimensions();
+    let px = border.resolve(x, width)?;
+    let py = border.resolve(y, height)?;
+    Some(*img.get_pixel(px, py))
+}
+
+/// Alpha-premultiplied luminance at a possibly out-of-range position
+fn premultiplied_luma(img: &RgbaImage, x: i64, y: i64, border: BorderMode) -> f32 {
+    sample_pixel(img, x, y, border).map_or(0.0, |p| {
+        let alpha = f32::from(p[3]) / 255.0;
+        (0.299 * f32::from(p[0]) + 0.587 * f32::from(p[1]) + 0.114 * f32::from(p[2])) * alpha
+    })
+}
+
+/// Unnormalized Sobel gradient (gx, gy) of the premultiplied luminance
+pub(crate) fn sobel_gradient(img: &RgbaImage, x: i64, y: i64, border: BorderMode) -> (f32, f32) {
+    let l = |dx: i64, dy: i64| premultiplied_luma(img, x + dx, y + dy, border);
+
+    let gx = l(1, -1) + 2.0 * l(1, 0) + l(1, 1) - l(-1, -1) - 2.0 * l(-1, 0) - l(-1, 1);
+    let gy = l(-1, 1) + 2.0 * l(0, 1) + l(1, 1) - l(-1, -1) - 2.0 * l(0, -1) - l(1, -1);
+    (gx, gy)
+}
+
+fn channel_median(window: &mut [Rgb<u8>]) -> [f32; 3] {
+    let mut median = [0.0; 3];
+    let mut channel: Vec<u8> = Vec::with_capacity(window.len());
+
+    for (c, value) in median.iter_mut().enumerate() {
+        channel.clear();
+        channel.extend(window.iter().map(|p| p[c]));
+        channel.sort_unstable();
+        *value = f32::from(channel[channel.len() / 2]);
+    }
+
+    median
 }
 
 fn color_difference(c1: &Rgb<u8>, c2: &Rgb<u8>) -> f32 {
@@ -217,7 +1134,7 @@ fn lerp(a: f32, b: f32, t: f32) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use image::{ImageBuffer, Rgb};
+    use image::GenericImageView;
 
     fn create_test_image() -> DynamicImage {
         let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_fn(10, 10, |x, y| {
@@ -243,10 +1160,21 @@ mod tests {
         assert!(moderate.sigma < heavy.sigma);
     }
 
+    #[test]
+    fn test_blur_radius_is_capped() {
+        for kind in [BlurKind::Gaussian, BlurKind::Box, BlurKind::Stack] {
+            assert_eq!(BlurConfig::new(1e9).with_kind(kind).radius, MAX_RADIUS);
+        }
+        assert_eq!(
+            BlurConfig::new(1.0).with_radius(u32::MAX).radius,
+            MAX_RADIUS
+        );
+    }
+
     #[test]
     fn test_gaussian_kernel_generation() {
         let kernel = generate_gaussian_kernel(1.0, 1);
-        assert_eq!(kernel.len(), 9); // 3x3 kernel
+        assert_eq!(kernel.len(), 3); // 1D kernel, applied once per axis
 
         // Sum should be approximately 1.0
         let sum: f32 = kernel.iter().sum();
@@ -273,6 +1201,74 @@ mod tests {
         assert_eq!(blurred.height(), 10);
     }
 
+    #[test]
+    fn test_blur_kinds_preserve_flat_color() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(7, 5, Rgb([40, 120, 200])));
+
+        for kind in [BlurKind::Gaussian, BlurKind::Box, BlurKind::Stack] {
+            let config = BlurConfig::new(1.5).with_kind(kind);
+            let blurred = apply_blur(&img, &config).to_rgb8();
+            assert!(blurred.pixels().all(|p| *p == Rgb([40, 120, 200])));
+        }
+    }
+
+    #[test]
+    fn test_custom_gaussian_blur_smooths_checkerboard() {
+        let img = create_test_image();
+        let config = BlurConfig::new(1.0);
+        let blurred = apply_custom_gaussian_blur(&img, &config).to_rgb8();
+
+        // On a checkerboard, a symmetric blur pulls every pixel towards gray
+        let center = blurred.get_pixel(5, 5)[0];
+        assert!(center > 60 && center < 195);
+    }
+
+    #[test]
+    fn test_stack_blur_matches_triangular_kernel() {
+        let src = [0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 20.0];
+        let mut dst = [0.0; 7];
+        stack_blur_line(&src, &mut dst, 2, BorderMode::Clamp);
+
+        let weights = [1.0, 2.0, 3.0, 2.0, 1.0];
+        for (i, out) in dst.iter().enumerate() {
+            let expected: f32 = weights
+                .iter()
+                .enumerate()
+                .map(|(k, w)| sample(&src, i as isize + k as isize - 2, BorderMode::Clamp) * w)
+                .sum::<f32>()
+                / 9.0;
+            assert!((out - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_blur_preserves_alpha_without_dark_fringes() {
+        let img: RgbaImage = ImageBuffer::from_fn(8, 8, |x, _y| {
+            if x < 4 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        });
+        let blurred = apply_blur(&DynamicImage::ImageRgba8(img), &BlurConfig::new(1.0));
+
+        let DynamicImage::ImageRgba8(blurred) = blurred else {
+            panic!("Expected RGBA output format");
+        };
+        let edge = blurred.get_pixel(4, 4);
+        assert!(edge[3] > 0 && edge[3] < 255);
+        // Premultiplied blending keeps the visible color white
+        assert_eq!([edge[0], edge[1], edge[2]], [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_box_radius_matches_sigma() {
+        let config = BlurConfig::new(1.2).with_kind(BlurKind::Box);
+        assert_eq!(config.radius, 2);
+        let config = config.with_radius(5);
+        assert_eq!(config.radius, 5);
+    }
+
     #[test]
     fn test_noise_reduction() {
         let img = create_test_image();
@@ -283,6 +1279,305 @@ mod tests {
         assert_eq!(filtered.height(), 10);
     }
 
+    #[test]
+    fn test_median_removes_salt_and_pepper() {
+        let mut img: RgbImage = ImageBuffer::from_pixel(5, 5, Rgb([100, 100, 100]));
+        img.put_pixel(2, 2, Rgb([255, 255, 255]));
+
+        let filter = NoiseReduction::new(0.0, 1.0).with_mode(DenoiseMode::Median);
+        let filtered = filter.apply(&DynamicImage::ImageRgb8(img)).to_rgb8();
+        assert_eq!(*filtered.get_pixel(2, 2), Rgb([100, 100, 100]));
+    }
+
+    #[test]
+    fn test_noise_reduction_preserves_alpha() {
+        let img: RgbaImage = ImageBuffer::from_fn(4, 4, |x, _y| {
+            if x == 0 {
+                Rgba([255, 0, 0, 0])
+            } else {
+                Rgba([10, 10, 10, 200])
+            }
+        });
+
+        let filter = NoiseReduction::new(500.0, 1.0);
+        let filtered = filter.apply(&DynamicImage::ImageRgba8(img));
+        let DynamicImage::ImageRgba8(filtered) = filtered else {
+            panic!("Expected RGBA output format");
+        };
+
+        assert_eq!(filtered.get_pixel(0, 0)[3], 0);
+        // Transparent red neighbours must not tint visible pixels
+        assert_eq!(*filtered.get_pixel(1, 1), Rgba([10, 10, 10, 200]));
+    }
+
+    #[test]
+    fn test_noise_reduction_tiny_images() {
+        for (w, h) in [(1, 1), (1, 5), (5, 1), (2, 2)] {
+            let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(w, h, Rgb([7, 8, 9])));
+            for mode in [DenoiseMode::Selective, DenoiseMode::Median] {
+                let filtered = NoiseReduction::new(30.0, 0.5).with_mode(mode).apply(&img);
+                assert_eq!(filtered.dimensions(), (w, h));
+            }
+        }
+    }
+
+    #[test]
+    fn test_border_mode_resolve() {
+        assert_eq!(BorderMode::Clamp.resolve(-3, 5), Some(0));
+        assert_eq!(BorderMode::Clamp.resolve(9, 5), Some(4));
+        assert_eq!(BorderMode::Mirror.resolve(-1, 5), Some(0));
+        assert_eq!(BorderMode::Mirror.resolve(-2, 5), Some(1));
+        assert_eq!(BorderMode::Mirror.resolve(5, 5), Some(4));
+        assert_eq!(BorderMode::Mirror.resolve(11, 5), Some(1));
+        assert_eq!(BorderMode::Wrap.resolve(-1, 5), Some(4));
+        assert_eq!(BorderMode::Wrap.resolve(7, 5), Some(2));
+        assert_eq!(BorderMode::Constant.resolve(-1, 5), None);
+        assert_eq!(BorderMode::Constant.resolve(2, 5), Some(2));
+
+        // Single-pixel and empty axes
+        for mode in [BorderMode::Clamp, BorderMode::Mirror, BorderMode::Wrap] {
+            assert_eq!(mode.resolve(-4, 1), Some(0));
+            assert_eq!(mode.resolve(3, 1), Some(0));
+            assert_eq!(mode.resolve(0, 0), None);
+        }
+    }
+
+    #[test]
+    fn test_constant_border_darkens_edges() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(6, 6, Rgb([200, 200, 200])));
+        let config = BlurConfig::new(1.0).with_border(BorderMode::Constant);
+        let blurred = apply_blur(&img, &config).to_rgb8();
+
+        assert!(blurred.get_pixel(0, 0)[0] < 200);
+        let wrapped = apply_blur(&img, &config.with_border(BorderMode::Wrap)).to_rgb8();
+        assert_eq!(*wrapped.get_pixel(0, 0), Rgb([200, 200, 200]));
+    }
+
+    #[test]
+    fn test_effects_handle_degenerate_images() {
+        let borders = [
+            BorderMode::Clamp,
+            BorderMode::Mirror,
+            BorderMode::Wrap,
+            BorderMode::Constant,
+        ];
+
+        for (w, h) in [(0, 0), (0, 3), (1, 1), (1, 2), (2, 1), (2, 2)] {
+            let rgb = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(w, h, Rgb([9, 99, 199])));
+            let rgba =
+                DynamicImage::ImageRgba8(ImageBuffer::from_pixel(w, h, Rgba([9, 99, 199, 120])));
+
+            for img in [&rgb, &rgba] {
+                assert_eq!(
+                    apply_gaussian_blur(img, &BlurConfig::light()).dimensions(),
+                    (w, h)
+                );
+
+                for border in borders {
+                    for kind in [BlurKind::Gaussian, BlurKind::Box, BlurKind::Stack] {
+                        let config = BlurConfig::heavy().with_kind(kind).with_border(border);
+                        assert_eq!(apply_blur(img, &config).dimensions(), (w, h));
+                    }
+
+                    for mode in [DenoiseMode::Selective, DenoiseMode::Median] {
+                        let filter = NoiseReduction::new(30.0, 0.5)
+                            .with_mode(mode)
+                            .with_radius(3)
+                            .with_border(border);
+                        assert_eq!(filter.apply(img).dimensions(), (w, h));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Left half dark, right half bright, with mild noise on both sides
+    fn create_edge_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(16, 16, |x, y| {
+            let noise = ((x * 7 + y * 13) % 5) as u8 * 4;
+            if x < 8 {
+                Rgb([40 + noise, 40 + noise, 40 + noise])
+            } else {
+                Rgb([200 + noise, 200 + noise, 200 + noise])
+            }
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_kuwahara_keeps_edges_and_flattens_noise() {
+        for kind in [KuwaharaKind::Classic, KuwaharaKind::Anisotropic] {
+            let filtered = Kuwahara::new(3).with_kind(kind).apply(&create_edge_image());
+            let filtered = filtered.to_rgb8();
+
+            // Pixels next to the edge stay on their own side
+            assert!(filtered.get_pixel(7, 8)[0] < 80, "{:?}", kind);
+            assert!(filtered.get_pixel(8, 8)[0] > 160, "{:?}", kind);
+
+            // Noise within the flat area is reduced
+            let row: Vec<u8> = (1..6).map(|x| filtered.get_pixel(x, 8)[0]).collect();
+            let spread = row.iter().max().unwrap() - row.iter().min().unwrap();
+            assert!(spread <= 8, "{:?} spread {}", kind, spread);
+        }
+    }
+
+    #[test]
+    fn test_kuwahara_flat_image_unchanged() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(6, 6, Rgb([50, 100, 150])));
+        for kind in [KuwaharaKind::Classic, KuwaharaKind::Anisotropic] {
+            let filtered = Kuwahara::new(2).with_kind(kind).apply(&img).to_rgb8();
+            assert!(filtered.pixels().all(|p| *p == Rgb([50, 100, 150])));
+        }
+    }
+
+    #[test]
+    fn test_bilateral_preserves_edges() {
+        let img = create_edge_image();
+        let filtered = BilateralFilter::new(2.0, 25.0).apply(&img).to_rgb8();
+        assert!(filtered.get_pixel(7, 8)[0] < 80);
+        assert!(filtered.get_pixel(8, 8)[0] > 160);
+
+        // A plain Gaussian of similar size smears the edge
+        let blurred = apply_blur(&img, &BlurConfig::new(2.0)).to_rgb8();
+        assert!(blurred.get_pixel(7, 8)[0] > filtered.get_pixel(7, 8)[0]);
+    }
+
+    #[test]
+    fn test_bilateral_radius_is_capped() {
+        assert_eq!(BilateralFilter::new(1e9, 25.0).radius, MAX_RADIUS);
+        assert_eq!(
+            BilateralFilter::new(2.0, 25.0).with_radius(u32::MAX).radius,
+            MAX_RADIUS
+        );
+    }
+
+    #[test]
+    fn test_painterly_filters_preserve_alpha() {
+        let img: RgbaImage = ImageBuffer::from_fn(6, 6, |x, _y| {
+            if x < 2 {
+                Rgba([255, 0, 0, 0])
+            } else {
+                Rgba([10, 20, 30, 180])
+            }
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let outputs = [
+            Kuwahara::new(2).apply(&img),
+            Kuwahara::new(2)
+                .with_kind(KuwaharaKind::Anisotropic)
+                .apply(&img),
+            BilateralFilter::new(1.5, 30.0).apply(&img),
+        ];
+        for output in outputs {
+            let output = output.to_rgba8();
+            assert_eq!(*output.get_pixel(0, 0), Rgba([255, 0, 0, 0]));
+            // Transparent red must not tint visible pixels
+            assert_eq!(*output.get_pixel(2, 3), Rgba([10, 20, 30, 180]));
+        }
+    }
+
+    #[test]
+    fn test_painterly_filters_handle_degenerate_images() {
+        for (w, h) in [(0, 0), (0, 3), (1, 1), (1, 4), (3, 1)] {
+            let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(w, h, Rgb([9, 99, 199])));
+            for border in [BorderMode::Clamp, BorderMode::Constant] {
+                for kind in [KuwaharaKind::Classic, KuwaharaKind::Anisotropic] {
+                    let filter = Kuwahara::new(2).with_kind(kind).with_border(border);
+                    assert_eq!(filter.apply(&img).dimensions(), (w, h));
+                }
+                let filter = BilateralFilter::new(1.0, 20.0).with_border(border);
+                assert_eq!(filter.apply(&img).dimensions(), (w, h));
+            }
+        }
+    }
+
+    #[test]
+    fn test_scanlines_and_lcd_grid_darken_gaps() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(6, 6, Rgb([200, 200, 200])));
+
+        let scanlines = RetroDisplay::new(RetroEffect::Scanlines)
+            .with_size(2)
+            .with_strength(0.5)
+            .apply(&img)
+            .to_rgb8();
+        assert_eq!(*scanlines.get_pixel(0, 0), Rgb([200, 200, 200]));
+        assert_eq!(*scanlines.get_pixel(0, 1), Rgb([100, 100, 100]));
+
+        let grid = RetroDisplay::new(RetroEffect::LcdGrid)
+            .with_size(3)
+            .with_strength(1.0)
+            .apply(&img)
+            .to_rgb8();
+        assert_eq!(*grid.get_pixel(0, 0), Rgb([200, 200, 200]));
+        assert_eq!(*grid.get_pixel(2, 0), Rgb([0, 0, 0]));
+        assert_eq!(*grid.get_pixel(0, 5), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_rgb_mask_stripes() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(3, 1, Rgb([200, 200, 200])));
+        let masked = RetroDisplay::new(RetroEffect::RgbMask)
+            .with_size(3)
+            .with_strength(1.0)
+            .apply(&img)
+            .to_rgb8();
+
+        assert_eq!(*masked.get_pixel(0, 0), Rgb([200, 0, 0]));
+        assert_eq!(*masked.get_pixel(1, 0), Rgb([0, 200, 0]));
+        assert_eq!(*masked.get_pixel(2, 0), Rgb([0, 0, 200]));
+    }
+
+    #[test]
+    fn test_barrel_keeps_center_and_clears_corners() {
+        let img: RgbaImage =
+            ImageBuffer::from_fn(21, 21, |x, y| Rgba([x as u8 * 10, y as u8 * 10, 0, 255]));
+        let output = RetroDisplay::new(RetroEffect::Barrel)
+            .with_strength(1.0)
+            .apply(&DynamicImage::ImageRgba8(img))
+            .to_rgba8();
+
+        assert_eq!(*output.get_pixel(10, 10), Rgba([100, 100, 0, 255]));
+        assert_eq!(output.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_bloom_brightens_around_highlights() {
+        let img: RgbImage = ImageBuffer::from_fn(15, 15, |x, y| {
+            if (x, y) == (7, 7) {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([20, 20, 20])
+            }
+        });
+        let output = RetroDisplay::new(RetroEffect::Bloom)
+            .with_strength(1.0)
+            .apply(&DynamicImage::ImageRgb8(img))
+            .to_rgb8();
+
+        assert!(output.get_pixel(8, 7)[0] > 20);
+        assert_eq!(*output.get_pixel(0, 0), Rgb([20, 20, 20]));
+        assert_eq!(*output.get_pixel(7, 7), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn test_retro_effects_handle_degenerate_images() {
+        for (w, h) in [(0, 0), (1, 1), (1, 4), (3, 1)] {
+            let img =
+                DynamicImage::ImageRgba8(ImageBuffer::from_pixel(w, h, Rgba([9, 99, 199, 50])));
+            for effect in [
+                RetroEffect::Scanlines,
+                RetroEffect::RgbMask,
+                RetroEffect::Barrel,
+                RetroEffect::Bloom,
+                RetroEffect::LcdGrid,
+            ] {
+                let output = RetroDisplay::new(effect).apply(&img);
+                assert_eq!(output.dimensions(), (w, h));
+            }
+        }
+    }
+
     #[test]
     fn test_color_difference() {
         let white = Rgb([255, 255, 255]);
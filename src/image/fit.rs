@@ -0,0 +1,280 @@
+use crate::Palette;
+use image::{DynamicImage, Rgb};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use super::color_space::Oklab;
+use super::converter::{NearestColorConverter, PaletteConverter};
+
+/// How palettes are ranked against an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FitMetric {
+    /// Mean color difference between each pixel and its palette match
+    #[default]
+    DeltaE,
+    /// Share of pixels left without a close palette match
+    Coverage,
+}
+
+/// How well one palette reproduces an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteFit {
+    pub name: String,
+    /// Mean ΔE (OKLab distance × 100) between sampled pixels and the palette
+    /// colors they convert to
+    pub mean_delta_e: f32,
+    /// Fraction of sampled pixels whose ΔE exceeds the coverage threshold
+    pub coverage_error: f32,
+}
+
+impl PaletteFit {
+    pub fn score(&self, metric: FitMetric) -> f32 {
+        match metric {
+            FitMetric::DeltaE => self.mean_delta_e,
+            FitMetric::Coverage => self.coverage_error,
+        }
+    }
+}
+
+/// ΔE above which a pixel counts as not covered by the palette
+const COVERAGE_THRESHOLD: f32 = 10.0;
+
+/// Scores palettes against an image by converting a grid of sampled pixels
+/// with the same nearest-color matching used for conversion
+pub struct PaletteRanker {
+    metric: FitMetric,
+    samples: usize,
+    threshold: f32,
+}
+
+impl PaletteRanker {
+    pub fn new() -> Self {
+        Self {
+            metric: FitMetric::DeltaE,
+            samples: 10_000,
+            threshold: COVERAGE_THRESHOLD,
+        }
+    }
+
+    pub fn with_metric(mut self, metric: FitMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Approximate number of pixels to sample; 0 uses every pixel
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold.max(0.0);
+        self
+    }
+
+    /// Evenly spaced opaque pixels from the image
+    pub fn sample(&self, img: &DynamicImage) -> Vec<Rgb<u8>> {
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let total = u64::from(width) * u64::from(height);
+
+        let step = if self.samples == 0 || total <= self.samples as u64 {
+            1
+        } else {
+            ((total as f64 / self.samples as f64).sqrt().floor() as u32).max(1)
+        };
+
+        let mut pixels = Vec::new();
+        for y in (step / 2..height).step_by(step as usize) {
+            for x in (step / 2..width).step_by(step as usize) {
+                let p = rgba.get_pixel(x, y);
+                if p[3] > 0 {
+                    pixels.push(Rgb([p[0], p[1], p[2]]));
+                }
+            }
+        }
+        pixels
+    }
+
+    /// Score one palette against already sampled pixels
+    pub fn score(&self, samples: &[Rgb<u8>], palette: &Palette) -> crate::Result<PaletteFit> {
+        let converter = NearestColorConverter::new(palette)?;
+
+        let mut total = 0.0f64;
+        let mut uncovered = 0usize;
+        for &pixel in samples {
+            let matched = converter.convert_pixel(pixel);
+            let delta_e = Oklab::from_rgb(pixel).distance(&Oklab::from_rgb(matched)) * 100.0;
+            total += f64::from(delta_e);
+            if delta_e > self.threshold {
+                uncovered += 1;
+            }
+        }
+
+        let count = samples.len().max(1) as f64;
+        Ok(PaletteFit {
+            name: palette.name.clone(),
+            mean_delta_e: (total / count) as f32,
+            coverage_error: (uncovered as f64 / count) as f32,
+        })
+    }
+
+    /// Rank palettes best fit first. Palettes without usable colors are
+    /// skipped with a warning.
+    pub fn rank(
+        &self,
+        img: &DynamicImage,
+        palettes: &[&Palette],
+    ) -> crate::Result<Vec<PaletteFit>> {
+        let samples = self.sample(img);
+        if samples.is_empty() {
+            return Err(crate::RustBucketError::InvalidImage(
+                "Image has no opaque pixels to compare palettes against".to_string(),
+            ));
+        }
+        log::info!(
+            "Ranking {} palettes on {} sampled pixels",
+            palettes.len(),
+            samples.len()
+        );
+
+        let mut fits = Vec::with_capacity(palettes.len());
+        for palette in palettes {
+            match self.score(&samples, palette) {
+                Ok(fit) => fits.push(fit),
+                Err(e) => log::warn!("Skipping palette '{}': {}", palette.name, e),
+            }
+        }
+
+        let other = match self.metric {
+            FitMetric::DeltaE => FitMetric::Coverage,
+            FitMetric::Coverage => FitMetric::DeltaE,
+        };
+        fits.sort_by(|a, b| {
+            a.score(self.metric)
+                .partial_cmp(&b.score(self.metric))
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    a.score(other)
+                        .partial_cmp(&b.score(other))
+                        .unwrap_or(Ordering::Equal),
+                )
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        if fits.is_empty() {
+            return Err(crate::RustBucketError::PaletteNotFound(
+                "No palette could be ranked".to_string(),
+            ));
+        }
+        Ok(fits)
+    }
+}
+
+impl Default for PaletteRanker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use image::{RgbImage, Rgba, RgbaImage};
+    use std::path::PathBuf;
+
+    fn palette(name: &str, hexes: &[&str]) -> Palette {
+        Palette {
+            name: name.to_string(),
+            path: PathBuf::from(format!("user://{}", name)),
+            colors: hexes
+                .iter()
+                .enumerate()
+                .map(|(i, hex)| Color {
+                    name: format!("c{}", i),
+                    hex: hex.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn two_tone() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(100, 100, |x, _| {
+            if x < 50 {
+                Rgb([200, 40, 40])
+            } else {
+                Rgb([40, 40, 200])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_exact_palette_ranks_first() {
+        let exact = palette("exact", &["#C82828", "#2828C8"]);
+        let close = palette("close", &["#BE3232", "#3232BE"]);
+        let gray = palette("gray", &["#000000", "#808080", "#FFFFFF"]);
+
+        let fits = PaletteRanker::new()
+            .rank(&two_tone(), &[&gray, &close, &exact])
+            .unwrap();
+        let names: Vec<&str> = fits.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["exact", "close", "gray"]);
+        assert_eq!(fits[0].mean_delta_e, 0.0);
+        assert_eq!(fits[0].coverage_error, 0.0);
+        assert_eq!(fits[2].coverage_error, 1.0);
+    }
+
+    #[test]
+    fn test_metrics_can_disagree() {
+        // One half matched exactly and the other badly, vs. both halves
+        // slightly off
+        let half = palette("half", &["#C82828", "#00FF00"]);
+        let near = palette("near", &["#B43C3C", "#3C3CB4"]);
+        let ranker = PaletteRanker::new().with_threshold(2.0);
+
+        let by_delta_e = ranker.rank(&two_tone(), &[&half, &near]).unwrap();
+        assert_eq!(by_delta_e[0].name, "near");
+        assert_eq!(by_delta_e[0].coverage_error, 1.0);
+        assert!((by_delta_e[1].coverage_error - 0.5).abs() < 1e-6);
+
+        let by_coverage = ranker
+            .with_metric(FitMetric::Coverage)
+            .rank(&two_tone(), &[&half, &near])
+            .unwrap();
+        assert_eq!(by_coverage[0].name, "half");
+    }
+
+    #[test]
+    fn test_sampling() {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(200, 100));
+        assert_eq!(PaletteRanker::new().sample(&img).len(), 20_000);
+        assert_eq!(
+            PaletteRanker::new().with_samples(0).sample(&img).len(),
+            20_000
+        );
+        let sampled = PaletteRanker::new().with_samples(500).sample(&img);
+        assert!((400..=700).contains(&sampled.len()));
+
+        let transparent = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([0; 4])));
+        assert!(PaletteRanker::new().sample(&transparent).is_empty());
+        let p = palette("p", &["#000000"]);
+        assert!(matches!(
+            PaletteRanker::new().rank(&transparent, &[&p]),
+            Err(crate::RustBucketError::InvalidImage(_))
+        ));
+    }
+
+    #[test]
+    fn test_unusable_palettes_are_skipped() {
+        let empty = palette("empty", &[]);
+        let good = palette("good", &["#C82828"]);
+        let fits = PaletteRanker::new()
+            .rank(&two_tone(), &[&empty, &good])
+            .unwrap();
+        assert_eq!(fits.len(), 1);
+        assert!(PaletteRanker::new().rank(&two_tone(), &[&empty]).is_err());
+    }
+}
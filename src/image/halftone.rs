@@ -0,0 +1,402 @@
+use crate::Palette;
+use image::{DynamicImage, ImageBuffer, Rgb, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::converter::{NearestColorConverter, PaletteConverter};
+use super::{palette_color, rgb_distance};
+
+/// Dot shape of a halftone screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HalftoneShape {
+    /// Circular dots that merge into each other in dark areas
+    #[default]
+    Round,
+    /// Parallel lines whose width follows the tone
+    Line,
+    /// Square dots
+    Square,
+}
+
+/// Renders images as a rotated screen of dots, one dot per cell, using
+/// palette colors as inks on a palette background color.
+///
+/// Each cell takes the ink nearest to its average color; the dot covers as
+/// much of the cell as that ink needs to reproduce the average against the
+/// background. With anti-aliasing, dot edges blend ink and background and
+/// the blend is snapped back to the nearest palette color, so the output
+/// never leaves the palette.
+pub struct HalftoneConverter {
+    converter: NearestColorConverter,
+    inks: Vec<Rgb<u8>>,
+    background: Rgb<u8>,
+    shape: HalftoneShape,
+    angle: f32,
+    cell_size: f32,
+    anti_alias: bool,
+}
+
+impl HalftoneConverter {
+    /// Use the selected colors (or the whole palette) as inks on
+    /// `background`, a palette color name or alias, or a hex code snapped to
+    /// the nearest selected color. Without a background, the first color is
+    /// used, which is the background color in most theme palettes.
+    pub fn new(
+        palette: &Palette,
+        selected_colors: &[String],
+        background: Option<&str>,
+    ) -> crate::Result<Self> {
+        let converter = if selected_colors.is_empty() {
+            NearestColorConverter::new(palette)?
+        } else {
+            NearestColorConverter::new_with_selected_colors(palette, selected_colors)?
+        };
+
+        let background = match background {
+            Some(name) => palette_color(palette, selected_colors, name)?,
+            None => converter.palette_colors()[0],
+        };
+
+        let mut inks: Vec<Rgb<u8>> = converter
+            .palette_colors()
+            .iter()
+            .copied()
+            .filter(|&color| color != background)
+            .collect();
+        if inks.is_empty() {
+            log::warn!("Halftone has no ink colors besides the background");
+            inks.push(background);
+        }
+
+        Ok(Self {
+            converter,
+            inks,
+            background,
+            shape: HalftoneShape::Round,
+            angle: 45.0,
+            cell_size: 8.0,
+            anti_alias: true,
+        })
+    }
+
+    pub fn with_shape(mut self, shape: HalftoneShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Screen angle in degrees
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    /// Cell size in pixels
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size.max(2.0);
+        self
+    }
+
+    pub fn with_anti_alias(mut self, anti_alias: bool) -> Self {
+        self.anti_alias = anti_alias;
+        self
+    }
+
+    pub fn background(&self) -> Rgb<u8> {
+        self.background
+    }
+
+    /// Position of a pixel center in rotated screen space, in cell units:
+    /// the cell index and the offset from the cell center (-0.5 to 0.5)
+    fn screen_position(&self, x: u32, y: u32) -> ((i64, i64), (f32, f32)) {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let u = (px * cos + py * sin) / self.cell_size;
+        let v = (-px * sin + py * cos) / self.cell_size;
+
+        let cell = (u.floor() as i64, v.floor() as i64);
+        (cell, (u - u.floor() - 0.5, v - v.floor() - 0.5))
+    }
+
+    /// Ink for a cell's average color and the fraction of the cell it covers
+    fn ink_for(&self, average: [f32; 3]) -> (Rgb<u8>, f32) {
+        let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+        let target = Rgb(average.map(to_u8));
+        let ink = *self
+            .inks
+            .iter()
+            .min_by(|a, b| rgb_distance(&target, a).total_cmp(&rgb_distance(&target, b)))
+            .unwrap_or(&self.background);
+
+        // Project the average onto the background -> ink line
+        let mut dot = 0.0;
+        let mut length_sq = 0.0;
+        for c in 0..3 {
+            let span = f32::from(ink[c]) - f32::from(self.background[c]);
+            dot += (average[c] - f32::from(self.background[c])) * span;
+            length_sq += span * span;
+        }
+
+        let coverage = if length_sq > 0.0 {
+            (dot / length_sq).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (ink, coverage)
+    }
+
+    /// Signed distance from a cell offset to the dot edge, in cell units
+    /// (negative inside the dot)
+    fn dot_distance(&self, (du, dv): (f32, f32), coverage: f32) -> f32 {
+        match self.shape {
+            HalftoneShape::Round => {
+                // Circle area matches coverage until it touches the cell
+                // edges, then grows towards the corners
+                let quarter = std::f32::consts::FRAC_PI_4;
+                let radius = if coverage <= quarter {
+                    (coverage / std::f32::consts::PI).sqrt()
+                } else {
+                    0.5 + (coverage - quarter) / (1.0 - quarter) * (0.5_f32.sqrt() - 0.5)
+                };
+                (du * du + dv * dv).sqrt() - radius
+            }
+            HalftoneShape::Line => dv.abs() - coverage / 2.0,
+            HalftoneShape::Square => du.abs().max(dv.abs()) - coverage.sqrt() / 2.0,
+        }
+    }
+
+    fn render(&self, img: &DynamicImage) -> RgbaImage {
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+
+        log::info!(
+            "Rendering {}x{} halftone ({:?} dots, {} px cells at {} degrees)",
+            width,
+            height,
+            self.shape,
+            self.cell_size,
+            self.angle
+        );
+
+        // Average visible color of every cell
+        let mut cells: HashMap<(i64, i64), ([f32; 3], u32)> = HashMap::new();
+        for (x, y, pixel) in rgba_img.enumerate_pixels() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let (cell, _) = self.screen_position(x, y);
+            let entry = cells.entry(cell).or_insert(([0.0; 3], 0));
+            for c in 0..3 {
+                entry.0[c] += f32::from(pixel[c]);
+            }
+            entry.1 += 1;
+        }
+        let inks: HashMap<(i64, i64), (Rgb<u8>, f32)> = cells
+            .into_iter()
+            .map(|(cell, (sums, count))| (cell, self.ink_for(sums.map(|s| s / count as f32))))
+            .collect();
+
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = rgba_img.get_pixel(x, y);
+            let (cell, offset) = self.screen_position(x, y);
+            let Some(&(ink, coverage)) = inks.get(&cell) else {
+                return Rgba([
+                    self.background[0],
+                    self.background[1],
+                    self.background[2],
+                    pixel[3],
+                ]);
+            };
+
+            let distance = self.dot_distance(offset, coverage);
+            let color = if self.anti_alias {
+                // Fraction of this pixel inside the dot
+                let inside = (0.5 - distance * self.cell_size).clamp(0.0, 1.0);
+                let blend = |c: usize| {
+                    let bg = f32::from(self.background[c]);
+                    (bg + (f32::from(ink[c]) - bg) * inside).round() as u8
+                };
+                self.converter
+                    .convert_pixel(Rgb([blend(0), blend(1), blend(2)]))
+            } else if distance < 0.0 {
+                ink
+            } else {
+                self.background
+            };
+
+            Rgba([color[0], color[1], color[2], pixel[3]])
+        })
+    }
+}
+
+impl PaletteConverter for HalftoneConverter {
+    fn palette_colors(&self) -> &[Rgb<u8>] {
+        self.converter.palette_colors()
+    }
+
+    fn convert_pixel(&self, rgb: Rgb<u8>) -> Rgb<u8> {
+        self.converter.convert_pixel(rgb)
+    }
+
+    fn convert_image(&self, img: &DynamicImage) -> DynamicImage {
+        if img.color().has_alpha() {
+            return self.convert_image_with_alpha(img);
+        }
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(self.render(img)).to_rgb8())
+    }
+
+    fn convert_image_with_alpha(&self, img: &DynamicImage) -> DynamicImage {
+        DynamicImage::ImageRgba8(self.render(img))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use image::{GenericImageView, RgbImage};
+    use std::path::PathBuf;
+
+    fn create_test_palette() -> Palette {
+        let color = |name: &str, hex: &str| Color {
+            name: name.to_string(),
+            hex: hex.to_string(),
+        };
+        Palette {
+            name: "test".to_string(),
+            path: PathBuf::from("test"),
+            colors: vec![
+                color("Paper", "#FFFFFF"),
+                color("Ink", "#000000"),
+                color("Gray", "#808080"),
+                color("Red", "#FF0000"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn gradient_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(64, 16, |x, _y| {
+            let v = 255 - (x * 4) as u8;
+            Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn ink_fraction(img: &DynamicImage, x_range: std::ops::Range<u32>) -> f32 {
+        let rgb = img.to_rgb8();
+        let mut inked = 0;
+        let mut total = 0;
+        for y in 0..rgb.height() {
+            for x in x_range.clone() {
+                total += 1;
+                if rgb.get_pixel(x, y)[0] < 128 {
+                    inked += 1;
+                }
+            }
+        }
+        inked as f32 / total as f32
+    }
+
+    #[test]
+    fn test_default_background_is_first_color() {
+        let converter = HalftoneConverter::new(&create_test_palette(), &[], None).unwrap();
+        assert_eq!(converter.background(), Rgb([255, 255, 255]));
+
+        let converter = HalftoneConverter::new(&create_test_palette(), &[], Some("ink")).unwrap();
+        assert_eq!(converter.background(), Rgb([0, 0, 0]));
+
+        // Off-palette hex codes snap to the nearest palette color
+        let converter =
+            HalftoneConverter::new(&create_test_palette(), &[], Some("#F0E8E0")).unwrap();
+        assert_eq!(converter.background(), Rgb([255, 255, 255]));
+
+        // ...or to the nearest selected color
+        let colors = vec!["Ink".to_string(), "Red".to_string()];
+        let converter =
+            HalftoneConverter::new(&create_test_palette(), &colors, Some("#F0E8E0")).unwrap();
+        assert_eq!(converter.background(), Rgb([255, 0, 0]));
+
+        assert!(HalftoneConverter::new(&create_test_palette(), &[], Some("Blue")).is_err());
+    }
+
+    #[test]
+    fn test_dots_grow_with_darkness() {
+        let palette = create_test_palette();
+        let colors = vec!["Paper".to_string(), "Ink".to_string()];
+
+        for shape in [
+            HalftoneShape::Round,
+            HalftoneShape::Line,
+            HalftoneShape::Square,
+        ] {
+            let converter = HalftoneConverter::new(&palette, &colors, None)
+                .unwrap()
+                .with_shape(shape)
+                .with_cell_size(4.0);
+            let output = converter.convert_image(&gradient_image());
+
+            let light = ink_fraction(&output, 0..16);
+            let dark = ink_fraction(&output, 48..64);
+            assert!(dark > light, "{:?}: {} vs {}", shape, light, dark);
+        }
+    }
+
+    #[test]
+    fn test_output_stays_in_palette() {
+        let palette = create_test_palette();
+        let allowed: Vec<Rgb<u8>> = palette
+            .colors
+            .iter()
+            .map(|c| super::super::hex_to_rgb(&c.hex).unwrap())
+            .collect();
+
+        for anti_alias in [false, true] {
+            let converter = HalftoneConverter::new(&palette, &[], None)
+                .unwrap()
+                .with_angle(15.0)
+                .with_anti_alias(anti_alias);
+            let output = converter.convert_image(&gradient_image()).to_rgb8();
+            assert!(output.pixels().all(|p| allowed.contains(p)));
+        }
+    }
+
+    #[test]
+    fn test_colored_cells_use_matching_ink() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(16, 16, Rgb([200, 30, 30])));
+        let converter = HalftoneConverter::new(&create_test_palette(), &[], None)
+            .unwrap()
+            .with_anti_alias(false);
+        let output = converter.convert_image(&img).to_rgb8();
+
+        assert!(output.pixels().any(|p| *p == Rgb([255, 0, 0])));
+        assert!(output.pixels().all(|p| *p != Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn test_alpha_preserved() {
+        let img: RgbaImage = ImageBuffer::from_fn(8, 8, |x, _y| {
+            if x < 4 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        });
+        let converter = HalftoneConverter::new(&create_test_palette(), &[], None).unwrap();
+        let output = converter
+            .convert_image(&DynamicImage::ImageRgba8(img))
+            .to_rgba8();
+
+        assert_eq!(output.get_pixel(0, 0)[3], 0);
+        assert_eq!(output.get_pixel(7, 7)[3], 255);
+    }
+
+    #[test]
+    fn test_degenerate_images() {
+        let converter = HalftoneConverter::new(&create_test_palette(), &[], None).unwrap();
+        for (w, h) in [(0, 0), (1, 1), (1, 5), (5, 1)] {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, Rgb([90, 90, 90])));
+            assert_eq!(converter.convert_image(&img).dimensions(), (w, h));
+        }
+    }
+}
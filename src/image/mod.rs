@@ -1,14 +1,45 @@
+mod adjust;
 mod benchmark;
+mod color_space;
+mod contact_sheet;
 mod converter;
+mod despeckle;
 mod effects;
+mod fit;
+mod halftone;
+mod mosaic;
+mod outline;
+mod pipeline;
 mod processor;
+mod recipe;
+mod recolor;
+mod tone_map;
 
+pub use adjust::Adjustments;
 pub use benchmark::{BenchmarkResult, ImageBenchmark, OptimizationSuggestions};
-pub use converter::{NearestColorConverter, PaletteConverter};
-pub use effects::{BlurConfig, NoiseReduction};
+pub use color_space::{linear_to_srgb, srgb_to_linear, Oklab};
+pub use contact_sheet::ContactSheet;
+pub use converter::{Dithering, NearestColorConverter, PaletteConverter};
+pub use despeckle::{Despeckle, DespeckleMode};
+pub use effects::{
+    apply_blur, BilateralFilter, BlurConfig, BlurKind, BorderMode, DenoiseMode, Kuwahara,
+    KuwaharaKind, NoiseReduction, RetroDisplay, RetroEffect,
+};
+pub use fit::{FitMetric, PaletteFit, PaletteRanker};
+pub use halftone::{HalftoneConverter, HalftoneShape};
+pub use mosaic::{grout_mask, CellShape, Mosaic};
+pub use outline::{EdgeDetector, Outline};
+pub use pipeline::{
+    BilateralStage, BlurStage, ConvertStage, DenoiseStage, DespeckleStage, Effect, HalftoneStage,
+    KuwaharaStage, OutlineStage, Pipeline, ProcessingDiagnostics, RecolorStage, ResnapStage,
+    RetroStage, StageContext, ToneRemapStage,
+};
 pub use processor::{ImagePipeline, ImageProcessor};
+pub use recipe::{Recipe, StageSpec};
+pub use recolor::{Recolor, RoleAssignment};
+pub use tone_map::{ToneRemap, ToneRemapReport};
 
-use crate::RustBucketError;
+use crate::{Palette, RustBucketError};
 use image::{DynamicImage, Rgb};
 use std::path::Path;
 
@@ -49,6 +80,49 @@ pub fn rgb_to_hex(rgb: &Rgb<u8>) -> String {
     crate::palette::rgb_to_hex(rgb[0], rgb[1], rgb[2])
 }
 
+/// Largest neighbourhood radius accepted for denoise, bilateral, Kuwahara,
+/// blur and despeckle windows
+pub const MAX_RADIUS: u32 = 128;
+
+/// Largest Gaussian sigma accepted for blur and bilateral filters, whose
+/// three-sigma radius is [`MAX_RADIUS`]
+pub const MAX_SIGMA: f32 = MAX_RADIUS as f32 / 3.0;
+
+/// Look up a color by palette color name or alias (case-insensitive). Hex
+/// codes are snapped to the nearest of the selected colors (or the whole
+/// palette) so the result is always one the image may use.
+pub fn palette_color(
+    palette: &Palette,
+    selected_colors: &[String],
+    name_or_hex: &str,
+) -> crate::Result<Rgb<u8>> {
+    if let Some(color) = palette.find_color(name_or_hex) {
+        return hex_to_rgb(&color.hex);
+    }
+
+    let rgb = hex_to_rgb(name_or_hex).map_err(|_| {
+        RustBucketError::ColorNotFound(format!(
+            "'{}' is neither a color in palette '{}' nor a hex code",
+            name_or_hex, palette.name
+        ))
+    })?;
+    let converter = if selected_colors.is_empty() {
+        NearestColorConverter::new(palette)?
+    } else {
+        NearestColorConverter::new_with_selected_colors(palette, selected_colors)?
+    };
+    let nearest = converter.convert_pixel(rgb);
+    if nearest != rgb {
+        log::info!(
+            "Color {} is not in palette '{}', using {} instead",
+            name_or_hex,
+            palette.name,
+            rgb_to_hex(&nearest)
+        );
+    }
+    Ok(nearest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +154,35 @@ mod tests {
         let hex = rgb_to_hex(&Rgb([0, 0, 0]));
         assert_eq!(hex, "#000000");
     }
+
+    #[test]
+    fn test_palette_color_lookup() {
+        let palette = crate::palette::BuiltinPalettes::get_palette("nord").unwrap();
+
+        assert_eq!(
+            palette_color(&palette, &[], "nord0").unwrap(),
+            Rgb([46, 52, 64])
+        );
+        assert_eq!(
+            palette_color(&palette, &[], "NORD0").unwrap(),
+            Rgb([46, 52, 64])
+        );
+        // Hex codes snap to the nearest palette color
+        assert_eq!(
+            palette_color(&palette, &[], "#102030").unwrap(),
+            Rgb([46, 52, 64])
+        );
+        assert_eq!(
+            palette_color(&palette, &[], "#88c0d0").unwrap(),
+            Rgb([136, 192, 208])
+        );
+        assert!(palette_color(&palette, &[], "nord99").is_err());
+
+        // With a selection, hex codes snap to the nearest selected color
+        let selected = vec!["nord6".to_string(), "nord8".to_string()];
+        assert_eq!(
+            palette_color(&palette, &selected, "#102030").unwrap(),
+            Rgb([136, 192, 208])
+        );
+    }
 }
@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Shape of the cells pixels are averaged over before palette matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CellShape {
+    /// Axis-aligned rectangular blocks
+    #[default]
+    Square,
+    /// Pointy-top hexagons
+    Hexagon,
+    /// Alternating equilateral triangles
+    Triangle,
+    /// "Stained glass" cells around jittered seed points
+    Voronoi,
+}
+
+/// Partition of an image into mosaic cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mosaic {
+    shape: CellShape,
+    cell_size: (u32, u32),
+    jitter: f32,
+    seed: u64,
+}
+
+impl Mosaic {
+    /// `cell_size` is the block size for square cells; other shapes use the
+    /// larger of the two as their spacing
+    pub fn new(shape: CellShape, cell_size: (u32, u32)) -> Self {
+        Self {
+            shape,
+            cell_size: (cell_size.0.max(1), cell_size.1.max(1)),
+            jitter: 1.0,
+            seed: 0,
+        }
+    }
+
+    /// How far Voronoi seeds stray from a regular grid, from 0.0 (square
+    /// cells) to 1.0 (anywhere within their grid cell)
+    pub fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seed for the Voronoi point placement
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn shape(&self) -> CellShape {
+        self.shape
+    }
+
+    /// Cell index of every pixel in row-major order, plus the number of
+    /// cells. Indices are dense and numbered in first-seen order.
+    pub fn cells(&self, width: u32, height: u32) -> (Vec<usize>, usize) {
+        let mut ids: HashMap<(i64, i64, u8), usize> = HashMap::new();
+        let mut cells = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let key = self.cell_key(x, y);
+                let next = ids.len();
+                cells.push(*ids.entry(key).or_insert(next));
+            }
+        }
+
+        (cells, ids.len())
+    }
+
+    fn spacing(&self) -> f32 {
+        self.cell_size.0.max(self.cell_size.1) as f32
+    }
+
+    fn cell_key(&self, x: u32, y: u32) -> (i64, i64, u8) {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+        match self.shape {
+            CellShape::Square => (
+                i64::from(x / self.cell_size.0),
+                i64::from(y / self.cell_size.1),
+                0,
+            ),
+            CellShape::Hexagon => {
+                let (q, r) = hex_round(px, py, self.spacing() / 3f32.sqrt());
+                (q, r, 0)
+            }
+            CellShape::Triangle => {
+                // Skewed lattice coordinates: each unit rhombus splits into
+                // an upward and a downward triangle
+                let side = self.spacing();
+                let a = px / side - py / (side * 3f32.sqrt());
+                let b = 2.0 * py / (side * 3f32.sqrt());
+                let upper = (a - a.floor()) + (b - b.floor()) >= 1.0;
+                (a.floor() as i64, b.floor() as i64, u8::from(upper))
+            }
+            CellShape::Voronoi => self.nearest_seed(px, py),
+        }
+    }
+
+    /// Seed point of a Voronoi grid cell, kept inside that grid cell
+    fn seed_point(&self, gx: i64, gy: i64) -> (f32, f32) {
+        let spacing = self.spacing();
+        let hash = splitmix64(self.seed ^ splitmix64((gx as u64) ^ splitmix64(gy as u64)));
+        let jx = (hash & 0xFFFF) as f32 / 65535.0 - 0.5;
+        let jy = ((hash >> 16) & 0xFFFF) as f32 / 65535.0 - 0.5;
+
+        (
+            (gx as f32 + 0.5 + jx * self.jitter) * spacing,
+            (gy as f32 + 0.5 + jy * self.jitter) * spacing,
+        )
+    }
+
+    fn nearest_seed(&self, px: f32, py: f32) -> (i64, i64, u8) {
+        let spacing = self.spacing();
+        let (gx, gy) = ((px / spacing).floor() as i64, (py / spacing).floor() as i64);
+
+        let mut nearest = (gx, gy, 0);
+        let mut min_distance = f32::INFINITY;
+        for ny in gy - 1..=gy + 1 {
+            for nx in gx - 1..=gx + 1 {
+                let (sx, sy) = self.seed_point(nx, ny);
+                let distance = (sx - px).powi(2) + (sy - py).powi(2);
+                if distance < min_distance {
+                    min_distance = distance;
+                    nearest = (nx, ny, 0);
+                }
+            }
+        }
+        nearest
+    }
+}
+
+/// Pixels on the border between two cells, widened to `width` pixels
+pub fn grout_mask(cells: &[usize], width: u32, height: u32, grout_width: u32) -> Vec<bool> {
+    let index = |x: u32, y: u32| (y * width + x) as usize;
+
+    let mut border = vec![false; cells.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let cell = cells[index(x, y)];
+            let right = x + 1 < width && cells[index(x + 1, y)] != cell;
+            let below = y + 1 < height && cells[index(x, y + 1)] != cell;
+            border[index(x, y)] = right || below;
+        }
+    }
+
+    if grout_width <= 1 {
+        return border;
+    }
+
+    let (width, height) = (i64::from(width), i64::from(height));
+    let before = i64::from((grout_width - 1) / 2);
+    let after = i64::from(grout_width / 2);
+
+    let mut widened = vec![false; border.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if !border[(y * width + x) as usize] {
+                continue;
+            }
+            for ny in (y - before).max(0)..=(y + after).min(height - 1) {
+                for nx in (x - before).max(0)..=(x + after).min(width - 1) {
+                    widened[(ny * width + nx) as usize] = true;
+                }
+            }
+        }
+    }
+    widened
+}
+
+/// Axial coordinates of the pointy-top hexagon with circumradius `radius`
+/// containing a point
+fn hex_round(px: f32, py: f32, radius: f32) -> (i64, i64) {
+    let q = (3f32.sqrt() / 3.0 * px - py / 3.0) / radius;
+    let r = (2.0 / 3.0 * py) / radius;
+    let s = -q - r;
+
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+
+    (rq as i64, rr as i64)
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell_sizes(mosaic: &Mosaic, width: u32, height: u32) -> Vec<usize> {
+        let (cells, count) = mosaic.cells(width, height);
+        let mut sizes = vec![0; count];
+        for cell in cells {
+            sizes[cell] += 1;
+        }
+        sizes
+    }
+
+    #[test]
+    fn test_square_cells_match_blocks() {
+        let mosaic = Mosaic::new(CellShape::Square, (4, 2));
+        let (cells, count) = mosaic.cells(8, 4);
+        assert_eq!(count, 4);
+        assert_eq!(cells[0], cells[3]);
+        assert_ne!(cells[3], cells[4]);
+        assert_eq!(cells[0], cells[8 + 3]);
+        assert_ne!(cells[0], cells[16]);
+    }
+
+    #[test]
+    fn test_cell_areas_follow_spacing() {
+        for shape in [CellShape::Hexagon, CellShape::Triangle, CellShape::Voronoi] {
+            let mosaic = Mosaic::new(shape, (10, 10));
+            let sizes = cell_sizes(&mosaic, 120, 120);
+            let average = (120 * 120) as f32 / sizes.len() as f32;
+
+            // Hexagons and Voronoi cells cover about spacing^2, triangles
+            // half of a 60 degree rhombus
+            let expected = match shape {
+                CellShape::Hexagon => 100.0 * 3f32.sqrt() / 2.0,
+                CellShape::Triangle => 100.0 * 3f32.sqrt() / 4.0,
+                _ => 100.0,
+            };
+            assert!(
+                (average - expected).abs() / expected < 0.25,
+                "{:?}: {} vs {}",
+                shape,
+                average,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_voronoi_seed_is_deterministic() {
+        let a = Mosaic::new(CellShape::Voronoi, (8, 8)).with_seed(7);
+        let b = Mosaic::new(CellShape::Voronoi, (8, 8)).with_seed(7);
+        let c = Mosaic::new(CellShape::Voronoi, (8, 8)).with_seed(8);
+
+        assert_eq!(a.cells(40, 40), b.cells(40, 40));
+        assert_ne!(a.cells(40, 40), c.cells(40, 40));
+    }
+
+    #[test]
+    fn test_voronoi_without_jitter_is_a_grid() {
+        let mosaic = Mosaic::new(CellShape::Voronoi, (5, 5)).with_jitter(0.0);
+        assert!(cell_sizes(&mosaic, 20, 20).iter().all(|&size| size == 25));
+    }
+
+    #[test]
+    fn test_grout_mask() {
+        let mosaic = Mosaic::new(CellShape::Square, (4, 4));
+        let (cells, _) = mosaic.cells(8, 8);
+
+        let mask = grout_mask(&cells, 8, 8, 1);
+        assert!(mask[3]);
+        assert!(!mask[2]);
+        assert!(mask[3 * 8]);
+        assert!(!mask[7 * 8 + 7]);
+
+        let wide = grout_mask(&cells, 8, 8, 3);
+        assert!(wide[2] && wide[3] && wide[4]);
+        assert!(wide.iter().filter(|&&g| g).count() > mask.iter().filter(|&&g| g).count());
+    }
+
+    #[test]
+    fn test_degenerate_sizes() {
+        for shape in [
+            CellShape::Square,
+            CellShape::Hexagon,
+            CellShape::Triangle,
+            CellShape::Voronoi,
+        ] {
+            let mosaic = Mosaic::new(shape, (0, 0));
+            assert_eq!(mosaic.cells(0, 0), (Vec::new(), 0));
+            assert_eq!(mosaic.cells(1, 3).0.len(), 3);
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use image::{DynamicImage, Rgb, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::effects::{apply_blur, sobel_gradient, BlurConfig, BorderMode};
+
+/// Edge detector used by [`Outline`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeDetector {
+    /// Thresholded Sobel gradient magnitude; bold, two-sided lines
+    #[default]
+    Sobel,
+    /// Canny: smoothing, non-maximum suppression and hysteresis; thin,
+    /// connected lines
+    Canny,
+}
+
+/// Gaussian sigma used to smooth the reference before Canny detection
+const CANNY_SIGMA: f32 = 1.0;
+
+/// Draws cartoon outlines in a single color along luminance edges.
+///
+/// Edges are detected on alpha-premultiplied luminance, so the boundary of
+/// a sprite's visible shape counts as an edge while colors hidden under
+/// transparent pixels don't. Outlines are only drawn on visible pixels.
+pub struct Outline {
+    color: Rgb<u8>,
+    detector: EdgeDetector,
+    threshold: f32,
+    thickness: u32,
+    border: BorderMode,
+}
+
+impl Outline {
+    pub fn new(color: Rgb<u8>) -> Self {
+        Self {
+            color,
+            detector: EdgeDetector::Sobel,
+            threshold: 64.0,
+            thickness: 1,
+            border: BorderMode::Clamp,
+        }
+    }
+
+    pub fn with_detector(mut self, detector: EdgeDetector) -> Self {
+        self.detector = detector;
+        self
+    }
+
+    /// Minimum luminance step (0-255) that counts as an edge
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold.max(0.0);
+        self
+    }
+
+    /// Line thickness in pixels
+    pub fn with_thickness(mut self, thickness: u32) -> Self {
+        self.thickness = thickness.max(1);
+        self
+    }
+
+    pub fn with_border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// Overlay outlines found in `reference` onto `img`. Both images must
+    /// have the same dimensions; pass `img` itself to trace its own edges.
+    pub fn apply(&self, img: &DynamicImage, reference: &DynamicImage) -> DynamicImage {
+        let mut rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+
+        let reference = if reference.width() == width && reference.height() == height {
+            reference
+        } else {
+            log::warn!("Outline reference size differs from the image; tracing the image instead");
+            img
+        };
+
+        let mask = self.dilate(&self.edge_mask(reference), width, height);
+        let mut drawn = 0;
+        for (pixel, &edge) in rgba_img.pixels_mut().zip(&mask) {
+            if edge && pixel[3] > 0 {
+                pixel[0] = self.color[0];
+                pixel[1] = self.color[1];
+                pixel[2] = self.color[2];
+                drawn += 1;
+            }
+        }
+        log::info!("Outline drew {} pixels ({:?})", drawn, self.detector);
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(rgba_img)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba_img).to_rgb8())
+        }
+    }
+
+    /// One flag per pixel, row-major, marking detected edges
+    pub fn edge_mask(&self, reference: &DynamicImage) -> Vec<bool> {
+        match self.detector {
+            EdgeDetector::Sobel => {
+                let rgba = reference.to_rgba8();
+                let luma = Luminance::new(&rgba);
+                (0..luma.len())
+                    .map(|i| luma.gradient(i, self.border).0 >= self.threshold)
+                    .collect()
+            }
+            EdgeDetector::Canny => {
+                let config = BlurConfig::new(CANNY_SIGMA).with_border(self.border);
+                let smoothed = apply_blur(reference, &config).to_rgba8();
+                self.canny(&Luminance::new(&smoothed))
+            }
+        }
+    }
+
+    fn canny(&self, luma: &Luminance) -> Vec<bool> {
+        let (width, height) = (luma.width as i64, luma.height as i64);
+        let gradients: Vec<(f32, f32)> = (0..luma.len())
+            .map(|i| luma.gradient(i, self.border))
+            .collect();
+        let magnitude_at = |x: i64, y: i64| {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                gradients[(y * width + x) as usize].0
+            } else {
+                0.0
+            }
+        };
+
+        // Non-maximum suppression along the quantized gradient direction
+        let mut thinned = vec![0.0f32; luma.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let (magnitude, angle) = gradients[(y * width + x) as usize];
+                if magnitude <= 0.0 {
+                    continue;
+                }
+
+                let degrees = angle.to_degrees().rem_euclid(180.0);
+                let (dx, dy) = match degrees {
+                    d if !(22.5..157.5).contains(&d) => (1, 0),
+                    d if d < 67.5 => (1, 1),
+                    d if d < 112.5 => (0, 1),
+                    _ => (-1, 1),
+                };
+                if magnitude >= magnitude_at(x + dx, y + dy)
+                    && magnitude >= magnitude_at(x - dx, y - dy)
+                {
+                    thinned[(y * width + x) as usize] = magnitude;
+                }
+            }
+        }
+
+        // Hysteresis: keep weak edges only when connected to a strong one
+        let low = self.threshold / 2.0;
+        let mut edges = vec![false; luma.len()];
+        let mut queue: VecDeque<usize> = (0..luma.len())
+            .filter(|&i| thinned[i] >= self.threshold)
+            .collect();
+        for &i in &queue {
+            edges[i] = true;
+        }
+
+        while let Some(i) = queue.pop_front() {
+            let (x, y) = ((i as i64) % width, (i as i64) / width);
+            for ny in (y - 1).max(0)..=(y + 1).min(height - 1) {
+                for nx in (x - 1).max(0)..=(x + 1).min(width - 1) {
+                    let j = (ny * width + nx) as usize;
+                    if !edges[j] && thinned[j] >= low {
+                        edges[j] = true;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Grow the mask with a `thickness` x `thickness` square
+    fn dilate(&self, mask: &[bool], width: u32, height: u32) -> Vec<bool> {
+        if self.thickness <= 1 {
+            return mask.to_vec();
+        }
+
+        let (width, height) = (i64::from(width), i64::from(height));
+        let before = i64::from((self.thickness - 1) / 2);
+        let after = i64::from(self.thickness / 2);
+
+        let mut dilated = vec![false; mask.len()];
+        for y in 0..height {
+            for x in 0..width {
+                if !mask[(y * width + x) as usize] {
+                    continue;
+                }
+                for ny in (y - before).max(0)..=(y + after).min(height - 1) {
+                    for nx in (x - before).max(0)..=(x + after).min(width - 1) {
+                        dilated[(ny * width + nx) as usize] = true;
+                    }
+                }
+            }
+        }
+        dilated
+    }
+}
+
+/// Alpha-premultiplied luminance of an image
+struct Luminance<'a> {
+    width: u32,
+    height: u32,
+    image: &'a RgbaImage,
+}
+
+impl<'a> Luminance<'a> {
+    fn new(image: &'a RgbaImage) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            image,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Sobel gradient magnitude (in luminance steps) and direction at a
+    /// row-major pixel index
+    fn gradient(&self, index: usize, border: BorderMode) -> (f32, f32) {
+        let x = (index % self.width as usize) as i64;
+        let y = (index / self.width as usize) as i64;
+        let (gx, gy) = sobel_gradient(self.image, x, y, border);
+        let (gx, gy) = (gx / 4.0, gy / 4.0);
+        ((gx * gx + gy * gy).sqrt(), gy.atan2(gx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, ImageBuffer, RgbImage, Rgba};
+
+    const INK: Rgb<u8> = Rgb([46, 52, 64]);
+
+    /// White image with a gray square in the middle
+    fn square_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(16, 16, |x, y| {
+            if (5..11).contains(&x) && (5..11).contains(&y) {
+                Rgb([120, 120, 120])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn count_ink(img: &DynamicImage) -> usize {
+        img.to_rgb8().pixels().filter(|p| **p == INK).count()
+    }
+
+    #[test]
+    fn test_sobel_outlines_square() {
+        let img = square_image();
+        let outlined = Outline::new(INK).apply(&img, &img).to_rgb8();
+
+        assert_eq!(*outlined.get_pixel(5, 8), INK);
+        assert_eq!(*outlined.get_pixel(0, 0), Rgb([255, 255, 255]));
+        assert_eq!(*outlined.get_pixel(8, 8), Rgb([120, 120, 120]));
+    }
+
+    #[test]
+    fn test_threshold_filters_weak_edges() {
+        let img = square_image();
+        let outlined = Outline::new(INK).with_threshold(200.0).apply(&img, &img);
+        assert_eq!(count_ink(&outlined), 0);
+    }
+
+    #[test]
+    fn test_canny_is_thinner_than_sobel() {
+        let img = square_image();
+        let sobel = count_ink(&Outline::new(INK).apply(&img, &img));
+        let canny = count_ink(
+            &Outline::new(INK)
+                .with_detector(EdgeDetector::Canny)
+                .apply(&img, &img),
+        );
+
+        assert!(canny > 0);
+        assert!(canny < sobel);
+    }
+
+    #[test]
+    fn test_thickness_grows_lines() {
+        let img = square_image();
+        let thin = count_ink(&Outline::new(INK).apply(&img, &img));
+        let thick = count_ink(&Outline::new(INK).with_thickness(3).apply(&img, &img));
+        assert!(thick > thin);
+    }
+
+    #[test]
+    fn test_outline_follows_sprite_alpha() {
+        // Opaque white disc on a transparent background whose hidden color
+        // differs between halves
+        let img: RgbaImage = ImageBuffer::from_fn(16, 16, |x, y| {
+            let (dx, dy) = (x as i32 - 8, y as i32 - 8);
+            if dx * dx + dy * dy <= 16 {
+                Rgba([255, 255, 255, 255])
+            } else if x < 8 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([255, 0, 0, 0])
+            }
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let outlined = Outline::new(INK).apply(&img, &img).to_rgba8();
+        // Rim of the visible shape is outlined, transparency is untouched
+        assert_eq!(*outlined.get_pixel(8, 4), Rgba([46, 52, 64, 255]));
+        assert_eq!(*outlined.get_pixel(8, 8), Rgba([255, 255, 255, 255]));
+        assert_eq!(*outlined.get_pixel(8, 2), Rgba([255, 0, 0, 0]));
+        assert_eq!(outlined.get_pixel(8, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_mismatched_reference_traces_image() {
+        let img = square_image();
+        let reference = DynamicImage::ImageRgb8(RgbImage::new(3, 3));
+        let outlined = Outline::new(INK).apply(&img, &reference);
+        assert!(count_ink(&outlined) > 0);
+    }
+
+    #[test]
+    fn test_degenerate_images() {
+        for (w, h) in [(0, 0), (1, 1), (1, 4), (4, 1)] {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, Rgb([9, 9, 9])));
+            for detector in [EdgeDetector::Sobel, EdgeDetector::Canny] {
+                let outline = Outline::new(INK).with_detector(detector).with_thickness(3);
+                assert_eq!(outline.apply(&img, &img).dimensions(), (w, h));
+            }
+        }
+    }
+}
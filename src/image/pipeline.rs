@@ -0,0 +1,1063 @@
+use crate::{Config, Palette};
+use image::{DynamicImage, Rgb};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::adjust::Adjustments;
+use super::color_space::Oklab;
+use super::converter::{Dithering, NearestColorConverter, OptimizedConverter, PaletteConverter};
+use super::despeckle::{Despeckle, DespeckleMode};
+use super::effects::{
+    apply_blur, BilateralFilter, BlurConfig, BlurKind, BorderMode, DenoiseMode, Kuwahara,
+    KuwaharaKind, NoiseReduction, RetroDisplay, RetroEffect,
+};
+use super::halftone::{HalftoneConverter, HalftoneShape};
+use super::mosaic::{CellShape, Mosaic};
+use super::outline::{EdgeDetector, Outline};
+use super::palette_color;
+use super::recolor::Recolor;
+use super::tone_map::{ToneRemap, ToneRemapReport};
+
+/// Measurements collected while processing an image, for benchmark and
+/// diagnostic output
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingDiagnostics {
+    pub tone_remap: Option<ToneRemapReport>,
+    /// Wall-clock time spent in each pipeline stage, in execution order
+    pub stage_timings: Vec<(String, Duration)>,
+}
+
+impl ProcessingDiagnostics {
+    /// Total time spent in stages other than palette conversion
+    pub fn effects_time(&self) -> Duration {
+        self.stage_timings
+            .iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "convert" | "halftone" | "recolor"))
+            .map(|(_, time)| *time)
+            .sum()
+    }
+}
+
+/// State shared by all stages of a single pipeline run
+pub struct StageContext<'a> {
+    /// The image the pipeline started with, before any stage ran
+    pub source: &'a DynamicImage,
+    pub palette: &'a Palette,
+    /// Color names selected with `-c` (empty for the whole palette)
+    pub colors: &'a [String],
+    /// Per-pixel nearest-color converter for the selected colors
+    pub converter: &'a NearestColorConverter,
+    pub diagnostics: &'a mut ProcessingDiagnostics,
+}
+
+/// A single image operation in a [`Pipeline`]
+pub trait Effect {
+    /// Short stage name used in logs and timings
+    fn name(&self) -> &'static str;
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage>;
+}
+
+/// An ordered list of effects run on an image
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn Effect>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<E: Effect + 'static>(&mut self, effect: E) {
+        self.stages.push(Box::new(effect));
+    }
+
+    pub fn with_stage<E: Effect + 'static>(mut self, effect: E) -> Self {
+        self.push(effect);
+        self
+    }
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Run every stage in order against `palette`, restricted to the
+    /// `colors` selection when it isn't empty
+    pub fn run(
+        &self,
+        img: DynamicImage,
+        palette: &Palette,
+        colors: &[String],
+    ) -> crate::Result<(DynamicImage, ProcessingDiagnostics)> {
+        let converter = if colors.is_empty() {
+            NearestColorConverter::new(palette)?
+        } else {
+            NearestColorConverter::new_with_selected_colors(palette, colors)?
+        };
+
+        let source = img.clone();
+        let mut diagnostics = ProcessingDiagnostics::default();
+        let mut ctx = StageContext {
+            source: &source,
+            palette,
+            colors,
+            converter: &converter,
+            diagnostics: &mut diagnostics,
+        };
+
+        let mut img = img;
+        for stage in &self.stages {
+            let start = Instant::now();
+            img = stage.apply(img, &mut ctx)?;
+            let elapsed = start.elapsed();
+
+            log::info!("Stage '{}' completed in {:?}", stage.name(), elapsed);
+            ctx.diagnostics
+                .stage_timings
+                .push((stage.name().to_string(), elapsed));
+        }
+
+        Ok((img, diagnostics))
+    }
+}
+
+/// Selective or median noise reduction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DenoiseStage {
+    pub threshold: f32,
+    pub strength: f32,
+    pub radius: u32,
+    pub mode: DenoiseMode,
+    pub border: BorderMode,
+}
+
+impl Default for DenoiseStage {
+    fn default() -> Self {
+        Self {
+            threshold: 30.0,
+            strength: 0.5,
+            radius: 1,
+            mode: DenoiseMode::default(),
+            border: BorderMode::default(),
+        }
+    }
+}
+
+impl DenoiseStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            threshold: config.denoise_threshold,
+            strength: config.denoise_strength,
+            radius: config.denoise_radius,
+            mode: config.denoise_mode,
+            border: config.border_mode,
+        }
+    }
+
+    pub fn noise_reduction(&self) -> NoiseReduction {
+        NoiseReduction::new(self.threshold, self.strength)
+            .with_mode(self.mode)
+            .with_radius(self.radius)
+            .with_border(self.border)
+    }
+}
+
+impl Effect for DenoiseStage {
+    fn name(&self) -> &'static str {
+        "denoise"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(self.noise_reduction().apply(&img))
+    }
+}
+
+impl Effect for Adjustments {
+    fn name(&self) -> &'static str {
+        "adjust"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        if self.is_identity() {
+            return Ok(img);
+        }
+        Ok(Adjustments::apply(self, &img))
+    }
+}
+
+/// Edge-preserving bilateral smoothing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BilateralStage {
+    /// Spatial falloff in pixels
+    pub sigma_space: f32,
+    /// Color falloff in 8-bit RGB distance
+    pub sigma_color: f32,
+    /// Window radius; derived from `sigma_space` when unset
+    pub radius: Option<u32>,
+    pub border: BorderMode,
+}
+
+impl Default for BilateralStage {
+    fn default() -> Self {
+        Self {
+            sigma_space: 3.0,
+            sigma_color: 25.0,
+            radius: None,
+            border: BorderMode::default(),
+        }
+    }
+}
+
+impl BilateralStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            sigma_space: config.bilateral_sigma_space,
+            sigma_color: config.bilateral_sigma_color,
+            radius: None,
+            border: config.border_mode,
+        }
+    }
+
+    pub fn filter(&self) -> BilateralFilter {
+        let filter =
+            BilateralFilter::new(self.sigma_space, self.sigma_color).with_border(self.border);
+        match self.radius {
+            Some(radius) => filter.with_radius(radius),
+            None => filter,
+        }
+    }
+}
+
+impl Effect for BilateralStage {
+    fn name(&self) -> &'static str {
+        "bilateral"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(self.filter().apply(&img))
+    }
+}
+
+/// Painterly Kuwahara smoothing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KuwaharaStage {
+    pub radius: u32,
+    pub kind: KuwaharaKind,
+    /// Sector weighting exponent for the anisotropic variant
+    pub sharpness: f32,
+    pub border: BorderMode,
+}
+
+impl Default for KuwaharaStage {
+    fn default() -> Self {
+        Self {
+            radius: 4,
+            kind: KuwaharaKind::default(),
+            sharpness: 8.0,
+            border: BorderMode::default(),
+        }
+    }
+}
+
+impl KuwaharaStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            radius: config.kuwahara_radius,
+            kind: config.kuwahara_kind,
+            border: config.border_mode,
+            ..Self::default()
+        }
+    }
+
+    pub fn filter(&self) -> Kuwahara {
+        Kuwahara::new(self.radius)
+            .with_kind(self.kind)
+            .with_sharpness(self.sharpness)
+            .with_border(self.border)
+    }
+}
+
+impl Effect for KuwaharaStage {
+    fn name(&self) -> &'static str {
+        "kuwahara"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(self.filter().apply(&img))
+    }
+}
+
+/// Fit image lightness into the palette's lightness range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToneRemapStage {
+    pub strength: f32,
+}
+
+impl Default for ToneRemapStage {
+    fn default() -> Self {
+        Self { strength: 1.0 }
+    }
+}
+
+impl Effect for ToneRemapStage {
+    fn name(&self) -> &'static str {
+        "tone-remap"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        let tone_remap = ToneRemap::new(ctx.converter.palette_colors(), self.strength);
+        let (remapped, report) = tone_remap.apply(&img);
+        log::info!("Tone remap applied: {}", report);
+        ctx.diagnostics.tone_remap = Some(report);
+        Ok(remapped)
+    }
+}
+
+/// Gaussian, box or stack blur. Its position in the pipeline decides
+/// whether it softens the source or the palette-mapped result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlurStage {
+    pub sigma: f32,
+    /// Kernel radius; derived from sigma when unset
+    pub radius: Option<u32>,
+    pub kind: BlurKind,
+    pub border: BorderMode,
+}
+
+impl Default for BlurStage {
+    fn default() -> Self {
+        Self {
+            sigma: BlurConfig::moderate().sigma,
+            radius: None,
+            kind: BlurKind::default(),
+            border: BorderMode::default(),
+        }
+    }
+}
+
+impl BlurStage {
+    pub fn from_config(config: &Config) -> Self {
+        // Without an explicit sigma, blocky images get a lighter blur
+        let default_sigma = match config.pixels_area {
+            Some((w, h)) if w > 2 || h > 2 => BlurConfig::light().sigma,
+            _ => BlurConfig::moderate().sigma,
+        };
+
+        Self {
+            sigma: config.blur_sigma.unwrap_or(default_sigma),
+            radius: config.blur_radius,
+            kind: config.blur_kind,
+            border: config.border_mode,
+        }
+    }
+
+    pub fn blur_config(&self) -> BlurConfig {
+        let blur_config = BlurConfig::new(self.sigma)
+            .with_kind(self.kind)
+            .with_border(self.border);
+
+        match self.radius {
+            Some(radius) => blur_config.with_radius(radius),
+            None => blur_config,
+        }
+    }
+}
+
+impl Effect for BlurStage {
+    fn name(&self) -> &'static str {
+        "blur"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(apply_blur(&img, &self.blur_config()))
+    }
+}
+
+/// Map the image onto the palette, with optional block averaging or
+/// dithering
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConvertStage {
+    /// Dithering for per-pixel conversion; block averaging is skipped when set
+    pub dither: Dithering,
+    pub pixels_area: Option<(u32, u32)>,
+    pub averaging: bool,
+    pub cell_shape: CellShape,
+    /// Voronoi seed jitter, 0.0-1.0
+    pub cell_jitter: f32,
+    pub cell_seed: u64,
+    /// Palette color name or hex code for lines between cells
+    pub grout_color: Option<String>,
+    pub grout_width: u32,
+}
+
+impl Default for ConvertStage {
+    fn default() -> Self {
+        Self {
+            dither: Dithering::None,
+            pixels_area: None,
+            averaging: true,
+            cell_shape: CellShape::default(),
+            cell_jitter: 1.0,
+            cell_seed: 0,
+            grout_color: None,
+            grout_width: 1,
+        }
+    }
+}
+
+/// Mosaic cell spacing when no pixel area is given
+const DEFAULT_CELL_SIZE: u32 = 8;
+
+impl ConvertStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            dither: Dithering::None,
+            pixels_area: config.pixels_area,
+            averaging: !config.disable_avg_pixels,
+            cell_shape: config.cell_shape,
+            cell_jitter: config.cell_jitter,
+            cell_seed: config.cell_seed,
+            grout_color: config.grout_color.clone(),
+            grout_width: config.grout_width,
+        }
+    }
+
+    fn uses_cells(&self) -> bool {
+        self.cell_shape != CellShape::Square || self.grout_color.is_some()
+    }
+
+    fn create_converter(&self, ctx: &StageContext) -> crate::Result<Box<dyn PaletteConverter>> {
+        if self.uses_cells() {
+            let cell_size = self
+                .pixels_area
+                .unwrap_or((DEFAULT_CELL_SIZE, DEFAULT_CELL_SIZE));
+            let mosaic = Mosaic::new(self.cell_shape, cell_size)
+                .with_jitter(self.cell_jitter)
+                .with_seed(self.cell_seed);
+
+            let mut converter =
+                OptimizedConverter::new(ctx.palette, ctx.colors, Some(cell_size), true)?
+                    .with_mosaic(mosaic);
+            if let Some(name) = &self.grout_color {
+                converter = converter.with_grout(
+                    palette_color(ctx.palette, ctx.colors, name)?,
+                    self.grout_width,
+                );
+            }
+            Ok(Box::new(converter))
+        } else if self.pixels_area.is_some() || self.averaging {
+            // Use optimized converter for advanced features
+            let converter =
+                OptimizedConverter::new(ctx.palette, ctx.colors, self.pixels_area, self.averaging)?;
+            Ok(Box::new(converter))
+        } else {
+            // Use basic nearest color converter
+            let converter = if ctx.colors.is_empty() {
+                NearestColorConverter::new(ctx.palette)?
+            } else {
+                NearestColorConverter::new_with_selected_colors(ctx.palette, ctx.colors)?
+            };
+            Ok(Box::new(converter))
+        }
+    }
+}
+
+impl Effect for ConvertStage {
+    fn name(&self) -> &'static str {
+        "convert"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        if self.dither != Dithering::None {
+            return Ok(ctx.converter.convert_image_dithered(&img, self.dither));
+        }
+
+        let converter = self.create_converter(ctx)?;
+        Ok(converter.convert_image(&img))
+    }
+}
+
+/// Map the image onto the palette as a halftone dot screen, an alternative
+/// to the convert stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HalftoneStage {
+    pub shape: HalftoneShape,
+    /// Screen angle in degrees
+    pub angle: f32,
+    /// Cell size in pixels
+    pub cell_size: f32,
+    /// Palette color name or hex code; the first color when unset
+    pub background: Option<String>,
+    pub anti_alias: bool,
+}
+
+impl Default for HalftoneStage {
+    fn default() -> Self {
+        Self {
+            shape: HalftoneShape::default(),
+            angle: 45.0,
+            cell_size: 8.0,
+            background: None,
+            anti_alias: true,
+        }
+    }
+}
+
+impl HalftoneStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            shape: config.halftone_shape,
+            angle: config.halftone_angle,
+            cell_size: config.halftone_cell_size,
+            background: config.halftone_background.clone(),
+            anti_alias: true,
+        }
+    }
+
+    pub fn converter(
+        &self,
+        palette: &Palette,
+        colors: &[String],
+    ) -> crate::Result<HalftoneConverter> {
+        Ok(
+            HalftoneConverter::new(palette, colors, self.background.as_deref())?
+                .with_shape(self.shape)
+                .with_angle(self.angle)
+                .with_cell_size(self.cell_size)
+                .with_anti_alias(self.anti_alias),
+        )
+    }
+}
+
+impl Effect for HalftoneStage {
+    fn name(&self) -> &'static str {
+        "halftone"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        let converter = self.converter(ctx.palette, ctx.colors)?;
+        Ok(converter.convert_image(&img))
+    }
+}
+
+/// Give each dominant image color its own palette color, an alternative to
+/// the convert stage for flat illustrations and logos
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecolorStage {
+    /// Dominant colors to extract; capped at the palette size
+    pub clusters: usize,
+    /// Weight of keeping the source colors' dark-to-light order
+    pub lightness_weight: f32,
+    /// Share of the shading inside each color region that is kept; above
+    /// 0.0 the output is no longer limited to palette colors
+    pub shading: f32,
+}
+
+impl Default for RecolorStage {
+    fn default() -> Self {
+        Self {
+            clusters: 8,
+            lightness_weight: 0.5,
+            shading: 1.0,
+        }
+    }
+}
+
+impl RecolorStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            clusters: config.recolor_clusters,
+            lightness_weight: config.recolor_lightness_weight,
+            shading: config.recolor_shading,
+        }
+    }
+
+    pub fn recolor(&self) -> Recolor {
+        Recolor::new(self.clusters)
+            .with_lightness_weight(self.lightness_weight)
+            .with_shading(self.shading)
+    }
+}
+
+impl Effect for RecolorStage {
+    fn name(&self) -> &'static str {
+        "recolor"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        self.recolor().recolor(&img, ctx.converter.palette_colors())
+    }
+}
+
+/// Post-quantization cleanup of small isolated regions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DespeckleStage {
+    /// Regions smaller than this many pixels are merged away
+    pub min_region: u32,
+    pub mode: DespeckleMode,
+    /// Majority filter window radius
+    pub radius: u32,
+}
+
+impl Default for DespeckleStage {
+    fn default() -> Self {
+        Self {
+            min_region: 4,
+            mode: DespeckleMode::default(),
+            radius: 1,
+        }
+    }
+}
+
+impl DespeckleStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_region: config.despeckle_min_region,
+            mode: config.despeckle_mode,
+            radius: config.despeckle_radius,
+        }
+    }
+
+    pub fn despeckle(&self) -> Despeckle {
+        Despeckle::new(self.min_region)
+            .with_mode(self.mode)
+            .with_radius(self.radius)
+    }
+}
+
+impl Effect for DespeckleStage {
+    fn name(&self) -> &'static str {
+        "despeckle"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(self.despeckle().apply(&img, ctx.converter.palette_colors()))
+    }
+}
+
+/// Cartoon outlines in a palette color
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutlineStage {
+    /// Palette color name or hex code; the darkest palette color when unset
+    pub color: Option<String>,
+    pub detector: EdgeDetector,
+    /// Minimum luminance step (0-255) that counts as an edge
+    pub threshold: f32,
+    pub thickness: u32,
+    /// Trace edges in the pipeline's input rather than the current image;
+    /// off by default so outlines follow the quantized regions
+    pub from_source: bool,
+    pub border: BorderMode,
+}
+
+impl Default for OutlineStage {
+    fn default() -> Self {
+        Self {
+            color: None,
+            detector: EdgeDetector::default(),
+            threshold: 64.0,
+            thickness: 1,
+            from_source: false,
+            border: BorderMode::default(),
+        }
+    }
+}
+
+impl OutlineStage {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            color: config.outline_color.clone(),
+            detector: config.outline_detector,
+            threshold: config.outline_threshold,
+            thickness: config.outline_thickness,
+            from_source: false,
+            border: config.border_mode,
+        }
+    }
+
+    /// The chosen palette color, or the darkest one
+    fn resolve_color(&self, ctx: &StageContext) -> crate::Result<Rgb<u8>> {
+        let Some(name) = &self.color else {
+            return ctx
+                .converter
+                .palette_colors()
+                .iter()
+                .copied()
+                .min_by(|a, b| Oklab::from_rgb(*a).l.total_cmp(&Oklab::from_rgb(*b).l))
+                .ok_or_else(|| {
+                    crate::RustBucketError::ColorNotFound(format!(
+                        "Palette '{}' has no colors to draw outlines with",
+                        ctx.palette.name
+                    ))
+                });
+        };
+
+        palette_color(ctx.palette, ctx.colors, name)
+    }
+}
+
+impl Effect for OutlineStage {
+    fn name(&self) -> &'static str {
+        "outline"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        let outline = Outline::new(self.resolve_color(ctx)?)
+            .with_detector(self.detector)
+            .with_threshold(self.threshold)
+            .with_thickness(self.thickness)
+            .with_border(self.border);
+
+        let reference = if self.from_source { ctx.source } else { &img };
+        Ok(outline.apply(&img, reference))
+    }
+}
+
+/// Simulated CRT or LCD display, usually followed by a resnap stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetroStage {
+    pub effect: RetroEffect,
+    /// Effect intensity, 0.0-1.0
+    pub strength: f32,
+    /// Scanline period, mask triad, LCD cell or bloom radius in pixels
+    pub size: u32,
+}
+
+impl Default for RetroStage {
+    fn default() -> Self {
+        Self {
+            effect: RetroEffect::default(),
+            strength: 0.5,
+            size: 3,
+        }
+    }
+}
+
+impl RetroStage {
+    pub fn from_config(config: &Config, effect: RetroEffect) -> Self {
+        Self {
+            effect,
+            strength: config.retro_strength,
+            size: config.retro_size,
+        }
+    }
+
+    pub fn display(&self) -> RetroDisplay {
+        RetroDisplay::new(self.effect)
+            .with_strength(self.strength)
+            .with_size(self.size)
+    }
+}
+
+impl Effect for RetroStage {
+    fn name(&self) -> &'static str {
+        "retro"
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(self.display().apply(&img))
+    }
+}
+
+/// Snap every pixel back to the palette, e.g. after a blur
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResnapStage {
+    pub dither: Dithering,
+}
+
+impl Effect for ResnapStage {
+    fn name(&self) -> &'static str {
+        "resnap"
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+        Ok(ctx.converter.convert_image_dithered(&img, self.dither))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use image::{ImageBuffer, Rgb, RgbImage};
+    use std::path::PathBuf;
+
+    fn create_test_palette() -> Palette {
+        Palette {
+            name: "test".to_string(),
+            path: PathBuf::from("test"),
+            colors: vec![
+                Color {
+                    name: "Black".to_string(),
+                    hex: "#000000".to_string(),
+                },
+                Color {
+                    name: "White".to_string(),
+                    hex: "#FFFFFF".to_string(),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn create_test_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(12, 12, |x, _y| {
+            let v = (x * 21) as u8;
+            Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    struct Invert;
+
+    impl Effect for Invert {
+        fn name(&self) -> &'static str {
+            "invert"
+        }
+
+        fn apply(&self, img: DynamicImage, _ctx: &mut StageContext) -> crate::Result<DynamicImage> {
+            let mut img = img;
+            img.invert();
+            Ok(img)
+        }
+    }
+
+    #[test]
+    fn test_empty_pipeline_is_identity() {
+        let pipeline = Pipeline::new();
+        assert!(pipeline.is_empty());
+
+        let img = create_test_image();
+        let (result, diagnostics) = pipeline
+            .run(img.clone(), &create_test_palette(), &[])
+            .unwrap();
+        assert_eq!(result.to_rgb8(), img.to_rgb8());
+        assert!(diagnostics.stage_timings.is_empty());
+    }
+
+    #[test]
+    fn test_custom_effect_and_stage_order() {
+        let pipeline = Pipeline::new()
+            .with_stage(Invert)
+            .with_stage(ConvertStage::default());
+        assert_eq!(pipeline.stage_names(), vec!["invert", "convert"]);
+
+        let (result, diagnostics) = pipeline
+            .run(create_test_image(), &create_test_palette(), &[])
+            .unwrap();
+        let result = result.to_rgb8();
+
+        // Dark left edge becomes white after inverting
+        assert_eq!(*result.get_pixel(0, 0), Rgb([255, 255, 255]));
+        assert_eq!(diagnostics.stage_timings.len(), 2);
+        assert_eq!(diagnostics.stage_timings[1].0, "convert");
+    }
+
+    #[test]
+    fn test_blur_then_resnap_stays_in_palette() {
+        let pipeline = Pipeline::new()
+            .with_stage(ConvertStage::default())
+            .with_stage(BlurStage::default())
+            .with_stage(ResnapStage {
+                dither: Dithering::Ordered,
+            });
+
+        let (result, _) = pipeline
+            .run(create_test_image(), &create_test_palette(), &[])
+            .unwrap();
+        assert!(result
+            .to_rgb8()
+            .pixels()
+            .all(|p| *p == Rgb([0, 0, 0]) || *p == Rgb([255, 255, 255])));
+    }
+
+    #[test]
+    fn test_despeckle_after_convert() {
+        let img: RgbImage = ImageBuffer::from_fn(12, 12, |x, y| {
+            if (x, y) == (4, 4) {
+                Rgb([10, 10, 10])
+            } else {
+                Rgb([240, 240, 240])
+            }
+        });
+
+        let pipeline = Pipeline::new()
+            .with_stage(ConvertStage::default())
+            .with_stage(DespeckleStage::default());
+        let (result, _) = pipeline
+            .run(DynamicImage::ImageRgb8(img), &create_test_palette(), &[])
+            .unwrap();
+        assert!(result
+            .to_rgb8()
+            .pixels()
+            .all(|p| *p == Rgb([255, 255, 255])));
+    }
+
+    #[test]
+    fn test_convert_with_mosaic_grout() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(16, 16, Rgb([240, 240, 240])));
+        let palette = create_test_palette();
+
+        let stage = ConvertStage {
+            cell_shape: CellShape::Hexagon,
+            pixels_area: Some((6, 6)),
+            grout_color: Some("black".to_string()),
+            ..ConvertStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &[])
+            .unwrap();
+        let result = result.to_rgb8();
+        let black = result.pixels().filter(|p| **p == Rgb([0, 0, 0])).count();
+        assert!(black > 0 && black < 16 * 16 / 2);
+
+        // A hex grout color is snapped to the palette
+        let stage = ConvertStage {
+            cell_shape: CellShape::Hexagon,
+            pixels_area: Some((6, 6)),
+            grout_color: Some("#202428".to_string()),
+            ..ConvertStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &[])
+            .unwrap();
+        assert!(result
+            .to_rgb8()
+            .pixels()
+            .all(|p| *p == Rgb([0, 0, 0]) || *p == Rgb([255, 255, 255])));
+
+        // ...and to the selected colors when `-c` is given
+        let stage = ConvertStage {
+            cell_shape: CellShape::Hexagon,
+            pixels_area: Some((6, 6)),
+            grout_color: Some("#202428".to_string()),
+            ..ConvertStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &["White".to_string()])
+            .unwrap();
+        assert!(result
+            .to_rgb8()
+            .pixels()
+            .all(|p| *p == Rgb([255, 255, 255])));
+
+        // Square cells with grout and no pixel area use the default size
+        let stage = ConvertStage {
+            grout_color: Some("black".to_string()),
+            ..ConvertStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &[])
+            .unwrap();
+        let result = result.to_rgb8();
+        let black = result.pixels().filter(|p| **p == Rgb([0, 0, 0])).count();
+        assert!(black > 0 && black < 16 * 16 / 2);
+
+        let stage = ConvertStage {
+            grout_color: Some("purple".to_string()),
+            ..ConvertStage::default()
+        };
+        assert!(Pipeline::new()
+            .with_stage(stage)
+            .run(img, &palette, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_outline_color_resolution() {
+        let img: RgbImage = ImageBuffer::from_fn(12, 12, |x, _y| {
+            if x < 6 {
+                Rgb([250, 250, 250])
+            } else {
+                Rgb([5, 5, 5])
+            }
+        });
+        let img = DynamicImage::ImageRgb8(img);
+        let palette = create_test_palette();
+
+        // Outline in white along the edge, traced from the converted image
+        // and then from the unconverted source
+        for from_source in [false, true] {
+            let stage = OutlineStage {
+                color: Some("white".to_string()),
+                from_source,
+                ..OutlineStage::default()
+            };
+            let pipeline = Pipeline::new()
+                .with_stage(ConvertStage::default())
+                .with_stage(stage);
+            let (result, _) = pipeline.run(img.clone(), &palette, &[]).unwrap();
+            assert_eq!(*result.to_rgb8().get_pixel(6, 5), Rgb([255, 255, 255]));
+        }
+
+        let stage = OutlineStage {
+            color: Some("#123456".to_string()),
+            ..OutlineStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &[])
+            .unwrap();
+        // Hex codes snap to the nearest palette color
+        assert_eq!(*result.to_rgb8().get_pixel(6, 5), Rgb([0, 0, 0]));
+
+        // ...or to the nearest selected color when `-c` is given
+        let stage = OutlineStage {
+            color: Some("#123456".to_string()),
+            ..OutlineStage::default()
+        };
+        let (result, _) = Pipeline::new()
+            .with_stage(stage)
+            .run(img.clone(), &palette, &["White".to_string()])
+            .unwrap();
+        assert_eq!(*result.to_rgb8().get_pixel(6, 5), Rgb([255, 255, 255]));
+
+        let stage = OutlineStage {
+            color: Some("nord0".to_string()),
+            ..OutlineStage::default()
+        };
+        assert!(Pipeline::new()
+            .with_stage(stage)
+            .run(img, &palette, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_stage_uses_selected_colors() {
+        let colors = vec!["White".to_string()];
+        let pipeline = Pipeline::new().with_stage(ResnapStage::default());
+
+        let (result, _) = pipeline
+            .run(create_test_image(), &create_test_palette(), &colors)
+            .unwrap();
+        assert!(result
+            .to_rgb8()
+            .pixels()
+            .all(|p| *p == Rgb([255, 255, 255])));
+    }
+}
@@ -1,16 +1,26 @@
 use crate::{Config, Palette};
 use image::DynamicImage;
+use std::sync::OnceLock;
 
-use super::converter::{NearestColorConverter, OptimizedConverter, PaletteConverter};
-use super::effects::{apply_gaussian_blur, BlurConfig};
+use super::effects::RetroEffect;
+use super::effects::{BlurConfig, KuwaharaKind, NoiseReduction};
+use super::mosaic::CellShape;
+use super::outline::EdgeDetector;
+use super::pipeline::{BlurStage, DenoiseStage, Pipeline, ProcessingDiagnostics};
+use super::recipe::Recipe;
 
 pub struct ImageProcessor {
     config: Config,
+    /// Loaded on first use, so a recipe file is read once per processor
+    recipe: OnceLock<Recipe>,
 }
 
 impl ImageProcessor {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            recipe: OnceLock::new(),
+        }
     }
 
     pub fn process_image(
@@ -18,64 +28,64 @@ impl ImageProcessor {
         img: DynamicImage,
         palette: &Palette,
     ) -> crate::Result<DynamicImage> {
-        log::info!("Starting image processing pipeline");
+        self.process_image_with_diagnostics(img, palette)
+            .map(|(processed_img, _)| processed_img)
+    }
 
-        // Step 1: Create the appropriate converter
-        let converter = self.create_converter(palette)?;
+    pub fn process_image_with_diagnostics(
+        &self,
+        img: DynamicImage,
+        palette: &Palette,
+    ) -> crate::Result<(DynamicImage, ProcessingDiagnostics)> {
+        log::info!("Starting image processing pipeline");
 
-        // Step 2: Convert image to palette colors
-        let mut processed_img = converter.convert_image(&img);
-        log::info!("Image converted to palette colors");
+        // Step 1: Build the stage list from the recipe file or the CLI flags
+        let pipeline = self.pipeline()?;
+        log::info!("Pipeline stages: {}", pipeline.stage_names().join(" -> "));
 
-        // Step 3: Apply blur if enabled
-        if self.config.enable_blur {
-            processed_img = self.apply_blur(processed_img)?;
-            log::info!("Gaussian blur applied");
-        }
+        // Step 2: Run every stage in order
+        let (mut processed_img, diagnostics) =
+            pipeline.run(img.clone(), palette, &self.config.colors)?;
 
-        // Step 4: Ensure output format matches input format
+        // Step 3: Ensure output format matches input format
         processed_img = self.preserve_format(processed_img, &img);
 
         log::info!("Image processing pipeline completed");
-        Ok(processed_img)
-    }
-
-    fn create_converter(&self, palette: &Palette) -> crate::Result<Box<dyn PaletteConverter>> {
-        if self.config.pixels_area.is_some() || !self.config.disable_avg_pixels {
-            // Use optimized converter for advanced features
-            let converter = OptimizedConverter::new(
-                palette,
-                &self.config.colors,
-                self.config.pixels_area,
-                !self.config.disable_avg_pixels,
-            )?;
-            Ok(Box::new(converter))
-        } else {
-            // Use basic nearest color converter
-            let converter = if self.config.colors.is_empty() {
-                NearestColorConverter::new(palette)?
-            } else {
-                NearestColorConverter::new_with_selected_colors(palette, &self.config.colors)?
-            };
-            Ok(Box::new(converter))
-        }
+        Ok((processed_img, diagnostics))
     }
 
-    fn apply_blur(&self, img: DynamicImage) -> crate::Result<DynamicImage> {
-        // Use enhanced blur configuration
-        let blur_config = if let Some((w, h)) = self.config.pixels_area {
-            // Use lighter blur for pixelated images
-            if w > 2 || h > 2 {
-                BlurConfig::light()
-            } else {
-                BlurConfig::moderate()
+    /// The recipe to run: loaded from `--recipe` when given, otherwise
+    /// built from the effect flags
+    pub fn recipe(&self) -> crate::Result<&Recipe> {
+        if let Some(recipe) = self.recipe.get() {
+            return Ok(recipe);
+        }
+        let recipe = match &self.config.recipe {
+            Some(path) => Recipe::load(path)?,
+            None => {
+                let recipe = Recipe::from_config(&self.config);
+                for warning in recipe.warnings() {
+                    log::warn!("{}", warning);
+                }
+                recipe
             }
-        } else {
-            BlurConfig::moderate()
         };
+        Ok(self.recipe.get_or_init(|| recipe))
+    }
+
+    pub fn pipeline(&self) -> crate::Result<Pipeline> {
+        self.recipe().map(|recipe| recipe.to_pipeline())
+    }
+
+    /// Blur settings from the configuration, falling back to a light
+    /// Gaussian for pixel areas above 2 and a moderate one otherwise
+    pub fn blur_config(&self) -> BlurConfig {
+        BlurStage::from_config(&self.config).blur_config()
+    }
 
-        let blurred = apply_gaussian_blur(&img, &blur_config);
-        Ok(blurred)
+    /// Noise reduction filter configured from the denoise settings
+    pub fn noise_reduction(&self) -> NoiseReduction {
+        DenoiseStage::from_config(&self.config).noise_reduction()
     }
 
     fn preserve_format(
@@ -96,6 +106,18 @@ impl ImageProcessor {
         self.config.enable_blur
     }
 
+    pub fn should_denoise(&self) -> bool {
+        self.config.denoise
+    }
+
+    pub fn should_tone_remap(&self) -> bool {
+        self.config.tone_remap
+    }
+
+    pub fn should_pre_blur(&self) -> bool {
+        self.config.pre_blur
+    }
+
     pub fn should_use_averaging(&self) -> bool {
         !self.config.disable_avg_pixels
     }
@@ -114,6 +136,15 @@ impl ImagePipeline {
         palette: &Palette,
         config: &Config,
     ) -> crate::Result<()> {
+        Self::process_file_with_diagnostics(input_path, output_path, palette, config).map(|_| ())
+    }
+
+    pub fn process_file_with_diagnostics(
+        input_path: &std::path::Path,
+        output_path: &std::path::Path,
+        palette: &Palette,
+        config: &Config,
+    ) -> crate::Result<ProcessingDiagnostics> {
         log::info!(
             "Processing image: {} -> {}",
             input_path.display(),
@@ -129,17 +160,29 @@ impl ImagePipeline {
             img.color()
         );
 
+        Self::process_loaded_with_diagnostics(img, output_path, palette, config)
+    }
+
+    /// Like [`Self::process_file_with_diagnostics`] for an image that is
+    /// already decoded
+    pub fn process_loaded_with_diagnostics(
+        img: DynamicImage,
+        output_path: &std::path::Path,
+        palette: &Palette,
+        config: &Config,
+    ) -> crate::Result<ProcessingDiagnostics> {
         // Create processor
         let processor = ImageProcessor::new(config.clone());
 
         // Process image
-        let processed_img = processor.process_image(img, palette)?;
+        let (processed_img, diagnostics) =
+            processor.process_image_with_diagnostics(img, palette)?;
 
         // Save result
         super::save_image(&processed_img, output_path)?;
 
         log::info!("Image processing completed successfully");
-        Ok(())
+        Ok(diagnostics)
     }
 
     pub fn estimate_processing_time(img_width: u32, img_height: u32, config: &Config) -> f64 {
@@ -156,11 +199,87 @@ impl ImagePipeline {
             }
         }
 
+        // Mosaic cells are looked up per pixel; Voronoi compares 9 seeds
+        time_estimate *= match config.cell_shape {
+            CellShape::Square => 1.0,
+            CellShape::Hexagon | CellShape::Triangle => 1.3,
+            CellShape::Voronoi => 2.0,
+        };
+
         // Add time for blur
         if config.enable_blur {
             time_estimate *= 1.5; // 50% more time for blur
         }
 
+        if config.pre_blur {
+            time_estimate *= 1.5;
+        }
+
+        if config.tone_remap {
+            time_estimate *= 1.4;
+        }
+
+        if !config.adjustments.is_identity() {
+            time_estimate *= 1.3;
+        }
+
+        if config.denoise {
+            let window = (2.0 * f64::from(config.denoise_radius) + 1.0).powi(2);
+            time_estimate *= 1.0 + window * 0.1;
+        }
+
+        if config.bilateral {
+            let window = f64::from((2.0 * config.bilateral_sigma_space).ceil() * 2.0 + 1.0);
+            time_estimate *= 1.0 + window * window * 0.1;
+        }
+
+        if config.kuwahara {
+            let window = (f64::from(config.kuwahara_radius) + 1.0).powi(2);
+            time_estimate *= match config.kuwahara_kind {
+                KuwaharaKind::Classic => 1.0 + window * 0.4,
+                KuwaharaKind::Anisotropic => 1.0 + window * 1.2,
+            };
+        }
+
+        if config.outline {
+            time_estimate *= match config.outline_detector {
+                EdgeDetector::Sobel => 1.5,
+                EdgeDetector::Canny => 2.5,
+            };
+        }
+
+        // Halftone replaces conversion with a cell averaging pass and a
+        // per-pixel dot pass
+        if config.halftone {
+            time_estimate *= 1.5;
+        }
+
+        // Recolor clusters the image with k-means before remapping
+        if config.recolor {
+            time_estimate *= 2.0;
+        }
+
+        if config.despeckle {
+            time_estimate *= 1.5;
+        }
+
+        for effect in &config.retro {
+            time_estimate *= match effect {
+                RetroEffect::Bloom => 2.0,
+                RetroEffect::Barrel => 1.4,
+                _ => 1.1,
+            };
+        }
+
+        if !config.retro.is_empty() && config.retro_resnap {
+            time_estimate *= 2.0;
+        }
+
+        // Re-snapping is a second full palette lookup
+        if config.enable_blur && config.blur_resnap {
+            time_estimate *= 2.0;
+        }
+
         time_estimate
     }
 }
@@ -168,6 +287,7 @@ impl ImagePipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::image::{BlurKind, DenoiseMode, Dithering};
     use crate::{Color, Palette};
     use image::{ImageBuffer, Rgb};
     use std::path::PathBuf;
@@ -183,6 +303,7 @@ mod tests {
             pixels_area: None,
             quiet_mode: false,
             benchmark: false,
+            ..Default::default()
         }
     }
 
@@ -204,6 +325,7 @@ mod tests {
                     hex: "#0000FF".to_string(),
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -250,6 +372,162 @@ mod tests {
         assert!(processor.should_apply_blur());
     }
 
+    #[test]
+    fn test_blur_config_from_settings() {
+        let mut config = create_test_config();
+        config.blur_sigma = Some(2.0);
+        config.blur_kind = BlurKind::Stack;
+
+        let blur_config = ImageProcessor::new(config.clone()).blur_config();
+        assert_eq!(blur_config.sigma, 2.0);
+        assert_eq!(blur_config.kind, BlurKind::Stack);
+
+        config.blur_radius = Some(7);
+        let blur_config = ImageProcessor::new(config).blur_config();
+        assert_eq!(blur_config.radius, 7);
+    }
+
+    #[test]
+    fn test_default_blur_sigma_follows_pixel_area() {
+        let mut config = create_test_config();
+        config.pixels_area = Some((2, 2));
+        let blur_config = ImageProcessor::new(config.clone()).blur_config();
+        assert_eq!(blur_config.sigma, BlurConfig::moderate().sigma);
+
+        config.pixels_area = Some((3, 2));
+        let blur_config = ImageProcessor::new(config).blur_config();
+        assert_eq!(blur_config.sigma, BlurConfig::light().sigma);
+    }
+
+    #[test]
+    fn test_blur_resnap_keeps_palette_colors() {
+        let mut config = create_test_config();
+        config.enable_blur = true;
+        config.blur_resnap = true;
+        config.dithering = Dithering::FloydSteinberg;
+
+        let processor = ImageProcessor::new(config);
+        let processed = processor
+            .process_image(create_test_image(), &create_test_palette())
+            .unwrap()
+            .to_rgb8();
+
+        let allowed = [Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255])];
+        assert!(processed.pixels().all(|p| allowed.contains(p)));
+    }
+
+    #[test]
+    fn test_pre_blur_only_stays_in_palette() {
+        let mut config = create_test_config();
+        config.pre_blur = true;
+
+        let processor = ImageProcessor::new(config);
+        assert!(processor.should_pre_blur());
+        assert!(!processor.should_apply_blur());
+
+        let processed = processor
+            .process_image(create_test_image(), &create_test_palette())
+            .unwrap()
+            .to_rgb8();
+        let allowed = [Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255])];
+        assert!(processed.pixels().all(|p| allowed.contains(p)));
+    }
+
+    #[test]
+    fn test_denoise_stage() {
+        let mut config = create_test_config();
+        config.denoise = true;
+        config.denoise_mode = DenoiseMode::Median;
+
+        let processor = ImageProcessor::new(config);
+        assert!(processor.should_denoise());
+
+        let result = processor.process_image(create_test_image(), &create_test_palette());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_adjustments_run_before_conversion() {
+        let mut config = create_test_config();
+        config.adjustments.saturation = 0.0;
+
+        // Without color, red and green halves collapse onto the same palette color
+        let processed = ImageProcessor::new(config)
+            .process_image(create_test_image(), &create_test_palette())
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(processed.get_pixel(0, 0), processed.get_pixel(9, 0));
+    }
+
+    #[test]
+    fn test_tone_remap_diagnostics() {
+        let mut config = create_test_config();
+        config.tone_remap = true;
+        config.tone_remap_strength = 0.5;
+
+        let processor = ImageProcessor::new(config);
+        assert!(processor.should_tone_remap());
+
+        let (_, diagnostics) = processor
+            .process_image_with_diagnostics(create_test_image(), &create_test_palette())
+            .unwrap();
+        let report = diagnostics.tone_remap.expect("tone remap report");
+        assert_eq!(report.strength, 0.5);
+        assert!(report.palette_range.0 < report.palette_range.1);
+    }
+
+    #[test]
+    fn test_recipe_file_overrides_flags() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let recipe_path = temp_dir.path().join("recipe.toml");
+        std::fs::write(
+            &recipe_path,
+            "[[stages]]\ntype = \"convert\"\naveraging = false\n\n[[stages]]\ntype = \"blur\"\nkind = \"box\"\n\n[[stages]]\ntype = \"resnap\"\n",
+        )
+        .unwrap();
+
+        let mut config = create_test_config();
+        config.denoise = true;
+        config.recipe = Some(recipe_path.clone());
+
+        let processor = ImageProcessor::new(config);
+        assert_eq!(
+            processor.pipeline().unwrap().stage_names(),
+            vec!["convert", "blur", "resnap"]
+        );
+
+        let (processed, diagnostics) = processor
+            .process_image_with_diagnostics(create_test_image(), &create_test_palette())
+            .unwrap();
+        assert_eq!(diagnostics.stage_timings.len(), 3);
+
+        // The recipe is read once, so later images don't touch the file
+        std::fs::remove_file(&recipe_path).unwrap();
+        assert!(processor
+            .process_image(create_test_image(), &create_test_palette())
+            .is_ok());
+
+        let allowed = [Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255])];
+        assert!(processed.to_rgb8().pixels().all(|p| allowed.contains(p)));
+    }
+
+    #[test]
+    fn test_processor_is_shareable_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ImageProcessor>();
+    }
+
+    #[test]
+    fn test_missing_recipe_file_is_an_error() {
+        let mut config = create_test_config();
+        config.recipe = Some(PathBuf::from("does_not_exist.toml"));
+
+        let processor = ImageProcessor::new(config);
+        assert!(processor
+            .process_image(create_test_image(), &create_test_palette())
+            .is_err());
+    }
+
     #[test]
     fn test_pixel_area_configuration() {
         let mut config = create_test_config();
@@ -269,5 +547,14 @@ mod tests {
         blur_config.enable_blur = true;
         let blur_time = ImagePipeline::estimate_processing_time(100, 100, &blur_config);
         assert!(blur_time > time);
+
+        // Huge radii from a library caller must not overflow
+        let mut wide_config = create_test_config();
+        wide_config.denoise = true;
+        wide_config.denoise_radius = u32::MAX;
+        wide_config.kuwahara = true;
+        wide_config.kuwahara_radius = u32::MAX;
+        let wide_time = ImagePipeline::estimate_processing_time(100, 100, &wide_config);
+        assert!(wide_time.is_finite() && wide_time > time);
     }
 }
@@ -0,0 +1,733 @@
+use crate::{Config, RustBucketError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::adjust::Adjustments;
+use super::pipeline::{
+    BilateralStage, BlurStage, ConvertStage, DenoiseStage, DespeckleStage, HalftoneStage,
+    KuwaharaStage, OutlineStage, Pipeline, RecolorStage, ResnapStage, RetroStage, ToneRemapStage,
+};
+use super::{MAX_RADIUS, MAX_SIGMA};
+
+/// One `[[stages]]` entry of a recipe file, selected by its `type` key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum StageSpec {
+    Denoise(DenoiseStage),
+    Adjust(Adjustments),
+    Bilateral(BilateralStage),
+    Kuwahara(KuwaharaStage),
+    ToneRemap(ToneRemapStage),
+    Blur(BlurStage),
+    Convert(ConvertStage),
+    Halftone(HalftoneStage),
+    Recolor(RecolorStage),
+    Despeckle(DespeckleStage),
+    Outline(OutlineStage),
+    Retro(RetroStage),
+    Resnap(ResnapStage),
+}
+
+impl StageSpec {
+    /// The stage's `type` key
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            StageSpec::Denoise(_) => "denoise",
+            StageSpec::Adjust(_) => "adjust",
+            StageSpec::Bilateral(_) => "bilateral",
+            StageSpec::Kuwahara(_) => "kuwahara",
+            StageSpec::ToneRemap(_) => "tone-remap",
+            StageSpec::Blur(_) => "blur",
+            StageSpec::Convert(_) => "convert",
+            StageSpec::Halftone(_) => "halftone",
+            StageSpec::Recolor(_) => "recolor",
+            StageSpec::Despeckle(_) => "despeckle",
+            StageSpec::Outline(_) => "outline",
+            StageSpec::Retro(_) => "retro",
+            StageSpec::Resnap(_) => "resnap",
+        }
+    }
+
+    /// Apply the same bounds as the equivalent command-line flags
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            StageSpec::Denoise(s) => {
+                non_negative("threshold", s.threshold)?;
+                unit_interval("strength", s.strength)?;
+                radius("radius", s.radius)
+            }
+            StageSpec::Adjust(s) => {
+                if !(-1.0..=1.0).contains(&s.brightness) {
+                    return Err(format!(
+                        "brightness must be between -1.0 and 1.0: {}",
+                        s.brightness
+                    ));
+                }
+                non_negative("contrast", s.contrast)?;
+                positive("gamma", s.gamma)?;
+                non_negative("saturation", s.saturation)?;
+                if !s.hue_rotate.is_finite() {
+                    return Err(format!("hue_rotate must be a number: {}", s.hue_rotate));
+                }
+                match s.levels {
+                    Some((black, white)) if black >= white => Err(format!(
+                        "levels black point must be below white point: {}, {}",
+                        black, white
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            StageSpec::Bilateral(s) => {
+                sigma("sigma_space", s.sigma_space)?;
+                positive("sigma_color", s.sigma_color)?;
+                s.radius.map_or(Ok(()), |r| radius("radius", r))
+            }
+            StageSpec::Kuwahara(s) => {
+                non_negative("sharpness", s.sharpness)?;
+                radius("radius", s.radius)
+            }
+            StageSpec::ToneRemap(s) => unit_interval("strength", s.strength),
+            StageSpec::Blur(s) => {
+                sigma("sigma", s.sigma)?;
+                match s.radius {
+                    Some(r) if r > MAX_RADIUS => {
+                        Err(format!("radius must be at most {}: {}", MAX_RADIUS, r))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            StageSpec::Convert(s) => {
+                if let Some((width, height)) = s.pixels_area {
+                    at_least("pixels_area width", width, 1)?;
+                    at_least("pixels_area height", height, 1)?;
+                }
+                unit_interval("cell_jitter", s.cell_jitter)?;
+                at_least("grout_width", s.grout_width, 1)
+            }
+            StageSpec::Halftone(s) => {
+                if !s.angle.is_finite() {
+                    return Err(format!("angle must be a number: {}", s.angle));
+                }
+                positive("cell_size", s.cell_size)
+            }
+            StageSpec::Recolor(s) => {
+                if !(1..=256).contains(&s.clusters) {
+                    return Err(format!(
+                        "clusters must be between 1 and 256: {}",
+                        s.clusters
+                    ));
+                }
+                unit_interval("lightness_weight", s.lightness_weight)?;
+                unit_interval("shading", s.shading)
+            }
+            StageSpec::Despeckle(s) => {
+                at_least("min_region", s.min_region, 1)?;
+                radius("radius", s.radius)
+            }
+            StageSpec::Outline(s) => {
+                positive("threshold", s.threshold)?;
+                at_least("thickness", s.thickness, 1)
+            }
+            StageSpec::Retro(s) => {
+                unit_interval("strength", s.strength)?;
+                at_least("size", s.size, 2)
+            }
+            StageSpec::Resnap(_) => Ok(()),
+        }
+    }
+
+    fn add_to(&self, pipeline: &mut Pipeline) {
+        match self.clone() {
+            StageSpec::Denoise(stage) => pipeline.push(stage),
+            StageSpec::Adjust(stage) => pipeline.push(stage),
+            StageSpec::Bilateral(stage) => pipeline.push(stage),
+            StageSpec::Kuwahara(stage) => pipeline.push(stage),
+            StageSpec::ToneRemap(stage) => pipeline.push(stage),
+            StageSpec::Blur(stage) => pipeline.push(stage),
+            StageSpec::Convert(stage) => pipeline.push(stage),
+            StageSpec::Halftone(stage) => pipeline.push(stage),
+            StageSpec::Recolor(stage) => pipeline.push(stage),
+            StageSpec::Despeckle(stage) => pipeline.push(stage),
+            StageSpec::Outline(stage) => pipeline.push(stage),
+            StageSpec::Retro(stage) => pipeline.push(stage),
+            StageSpec::Resnap(stage) => pipeline.push(stage),
+        }
+    }
+}
+
+/// A processing recipe: an ordered list of stages, stored as TOML
+///
+/// ```toml
+/// name = "illustrated"
+///
+/// [[stages]]
+/// type = "denoise"
+/// mode = "median"
+///
+/// [[stages]]
+/// type = "convert"
+/// dither = "floyd-steinberg"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recipe {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub stages: Vec<StageSpec>,
+}
+
+impl Recipe {
+    /// The recipe equivalent to the effect flags in `config`
+    pub fn from_config(config: &Config) -> Self {
+        let mut stages = Vec::new();
+
+        if config.denoise {
+            stages.push(StageSpec::Denoise(DenoiseStage::from_config(config)));
+        }
+
+        if !config.adjustments.is_identity() {
+            stages.push(StageSpec::Adjust(config.adjustments.clone()));
+        }
+
+        if config.bilateral {
+            stages.push(StageSpec::Bilateral(BilateralStage::from_config(config)));
+        }
+
+        if config.kuwahara {
+            stages.push(StageSpec::Kuwahara(KuwaharaStage::from_config(config)));
+        }
+
+        if config.pre_blur {
+            stages.push(StageSpec::Blur(BlurStage::from_config(config)));
+        }
+
+        if config.tone_remap {
+            stages.push(StageSpec::ToneRemap(ToneRemapStage {
+                strength: config.tone_remap_strength,
+            }));
+        }
+
+        if config.halftone {
+            stages.push(StageSpec::Halftone(HalftoneStage::from_config(config)));
+        } else if config.recolor {
+            stages.push(StageSpec::Recolor(RecolorStage::from_config(config)));
+        } else {
+            stages.push(StageSpec::Convert(ConvertStage::from_config(config)));
+        }
+
+        if config.despeckle {
+            stages.push(StageSpec::Despeckle(DespeckleStage::from_config(config)));
+        }
+
+        if config.outline {
+            stages.push(StageSpec::Outline(OutlineStage::from_config(config)));
+        }
+
+        if config.enable_blur {
+            stages.push(StageSpec::Blur(BlurStage::from_config(config)));
+
+            if config.blur_resnap {
+                stages.push(StageSpec::Resnap(ResnapStage {
+                    dither: config.dithering,
+                }));
+            }
+        }
+
+        for &effect in &config.retro {
+            stages.push(StageSpec::Retro(RetroStage::from_config(config, effect)));
+        }
+
+        if !config.retro.is_empty() && config.retro_resnap {
+            stages.push(StageSpec::Resnap(ResnapStage {
+                dither: config.dithering,
+            }));
+        }
+
+        Self {
+            name: None,
+            description: None,
+            stages,
+        }
+    }
+
+    /// Whether `config` asks for any effect, i.e. its flags would build a
+    /// different recipe than the defaults
+    pub fn config_has_effects(config: &Config) -> bool {
+        Self::from_config(config) != Self::from_config(&Config::default())
+    }
+
+    pub fn from_toml(content: &str) -> crate::Result<Self> {
+        let recipe: Recipe = toml::from_str(content)
+            .map_err(|e| RustBucketError::RecipeError(format!("Failed to parse recipe: {}", e)))?;
+
+        for (index, stage) in recipe.stages.iter().enumerate() {
+            stage.validate().map_err(|e| {
+                RustBucketError::RecipeError(format!(
+                    "Stage {} ({}): {}",
+                    index + 1,
+                    stage.type_name(),
+                    e
+                ))
+            })?;
+        }
+
+        for warning in recipe.warnings() {
+            log::warn!("{}", warning);
+        }
+
+        Ok(recipe)
+    }
+
+    /// Stage combinations that likely don't do what was intended
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // Recolor only lands on palette colors when it drops the shading
+        if !self.stages.iter().any(|stage| match stage {
+            StageSpec::Convert(_) | StageSpec::Halftone(_) | StageSpec::Resnap(_) => true,
+            StageSpec::Recolor(recolor) => recolor.shading == 0.0,
+            _ => false,
+        }) {
+            warnings.push(
+                "Recipe has no convert, halftone, resnap or unshaded recolor stage; output may contain colors outside the palette"
+                    .to_string(),
+            );
+        }
+
+        // Despeckle snaps each pixel to its nearest palette color, undoing
+        // the role assignment of a shaded recolor
+        let shaded_recolor = self.stages.iter().position(
+            |stage| matches!(stage, StageSpec::Recolor(recolor) if recolor.shading > 0.0),
+        );
+        if let Some(index) = shaded_recolor {
+            if self.stages[index..]
+                .iter()
+                .any(|stage| matches!(stage, StageSpec::Despeckle(_)))
+            {
+                warnings.push(
+                    "Despeckle after a shaded recolor snaps colors to the nearest palette color again; set the recolor shading to 0 to keep the role assignment"
+                        .to_string(),
+                );
+            }
+        }
+
+        warnings
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let content = fs::read_to_string(path.as_ref()).map_err(RustBucketError::IoError)?;
+        Self::from_toml(&content).map_err(|e| {
+            RustBucketError::RecipeError(format!("{}: {}", path.as_ref().display(), e))
+        })
+    }
+
+    pub fn to_toml(&self) -> crate::Result<String> {
+        let mut value = toml::Value::try_from(self).map_err(|e| {
+            RustBucketError::RecipeError(format!("Failed to serialize recipe: {}", e))
+        })?;
+        shorten_floats(&mut value);
+
+        toml::to_string_pretty(&value)
+            .map_err(|e| RustBucketError::RecipeError(format!("Failed to serialize recipe: {}", e)))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let content = self.to_toml()?;
+        fs::write(path.as_ref(), content).map_err(RustBucketError::IoError)?;
+        Ok(())
+    }
+
+    pub fn to_pipeline(&self) -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        for stage in &self.stages {
+            stage.add_to(&mut pipeline);
+        }
+        pipeline
+    }
+}
+
+fn positive(name: &str, value: f32) -> Result<(), String> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("{} must be a positive number: {}", name, value));
+    }
+    Ok(())
+}
+
+fn non_negative(name: &str, value: f32) -> Result<(), String> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("{} must not be negative: {}", name, value));
+    }
+    Ok(())
+}
+
+fn sigma(name: &str, value: f32) -> Result<(), String> {
+    positive(name, value)?;
+    if value > MAX_SIGMA {
+        return Err(format!(
+            "{} must be at most {:.1}: {}",
+            name, MAX_SIGMA, value
+        ));
+    }
+    Ok(())
+}
+
+fn unit_interval(name: &str, value: f32) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("{} must be between 0.0 and 1.0: {}", name, value));
+    }
+    Ok(())
+}
+
+fn at_least(name: &str, value: u32, min: u32) -> Result<(), String> {
+    if value < min {
+        return Err(format!("{} must be at least {}: {}", name, min, value));
+    }
+    Ok(())
+}
+
+fn radius(name: &str, value: u32) -> Result<(), String> {
+    if !(1..=MAX_RADIUS).contains(&value) {
+        return Err(format!(
+            "{} must be between 1 and {}: {}",
+            name, MAX_RADIUS, value
+        ));
+    }
+    Ok(())
+}
+
+/// Stage settings are `f32`, which TOML widens to `f64` (1.2 becomes
+/// 1.2000000476837158); write the shortest representation instead
+fn shorten_floats(value: &mut toml::Value) {
+    match value {
+        toml::Value::Float(f) => {
+            if let Ok(short) = (*f as f32).to_string().parse() {
+                *f = short;
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(shorten_floats),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, v)| shorten_floats(v)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::{BlurKind, DenoiseMode, Dithering, KuwaharaKind, RetroEffect};
+    use crate::Args;
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_default_config_recipe() {
+        let recipe = Recipe::from_config(&Config::default());
+        assert_eq!(
+            recipe.stages,
+            vec![StageSpec::Convert(ConvertStage::default())]
+        );
+    }
+
+    #[test]
+    fn test_flags_map_onto_recipe() {
+        let config = Config {
+            denoise: true,
+            bilateral: true,
+            kuwahara: true,
+            pre_blur: true,
+            despeckle: true,
+            outline: true,
+            enable_blur: true,
+            blur_resnap: true,
+            dithering: Dithering::Ordered,
+            ..Config::default()
+        };
+
+        let names = Recipe::from_config(&config).to_pipeline().stage_names();
+        assert_eq!(
+            names,
+            vec![
+                "denoise",
+                "bilateral",
+                "kuwahara",
+                "blur",
+                "convert",
+                "despeckle",
+                "outline",
+                "blur",
+                "resnap"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_halftone_replaces_convert() {
+        let config = Config {
+            halftone: true,
+            halftone_cell_size: 6.0,
+            enable_blur: true,
+            blur_resnap: true,
+            ..Config::default()
+        };
+
+        let recipe = Recipe::from_config(&config);
+        assert_eq!(
+            recipe.to_pipeline().stage_names(),
+            vec!["halftone", "blur", "resnap"]
+        );
+        match &recipe.stages[0] {
+            StageSpec::Halftone(stage) => assert_eq!(stage.cell_size, 6.0),
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recolor_replaces_convert() {
+        let config = Config {
+            recolor: true,
+            recolor_clusters: 5,
+            despeckle: true,
+            ..Config::default()
+        };
+
+        let recipe = Recipe::from_config(&config);
+        assert_eq!(
+            recipe.to_pipeline().stage_names(),
+            vec!["recolor", "despeckle"]
+        );
+        match &recipe.stages[0] {
+            StageSpec::Recolor(stage) => assert_eq!(stage.clusters, 5),
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+
+        let parsed = Recipe::from_toml("[[stages]]\ntype = \"recolor\"\nshading = 0.0\n").unwrap();
+        assert_eq!(
+            parsed.stages,
+            vec![StageSpec::Recolor(RecolorStage {
+                shading: 0.0,
+                ..RecolorStage::default()
+            })]
+        );
+        assert!(parsed.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_shaded_recolor_warnings() {
+        // Shaded recolor leaves the palette, and despeckle collapses it again
+        let config = Config {
+            recolor: true,
+            despeckle: true,
+            ..Config::default()
+        };
+        let warnings = Recipe::from_config(&config).warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[1].contains("Despeckle"));
+
+        let flat = Config {
+            recolor_shading: 0.0,
+            ..config
+        };
+        assert!(Recipe::from_config(&flat).warnings().is_empty());
+    }
+
+    #[test]
+    fn test_retro_effects_follow_blur() {
+        let config = Config {
+            enable_blur: true,
+            retro: vec![RetroEffect::Bloom, RetroEffect::Scanlines],
+            retro_resnap: true,
+            ..Config::default()
+        };
+
+        let recipe = Recipe::from_config(&config);
+        assert_eq!(
+            recipe.to_pipeline().stage_names(),
+            vec!["convert", "blur", "retro", "retro", "resnap"]
+        );
+        match &recipe.stages[2] {
+            StageSpec::Retro(stage) => assert_eq!(stage.effect, RetroEffect::Bloom),
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_recipe() {
+        let recipe = Recipe::from_toml(
+            r#"
+name = "illustrated"
+
+[[stages]]
+type = "denoise"
+mode = "median"
+
+[[stages]]
+type = "adjust"
+auto_levels = true
+saturation = 1.2
+
+[[stages]]
+type = "kuwahara"
+kind = "anisotropic"
+radius = 5
+
+[[stages]]
+type = "convert"
+dither = "floyd-steinberg"
+
+[[stages]]
+type = "despeckle"
+min_region = 6
+
+[[stages]]
+type = "blur"
+sigma = 0.8
+kind = "stack"
+
+[[stages]]
+type = "resnap"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(recipe.name.as_deref(), Some("illustrated"));
+        assert_eq!(recipe.stages.len(), 7);
+        match &recipe.stages[0] {
+            StageSpec::Denoise(stage) => {
+                assert_eq!(stage.mode, DenoiseMode::Median);
+                assert_eq!(stage.threshold, 30.0);
+            }
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+        match &recipe.stages[1] {
+            StageSpec::Adjust(adjustments) => {
+                assert!(adjustments.auto_levels);
+                assert_eq!(adjustments.contrast, 1.0);
+            }
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+        match &recipe.stages[2] {
+            StageSpec::Kuwahara(stage) => {
+                assert_eq!(stage.kind, KuwaharaKind::Anisotropic);
+                assert_eq!(stage.radius, 5);
+                assert_eq!(stage.sharpness, 8.0);
+            }
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+        match &recipe.stages[4] {
+            StageSpec::Despeckle(stage) => {
+                assert_eq!(stage.min_region, 6);
+                assert_eq!(stage.radius, 1);
+            }
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+        match &recipe.stages[5] {
+            StageSpec::Blur(stage) => assert_eq!(stage.kind, BlurKind::Stack),
+            other => panic!("Unexpected stage: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_recipes() {
+        assert!(Recipe::from_toml("[[stages]]\ntype = \"sharpen\"\n").is_err());
+        assert!(Recipe::from_toml("[[stages]]\ntype = \"blur\"\nsigmaa = 2.0\n").is_err());
+        assert!(Recipe::from_toml("[[stages]]\ntype = \"convert\"\ndither = \"fancy\"\n").is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_stages_are_rejected() {
+        for stage in [
+            "type = \"convert\"\npixels_area = [0, 4]",
+            "type = \"blur\"\nsigma = -1.0",
+            "type = \"blur\"\nsigma = 1e9",
+            "type = \"denoise\"\nstrength = 1.5",
+            "type = \"kuwahara\"\nradius = 0",
+            "type = \"recolor\"\nclusters = 0",
+            "type = \"bilateral\"\nsigma_space = 1e9",
+            "type = \"bilateral\"\nradius = 1000",
+        ] {
+            let err = Recipe::from_toml(&format!("[[stages]]\n{}\n", stage)).unwrap_err();
+            assert!(err.to_string().contains("Stage 1"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_config_has_effects() {
+        assert!(!Recipe::config_has_effects(&Config::default()));
+        assert!(!Recipe::config_has_effects(&Config::from(
+            Args::parse_from(["rtbt", "-i", "in.png"])
+        )));
+        assert!(Recipe::config_has_effects(&Config {
+            denoise: true,
+            ..Config::default()
+        }));
+    }
+
+    #[test]
+    fn test_cli_defaults_match_config_defaults() {
+        let parse = |flags: &[&str]| {
+            let args = ["rtbt", "-i", "in.png"].iter().chain(flags);
+            Recipe::from_config(&Config::from(Args::parse_from(args)))
+        };
+
+        let all_effects = Config {
+            denoise: true,
+            bilateral: true,
+            kuwahara: true,
+            tone_remap: true,
+            despeckle: true,
+            outline: true,
+            enable_blur: true,
+            retro: vec![RetroEffect::Scanlines],
+            ..Config::default()
+        };
+        assert_eq!(
+            parse(&[
+                "--denoise",
+                "--bilateral",
+                "--kuwahara",
+                "--tone-remap",
+                "--despeckle",
+                "--outline",
+                "--blur",
+                "--retro",
+                "scanlines"
+            ]),
+            Recipe::from_config(&all_effects)
+        );
+        assert_eq!(
+            parse(&["--halftone"]),
+            Recipe::from_config(&Config {
+                halftone: true,
+                ..Config::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--recolor"]),
+            Recipe::from_config(&Config {
+                recolor: true,
+                ..Config::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_recipe() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("recipe.toml");
+
+        let config = Config {
+            denoise: true,
+            enable_blur: true,
+            blur_resnap: true,
+            pixels_area: Some((2, 3)),
+            ..Config::default()
+        };
+        let recipe = Recipe::from_config(&config);
+        recipe.save(&path).unwrap();
+
+        assert_eq!(Recipe::load(&path).unwrap(), recipe);
+        // A pixel area above 2 defaults to the light blur
+        assert!(recipe.to_toml().unwrap().contains("sigma = 0.8\n"));
+    }
+}
@@ -0,0 +1,382 @@
+use crate::palette::{hex_to_rgb, ExtractMethod, PaletteExtractor};
+use image::{DynamicImage, Rgb, Rgba, RgbaImage};
+
+use super::color_space::Oklab;
+
+/// Role-based recoloring: the image's dominant colors are each given a
+/// distinct palette color, then every pixel moves with its cluster, keeping
+/// its offset from the cluster center so shading inside a region survives.
+///
+/// Unlike nearest-color conversion, two different source colors never
+/// collapse onto the same palette color. With any shading the output holds
+/// colors between the palette colors; only a shading of 0.0 stays in it.
+#[derive(Debug, Clone)]
+pub struct Recolor {
+    clusters: usize,
+    lightness_weight: f32,
+    shading: f32,
+}
+
+/// A dominant image color and the palette color it was assigned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoleAssignment {
+    pub source: Rgb<u8>,
+    pub target: Rgb<u8>,
+}
+
+impl Recolor {
+    pub fn new(clusters: usize) -> Self {
+        Self {
+            clusters: clusters.max(1),
+            lightness_weight: 0.5,
+            shading: 1.0,
+        }
+    }
+
+    /// How strongly the dark-to-light order of the source colors is kept
+    /// (0.0 matches on color difference alone)
+    pub fn with_lightness_weight(mut self, weight: f32) -> Self {
+        self.lightness_weight = weight.max(0.0);
+        self
+    }
+
+    /// How much of each pixel's offset from its cluster center is carried
+    /// over (0.0 gives flat palette colors, anything above leaves the palette)
+    pub fn with_shading(mut self, shading: f32) -> Self {
+        self.shading = shading.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Extract the dominant colors and assign each a distinct palette color.
+    /// At most one cluster per palette color is extracted.
+    pub fn assign(
+        &self,
+        img: &DynamicImage,
+        palette: &[Rgb<u8>],
+    ) -> crate::Result<Vec<RoleAssignment>> {
+        if palette.is_empty() {
+            return Err(crate::RustBucketError::PaletteNotFound(
+                "Recoloring needs at least one palette color".to_string(),
+            ));
+        }
+
+        let clusters = self.clusters.min(palette.len());
+        let sources: Vec<Rgb<u8>> = PaletteExtractor::new(clusters)
+            .with_method(ExtractMethod::Kmeans)
+            .extract(img, "source")?
+            .colors
+            .iter()
+            .map(|c| hex_to_rgb(&c.hex).map(|(r, g, b)| Rgb([r, g, b])))
+            .collect::<crate::Result<_>>()?;
+
+        let source_labs: Vec<Oklab> = sources.iter().map(|&c| Oklab::from_rgb(c)).collect();
+        let target_labs: Vec<Oklab> = palette.iter().map(|&c| Oklab::from_rgb(c)).collect();
+        let source_ranks = lightness_ranks(&source_labs);
+        let target_ranks = lightness_ranks(&target_labs);
+
+        // ΔE plus a penalty for landing at a different relative lightness
+        let cost: Vec<Vec<f64>> = source_labs
+            .iter()
+            .zip(&source_ranks)
+            .map(|(source, source_rank)| {
+                target_labs
+                    .iter()
+                    .zip(&target_ranks)
+                    .map(|(target, target_rank)| {
+                        let delta_e = source.distance(target) * 100.0;
+                        let order = (source_rank - target_rank).abs() * 100.0;
+                        f64::from(delta_e + self.lightness_weight * order)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let assignment = hungarian(&cost);
+        log::info!(
+            "Recoloring {} image colors onto {} palette colors",
+            sources.len(),
+            palette.len()
+        );
+
+        Ok(sources
+            .into_iter()
+            .zip(assignment)
+            .map(|(source, column)| RoleAssignment {
+                source,
+                target: palette[column],
+            })
+            .collect())
+    }
+
+    pub fn recolor(&self, img: &DynamicImage, palette: &[Rgb<u8>]) -> crate::Result<DynamicImage> {
+        let assignments = self.assign(img, palette)?;
+        Ok(self.apply(img, &assignments))
+    }
+
+    /// Move every pixel from its nearest source color to that color's target
+    pub fn apply(&self, img: &DynamicImage, assignments: &[RoleAssignment]) -> DynamicImage {
+        let roles: Vec<(Oklab, Oklab)> = assignments
+            .iter()
+            .map(|a| (Oklab::from_rgb(a.source), Oklab::from_rgb(a.target)))
+            .collect();
+
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let mut output = RgbaImage::new(width, height);
+        let mut cache = std::collections::HashMap::new();
+
+        for (x, y, pixel) in rgba.enumerate_pixels() {
+            let rgb = Rgb([pixel[0], pixel[1], pixel[2]]);
+            let mapped = *cache.entry(rgb).or_insert_with(|| {
+                let lab = Oklab::from_rgb(rgb);
+                let (source, target) = roles
+                    .iter()
+                    .min_by(|a, b| lab.distance(&a.0).total_cmp(&lab.distance(&b.0)))
+                    .expect("at least one role");
+                Oklab {
+                    l: target.l + (lab.l - source.l) * self.shading,
+                    a: target.a + (lab.a - source.a) * self.shading,
+                    b: target.b + (lab.b - source.b) * self.shading,
+                }
+                .to_rgb()
+            });
+            output.put_pixel(x, y, Rgba([mapped[0], mapped[1], mapped[2], pixel[3]]));
+        }
+
+        if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        }
+    }
+}
+
+/// Each color's lightness rank scaled to 0.0 (darkest) ..= 1.0 (lightest)
+fn lightness_ranks(colors: &[Oklab]) -> Vec<f32> {
+    let mut order: Vec<usize> = (0..colors.len()).collect();
+    order.sort_by(|&a, &b| colors[a].l.total_cmp(&colors[b].l));
+
+    let span = (colors.len().max(2) - 1) as f32;
+    let mut ranks = vec![0.5; colors.len()];
+    if colors.len() > 1 {
+        for (rank, index) in order.into_iter().enumerate() {
+            ranks[index] = rank as f32 / span;
+        }
+    }
+    ranks
+}
+
+/// Minimum-cost assignment of every row to a distinct column (rows must not
+/// outnumber columns). Returns the chosen column for each row.
+fn hungarian(cost: &[Vec<f64>]) -> Vec<usize> {
+    let rows = cost.len();
+    let columns = cost.first().map_or(0, Vec::len);
+    debug_assert!(rows <= columns);
+
+    // Potentials and matching are 1-based; index 0 is the virtual start
+    let mut u = vec![0.0; rows + 1];
+    let mut v = vec![0.0; columns + 1];
+    let mut matched_row = vec![0usize; columns + 1];
+    let mut way = vec![0usize; columns + 1];
+
+    for row in 1..=rows {
+        matched_row[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; columns + 1];
+        let mut used = vec![false; columns + 1];
+
+        loop {
+            used[column] = true;
+            let current = matched_row[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+                let slack = cost[current - 1][j - 1] - u[current] - v[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next = j;
+                }
+            }
+
+            for j in 0..=columns {
+                if used[j] {
+                    u[matched_row[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+
+            column = next;
+            if matched_row[column] == 0 {
+                break;
+            }
+        }
+
+        // Flip the augmenting path
+        loop {
+            let previous = way[column];
+            matched_row[column] = matched_row[previous];
+            column = previous;
+            if column == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![0; rows];
+    for j in 1..=columns {
+        if matched_row[j] != 0 {
+            assignment[matched_row[j] - 1] = j - 1;
+        }
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_hungarian_finds_optimum() {
+        let cost = vec![
+            vec![4.0, 1.0, 3.0],
+            vec![2.0, 0.0, 5.0],
+            vec![3.0, 2.0, 2.0],
+        ];
+        let assignment = hungarian(&cost);
+        let total: f64 = assignment
+            .iter()
+            .enumerate()
+            .map(|(r, &c)| cost[r][c])
+            .sum();
+        assert_eq!(total, 5.0);
+        assert_eq!(assignment, vec![1, 0, 2]);
+
+        // More columns than rows
+        let cost = vec![vec![9.0, 1.0, 8.0, 7.0], vec![9.0, 2.0, 8.0, 1.5]];
+        assert_eq!(hungarian(&cost), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_lightness_ranks() {
+        let colors: Vec<Oklab> = [
+            Rgb([200, 200, 200]),
+            Rgb([10, 10, 10]),
+            Rgb([100, 100, 100]),
+        ]
+        .iter()
+        .map(|&c| Oklab::from_rgb(c))
+        .collect();
+        assert_eq!(lightness_ranks(&colors), vec![1.0, 0.0, 0.5]);
+        assert_eq!(lightness_ranks(&colors[..1]), vec![0.5]);
+    }
+
+    fn two_reds() -> DynamicImage {
+        // Two similar reds that nearest-color matching would merge
+        DynamicImage::ImageRgb8(RgbImage::from_fn(40, 20, |x, _| {
+            if x < 20 {
+                Rgb([200, 30, 30])
+            } else {
+                Rgb([160, 40, 40])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_distinct_colors_stay_distinct() {
+        let palette = [Rgb([191, 97, 106]), Rgb([94, 129, 172]), Rgb([46, 52, 64])];
+        let result = Recolor::new(8)
+            .with_shading(0.0)
+            .recolor(&two_reds(), &palette)
+            .unwrap()
+            .to_rgb8();
+
+        let left = *result.get_pixel(0, 0);
+        let right = *result.get_pixel(39, 0);
+        assert_ne!(left, right);
+        assert!(palette.contains(&left) && palette.contains(&right));
+        // The lighter red keeps the closest palette color
+        assert_eq!(left, Rgb([191, 97, 106]));
+    }
+
+    #[test]
+    fn test_lightness_order_is_kept() {
+        // Red is lighter than blue, but the palette's red is the darker
+        // color: ΔE alone matches by hue, the order term keeps the
+        // darker region darker
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(20, 10, |x, _| {
+            if x < 10 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        }));
+        let palette = [Rgb([120, 0, 0]), Rgb([120, 170, 255])];
+        let target_of = |weight: f32, source: Rgb<u8>| {
+            Recolor::new(2)
+                .with_lightness_weight(weight)
+                .assign(&img, &palette)
+                .unwrap()
+                .into_iter()
+                .find(|a| a.source == source)
+                .unwrap()
+                .target
+        };
+
+        assert_eq!(target_of(0.0, Rgb([255, 0, 0])), Rgb([120, 0, 0]));
+        assert_eq!(target_of(1.0, Rgb([255, 0, 0])), Rgb([120, 170, 255]));
+        assert_eq!(target_of(1.0, Rgb([0, 0, 255])), Rgb([120, 0, 0]));
+    }
+
+    #[test]
+    fn test_shading_preserved_within_cluster() {
+        // A flat region with a slightly darker stripe
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(30, 10, |x, _| {
+            if x < 20 {
+                Rgb([40, 120, 40])
+            } else {
+                Rgb([36, 110, 36])
+            }
+        }));
+        let assignments = [RoleAssignment {
+            source: Rgb([40, 120, 40]),
+            target: Rgb([94, 129, 172]),
+        }];
+
+        let shaded = Recolor::new(1).apply(&img, &assignments).to_rgb8();
+        assert_eq!(*shaded.get_pixel(0, 0), Rgb([94, 129, 172]));
+        let stripe = Oklab::from_rgb(*shaded.get_pixel(25, 0));
+        assert!(stripe.l < Oklab::from_rgb(Rgb([94, 129, 172])).l);
+
+        let flat = Recolor::new(1)
+            .with_shading(0.0)
+            .apply(&img, &assignments)
+            .to_rgb8();
+        assert_eq!(*flat.get_pixel(25, 0), Rgb([94, 129, 172]));
+    }
+
+    #[test]
+    fn test_alpha_preserved_and_errors() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+            Rgba([200, 30, 30, if x == 0 { 0 } else { 255 }])
+        }));
+        let out = Recolor::new(4)
+            .recolor(&img, &[Rgb([0, 0, 255])])
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(out.get_pixel(0, 0)[3], 0);
+        assert_eq!(*out.get_pixel(1, 0), Rgba([0, 0, 255, 255]));
+
+        assert!(Recolor::new(4).recolor(&img, &[]).is_err());
+    }
+}
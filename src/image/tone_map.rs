@@ -0,0 +1,182 @@
+use image::{DynamicImage, ImageBuffer, Rgb, Rgba, RgbaImage};
+use std::fmt;
+
+use super::color_space::Oklab;
+
+/// Fraction of pixels ignored at each end when measuring the image's
+/// lightness range, so a few specular highlights don't define the white point
+const IMAGE_RANGE_CLIP: f32 = 0.01;
+
+/// Lightness ranges measured by [`ToneRemap`], in OKLab L (0.0 to 1.0)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneRemapReport {
+    pub palette_range: (f32, f32),
+    pub image_range: (f32, f32),
+    pub image_mean: f32,
+    pub strength: f32,
+}
+
+impl fmt::Display for ToneRemapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "image L {:.2}-{:.2} (mean {:.2}) -> palette L {:.2}-{:.2} at strength {:.2}",
+            self.image_range.0,
+            self.image_range.1,
+            self.image_mean,
+            self.palette_range.0,
+            self.palette_range.1,
+            self.strength
+        )
+    }
+}
+
+/// Remaps image lightness into the span covered by the palette so that
+/// nearest-color matching can use the whole palette instead of collapsing a
+/// bright photo onto a dark theme's two lightest colors (or vice versa).
+///
+/// Only lightness changes; OKLab hue and chroma are kept.
+pub struct ToneRemap {
+    palette_range: (f32, f32),
+    strength: f32,
+}
+
+impl ToneRemap {
+    /// Measure the lightness span of the given palette colors
+    pub fn new(palette_colors: &[Rgb<u8>], strength: f32) -> Self {
+        let lightness = palette_colors.iter().map(|&c| Oklab::from_rgb(c).l);
+        let palette_range = lightness.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), l| {
+            (lo.min(l), hi.max(l))
+        });
+
+        Self {
+            palette_range: if palette_range.0 <= palette_range.1 {
+                palette_range
+            } else {
+                (0.0, 1.0)
+            },
+            strength: strength.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn palette_range(&self) -> (f32, f32) {
+        self.palette_range
+    }
+
+    /// Measure the image's lightness distribution without modifying it
+    pub fn analyze(&self, img: &DynamicImage) -> ToneRemapReport {
+        let rgba_img = img.to_rgba8();
+        let mut lightness: Vec<f32> = rgba_img
+            .pixels()
+            .filter(|p| p[3] > 0)
+            .map(|p| Oklab::from_rgb(Rgb([p[0], p[1], p[2]])).l)
+            .collect();
+
+        let (image_range, image_mean) = if lightness.is_empty() {
+            ((0.0, 1.0), 0.5)
+        } else {
+            lightness.sort_unstable_by(f32::total_cmp);
+            let clip = (lightness.len() as f32 * IMAGE_RANGE_CLIP) as usize;
+            let low = lightness[clip];
+            let high = lightness[lightness.len() - 1 - clip];
+            let mean = lightness.iter().sum::<f32>() / lightness.len() as f32;
+            ((low, high), mean)
+        };
+
+        ToneRemapReport {
+            palette_range: self.palette_range,
+            image_range,
+            image_mean,
+            strength: self.strength,
+        }
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> (DynamicImage, ToneRemapReport) {
+        let report = self.analyze(img);
+        let (image_low, image_high) = report.image_range;
+        let (palette_low, palette_high) = self.palette_range;
+        let image_span = image_high - image_low;
+
+        let remap = |l: f32| {
+            let target = if image_span > f32::EPSILON {
+                let t = ((l - image_low) / image_span).clamp(0.0, 1.0);
+                palette_low + t * (palette_high - palette_low)
+            } else {
+                // Flat image: center it in the palette's range
+                (palette_low + palette_high) / 2.0
+            };
+            l + self.strength * (target - l)
+        };
+
+        let rgba_img = img.to_rgba8();
+        let output: RgbaImage =
+            ImageBuffer::from_fn(rgba_img.width(), rgba_img.height(), |x, y| {
+                let pixel = rgba_img.get_pixel(x, y);
+                let mut lab = Oklab::from_rgb(Rgb([pixel[0], pixel[1], pixel[2]]));
+                lab.l = remap(lab.l);
+                let rgb = lab.to_rgb();
+                Rgba([rgb[0], rgb[1], rgb[2], pixel[3]])
+            });
+
+        let output = if img.color().has_alpha() {
+            DynamicImage::ImageRgba8(output)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(output).to_rgb8())
+        };
+        (output, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn dark_palette() -> Vec<Rgb<u8>> {
+        vec![Rgb([20, 20, 30]), Rgb([60, 60, 80]), Rgb([110, 110, 130])]
+    }
+
+    fn bright_image() -> DynamicImage {
+        let img: RgbImage = ImageBuffer::from_fn(32, 4, |x, _y| {
+            let v = 180 + (x * 2) as u8;
+            Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_palette_range() {
+        let remap = ToneRemap::new(&dark_palette(), 1.0);
+        let (low, high) = remap.palette_range();
+        assert!(low < 0.2);
+        assert!(high < 0.6);
+    }
+
+    #[test]
+    fn test_bright_image_fits_dark_palette() {
+        let remap = ToneRemap::new(&dark_palette(), 1.0);
+        let (remapped, report) = remap.apply(&bright_image());
+
+        assert!(report.image_range.0 > report.palette_range.1);
+
+        let after = remap.analyze(&remapped);
+        assert!((after.image_range.0 - report.palette_range.0).abs() < 0.02);
+        assert!((after.image_range.1 - report.palette_range.1).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_zero_strength_is_identity() {
+        let remap = ToneRemap::new(&dark_palette(), 0.0);
+        let img = bright_image();
+        let (remapped, _) = remap.apply(&img);
+        assert_eq!(remapped.to_rgb8(), img.to_rgb8());
+    }
+
+    #[test]
+    fn test_report_display() {
+        let remap = ToneRemap::new(&dark_palette(), 0.5);
+        let text = remap.analyze(&bright_image()).to_string();
+        assert!(text.contains("palette L"));
+        assert!(text.contains("strength 0.50"));
+    }
+}
@@ -5,9 +5,14 @@ pub mod palette;
 
 pub use cli::Args;
 pub use error::{Result, RustBucketError};
-pub use image::{ImageBenchmark, ImagePipeline, ImageProcessor, OptimizationSuggestions};
+pub use image::{
+    Adjustments, BlurKind, BorderMode, CellShape, ContactSheet, DenoiseMode, DespeckleMode,
+    Dithering, EdgeDetector, FitMetric, HalftoneShape, ImageBenchmark, ImagePipeline,
+    ImageProcessor, KuwaharaKind, OptimizationSuggestions, PaletteRanker, Recipe, RetroEffect,
+};
 pub use palette::{PaletteLoader, PaletteManager};
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
@@ -16,11 +21,87 @@ pub struct Color {
     pub hex: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Palette {
     pub name: String,
     pub path: PathBuf,
     pub colors: Vec<Color>,
+    /// Named sets of color names, selected with `-c @group`
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Alternative color names, e.g. `red` for `nord11`
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Palette {
+    /// Find a color by name or alias, ignoring case. Color names win over
+    /// aliases.
+    pub fn find_color(&self, name: &str) -> Option<&Color> {
+        self.colors
+            .iter()
+            .find(|c| c.name == name)
+            .or_else(|| {
+                self.colors
+                    .iter()
+                    .find(|c| c.name.eq_ignore_ascii_case(name))
+            })
+            .or_else(|| {
+                let (_, target) = self
+                    .aliases
+                    .iter()
+                    .find(|(alias, _)| alias.eq_ignore_ascii_case(name))?;
+                self.colors.iter().find(|c| &c.name == target)
+            })
+    }
+
+    /// Color names in a group, ignoring case
+    pub fn group(&self, name: &str) -> Option<&[String]> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group.eq_ignore_ascii_case(name))
+            .map(|(_, members)| members.as_slice())
+    }
+
+    /// Resolve color names, aliases and `@group` selectors to palette
+    /// colors, in order and without duplicates. Unknown selectors are
+    /// skipped with a warning.
+    pub fn select_colors(&self, selectors: &[String]) -> Vec<&Color> {
+        let mut selected: Vec<&Color> = Vec::new();
+        for selector in selectors {
+            let selector = selector.trim();
+            let matches: Vec<&Color> = if let Some(group) = selector.strip_prefix('@') {
+                match self.group(group) {
+                    Some(members) => members.iter().filter_map(|m| self.find_color(m)).collect(),
+                    None => {
+                        log::warn!(
+                            "Color group '{}' not found in palette '{}'",
+                            group,
+                            self.name
+                        );
+                        continue;
+                    }
+                }
+            } else {
+                match self.find_color(selector) {
+                    Some(color) => vec![color],
+                    None => {
+                        log::warn!(
+                            "Selected color '{}' not found in palette '{}'",
+                            selector,
+                            self.name
+                        );
+                        continue;
+                    }
+                }
+            };
+
+            for color in matches {
+                if !selected.iter().any(|c| c.name == color.name) {
+                    selected.push(color);
+                }
+            }
+        }
+        selected
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,8 +111,57 @@ pub struct Config {
     pub palette: String,
     pub colors: Vec<String>,
     pub enable_blur: bool,
+    pub blur_sigma: Option<f32>,
+    pub blur_radius: Option<u32>,
+    pub blur_kind: BlurKind,
+    pub pre_blur: bool,
+    pub blur_resnap: bool,
+    pub dithering: Dithering,
+    pub denoise: bool,
+    pub denoise_threshold: f32,
+    pub denoise_strength: f32,
+    pub denoise_radius: u32,
+    pub denoise_mode: DenoiseMode,
+    pub border_mode: BorderMode,
+    pub adjustments: Adjustments,
+    pub bilateral: bool,
+    pub bilateral_sigma_space: f32,
+    pub bilateral_sigma_color: f32,
+    pub kuwahara: bool,
+    pub kuwahara_radius: u32,
+    pub kuwahara_kind: KuwaharaKind,
+    pub tone_remap: bool,
+    pub tone_remap_strength: f32,
+    pub halftone: bool,
+    pub halftone_shape: HalftoneShape,
+    pub halftone_angle: f32,
+    pub halftone_cell_size: f32,
+    pub halftone_background: Option<String>,
+    pub recolor: bool,
+    pub recolor_clusters: usize,
+    pub recolor_lightness_weight: f32,
+    pub recolor_shading: f32,
+    pub despeckle: bool,
+    pub despeckle_min_region: u32,
+    pub despeckle_mode: DespeckleMode,
+    pub despeckle_radius: u32,
+    pub outline: bool,
+    pub outline_color: Option<String>,
+    pub outline_detector: EdgeDetector,
+    pub outline_threshold: f32,
+    pub outline_thickness: u32,
+    pub retro: Vec<RetroEffect>,
+    pub retro_strength: f32,
+    pub retro_size: u32,
+    pub retro_resnap: bool,
+    pub recipe: Option<PathBuf>,
     pub disable_avg_pixels: bool,
     pub pixels_area: Option<(u32, u32)>,
+    pub cell_shape: CellShape,
+    pub cell_jitter: f32,
+    pub cell_seed: u64,
+    pub grout_color: Option<String>,
+    pub grout_width: u32,
     pub quiet_mode: bool,
     pub benchmark: bool,
 }
@@ -44,10 +174,147 @@ impl From<Args> for Config {
             palette: args.palette,
             colors: args.colors,
             enable_blur: args.enable_blur,
+            blur_sigma: args.blur_sigma,
+            blur_radius: args.blur_radius,
+            blur_kind: args.blur_kind,
+            pre_blur: args.pre_blur,
+            blur_resnap: args.blur_resnap,
+            dithering: args.dithering,
+            denoise: args.denoise,
+            denoise_threshold: args.denoise_threshold,
+            denoise_strength: args.denoise_strength,
+            denoise_radius: args.denoise_radius,
+            denoise_mode: args.denoise_mode,
+            border_mode: args.border_mode,
+            adjustments: Adjustments {
+                brightness: args.brightness,
+                contrast: args.contrast,
+                gamma: args.gamma,
+                saturation: args.saturation,
+                hue_rotate: args.hue_rotate,
+                levels: args.levels,
+                auto_levels: args.auto_levels,
+                auto_white_balance: args.auto_white_balance,
+                equalize: args.equalize,
+            },
+            bilateral: args.bilateral,
+            bilateral_sigma_space: args.bilateral_sigma_space,
+            bilateral_sigma_color: args.bilateral_sigma_color,
+            kuwahara: args.kuwahara,
+            kuwahara_radius: args.kuwahara_radius,
+            kuwahara_kind: args.kuwahara_kind,
+            tone_remap: args.tone_remap,
+            tone_remap_strength: args.tone_remap_strength,
+            halftone: args.halftone,
+            halftone_shape: args.halftone_shape,
+            halftone_angle: args.halftone_angle,
+            halftone_cell_size: args.halftone_cell_size,
+            halftone_background: args.halftone_background,
+            recolor: args.recolor,
+            recolor_clusters: args.recolor_clusters as usize,
+            recolor_lightness_weight: args.recolor_lightness_weight,
+            recolor_shading: args.recolor_shading,
+            despeckle: args.despeckle,
+            despeckle_min_region: args.despeckle_min_region,
+            despeckle_mode: args.despeckle_mode,
+            despeckle_radius: args.despeckle_radius,
+            outline: args.outline,
+            outline_color: args.outline_color,
+            outline_detector: args.outline_detector,
+            outline_threshold: args.outline_threshold,
+            outline_thickness: args.outline_thickness,
+            retro: args.retro,
+            retro_strength: args.retro_strength,
+            retro_size: args.retro_size,
+            retro_resnap: args.retro_resnap,
+            recipe: args.recipe,
             disable_avg_pixels: args.disable_avg_pixels,
             pixels_area: args.pixels_area,
+            cell_shape: args.cell_shape,
+            cell_jitter: args.cell_jitter,
+            cell_seed: args.cell_seed,
+            grout_color: args.grout_color,
+            grout_width: args.grout_width,
             quiet_mode: args.quiet_mode,
             benchmark: args.benchmark,
         }
     }
 }
+
+impl Default for Config {
+    fn default() -> Self {
+        // Effect settings come from the stage defaults, which the CLI uses too
+        let blur = image::BlurStage::default();
+        let denoise = image::DenoiseStage::default();
+        let bilateral = image::BilateralStage::default();
+        let kuwahara = image::KuwaharaStage::default();
+        let tone_remap = image::ToneRemapStage::default();
+        let halftone = image::HalftoneStage::default();
+        let recolor = image::RecolorStage::default();
+        let despeckle = image::DespeckleStage::default();
+        let outline = image::OutlineStage::default();
+        let retro = image::RetroStage::default();
+        let convert = image::ConvertStage::default();
+
+        Config {
+            input_path: None,
+            output_path: PathBuf::from("nord.png"),
+            palette: "nord".to_string(),
+            colors: Vec::new(),
+            enable_blur: false,
+            blur_sigma: None,
+            blur_radius: None,
+            blur_kind: blur.kind,
+            pre_blur: false,
+            blur_resnap: false,
+            dithering: Dithering::default(),
+            denoise: false,
+            denoise_threshold: denoise.threshold,
+            denoise_strength: denoise.strength,
+            denoise_radius: denoise.radius,
+            denoise_mode: denoise.mode,
+            border_mode: BorderMode::default(),
+            adjustments: Adjustments::default(),
+            bilateral: false,
+            bilateral_sigma_space: bilateral.sigma_space,
+            bilateral_sigma_color: bilateral.sigma_color,
+            kuwahara: false,
+            kuwahara_radius: kuwahara.radius,
+            kuwahara_kind: kuwahara.kind,
+            tone_remap: false,
+            tone_remap_strength: tone_remap.strength,
+            halftone: false,
+            halftone_shape: halftone.shape,
+            halftone_angle: halftone.angle,
+            halftone_cell_size: halftone.cell_size,
+            halftone_background: halftone.background,
+            recolor: false,
+            recolor_clusters: recolor.clusters,
+            recolor_lightness_weight: recolor.lightness_weight,
+            recolor_shading: recolor.shading,
+            despeckle: false,
+            despeckle_min_region: despeckle.min_region,
+            despeckle_mode: despeckle.mode,
+            despeckle_radius: despeckle.radius,
+            outline: false,
+            outline_color: outline.color,
+            outline_detector: outline.detector,
+            outline_threshold: outline.threshold,
+            outline_thickness: outline.thickness,
+            retro: Vec::new(),
+            retro_strength: retro.strength,
+            retro_size: retro.size,
+            retro_resnap: false,
+            recipe: None,
+            disable_avg_pixels: !convert.averaging,
+            pixels_area: convert.pixels_area,
+            cell_shape: convert.cell_shape,
+            cell_jitter: convert.cell_jitter,
+            cell_seed: convert.cell_seed,
+            grout_color: convert.grout_color,
+            grout_width: convert.grout_width,
+            quiet_mode: false,
+            benchmark: false,
+        }
+    }
+}
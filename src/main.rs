@@ -2,11 +2,30 @@ use anyhow::Result;
 use clap::Parser;
 use env_logger::Env;
 use log::{info, warn};
+use rustbucket::palette::{ExtractMethod, PaletteExtractor, ThemeFormat, ThemeGenerator};
 use rustbucket::{
-    Args, Config, ImageBenchmark, ImagePipeline, OptimizationSuggestions, PaletteManager,
+    Args, CellShape, Config, ImageBenchmark, ImagePipeline, OptimizationSuggestions,
+    PaletteManager, PaletteRanker, Recipe,
 };
 use std::env;
 
+/// Palette manager honoring the palette source flags
+fn palette_manager(args: &Args) -> PaletteManager {
+    let manager = PaletteManager::new();
+    if args.base16_dirs.is_empty() {
+        manager
+    } else {
+        manager.with_base16_dirs(args.base16_dirs.clone())
+    }
+}
+
+/// Palette ranker honoring the fit flags
+fn palette_ranker(args: &Args) -> PaletteRanker {
+    PaletteRanker::new()
+        .with_metric(args.fit_metric)
+        .with_samples(args.fit_samples)
+}
+
 fn main() -> Result<()> {
     // If no arguments provided, show help
     if env::args().len() == 1 {
@@ -24,11 +43,20 @@ fn main() -> Result<()> {
         env!("CARGO_PKG_VERSION")
     );
 
-    let config = Config::from(args.clone());
+    let mut config = Config::from(args.clone());
+
+    if let Some(recipe_path) = &config.recipe {
+        if Recipe::config_has_effects(&config) {
+            warn!(
+                "Effect flags are ignored when --recipe is given; stages come from {}",
+                recipe_path.display()
+            );
+        }
+    }
 
     // Handle list-palettes flag early (before requiring input/output paths)
     if args.list_palettes {
-        let mut palette_manager = PaletteManager::new();
+        let mut palette_manager = palette_manager(&args);
         match palette_manager.load_all_palettes() {
             Ok(()) => {
                 println!("Available Palettes:");
@@ -70,6 +98,68 @@ fn main() -> Result<()> {
         }
     }
 
+    // Handle show-palette flag early (before requiring input/output paths)
+    if let Some(palette_name) = &args.show_palette {
+        let mut palette_manager = palette_manager(&args);
+        palette_manager.load_all_palettes()?;
+
+        let palette = palette_manager.get_palette(palette_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Palette '{}' not found.\nAvailable palettes: {}",
+                palette_name,
+                palette_manager.get_palette_names().join(", ")
+            )
+        })?;
+
+        let truecolor = env::var_os("NO_COLOR").map_or(true, |v| v.is_empty());
+        print!("{}", rustbucket::palette::ansi_swatches(palette, truecolor));
+        return Ok(());
+    }
+
+    // Handle swatch-sheet flag early (before requiring input/output paths)
+    if let Some(sheet_args) = &args.swatch_sheet {
+        let palette_name = &sheet_args[0];
+        let output_path = std::path::PathBuf::from(&sheet_args[1]);
+
+        let mut palette_manager = palette_manager(&args);
+        palette_manager.load_all_palettes()?;
+
+        let sheet = rustbucket::palette::SwatchSheet::new();
+        let image = if palette_name.eq_ignore_ascii_case("all") {
+            let palettes: Vec<&rustbucket::Palette> = palette_manager
+                .get_palette_names()
+                .iter()
+                .filter_map(|name| palette_manager.get_palette(name))
+                .collect();
+            let (builtin, user): (Vec<_>, Vec<_>) = palettes
+                .into_iter()
+                .partition(|p| rustbucket::palette::is_builtin(p));
+            sheet.render(&[("Built-in", builtin), ("User", user)])
+        } else {
+            let palette = palette_manager.get_palette(palette_name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Palette '{}' not found.\nAvailable palettes: {}",
+                    palette_name,
+                    palette_manager.get_palette_names().join(", ")
+                )
+            })?;
+            sheet.render_palette(palette)
+        };
+
+        image.save(&output_path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to write swatch sheet {}: {}",
+                output_path.display(),
+                e
+            )
+        })?;
+
+        if !args.quiet_mode {
+            println!("Swatch sheet written to: {}", output_path.display());
+        }
+        return Ok(());
+    }
+
     // Handle create-palette flag early (before requiring input/output paths)
     if let Some(palette_path) = &args.create_palette {
         let palette_manager = PaletteManager::new();
@@ -106,37 +196,321 @@ fn main() -> Result<()> {
         let palette_name = &export_args[0];
         let output_path = std::path::PathBuf::from(&export_args[1]);
 
-        let mut palette_manager = PaletteManager::new();
+        let mut palette_manager = palette_manager(&args);
         palette_manager.load_all_palettes()?;
 
-        // Check if it's a built-in palette
-        if !palette_manager.is_builtin_palette(palette_name) {
-            let available_builtins = rustbucket::palette::BuiltinPalettes::get_names();
-            return Err(anyhow::anyhow!(
-                "Palette '{}' is not a built-in palette.\nAvailable built-in palettes: {}",
+        let palette = palette_manager.get_palette(palette_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Palette '{}' not found.\nAvailable palettes: {}",
                 palette_name,
-                available_builtins.join(", ")
-            ));
-        }
+                palette_manager.get_palette_names().join(", ")
+            )
+        })?;
 
-        if let Some(palette) = palette_manager.get_palette(palette_name) {
-            let toml_loader = rustbucket::palette::TomlPaletteLoader::new();
-            toml_loader.save_palette(palette, &output_path)?;
+        let registry = rustbucket::palette::ExporterRegistry::new();
+        registry.export_to_file(palette, &output_path, args.export_format.as_deref())?;
 
-            if !args.quiet_mode {
-                println!(
-                    "Built-in palette '{}' exported to: {}",
-                    palette_name,
-                    output_path.display()
-                );
+        if !args.quiet_mode {
+            println!(
+                "Palette '{}' exported to: {}",
+                palette_name,
+                output_path.display()
+            );
+            if registry
+                .for_path(&output_path)
+                .is_some_and(|exporter| exporter.name() == "toml")
+                && args.export_format.is_none()
+            {
                 println!();
                 println!("You can now customize this palette and use it with:");
                 println!("  rtbt -i input.png -o output.png -p {}", palette_name);
             }
-            return Ok(());
+        }
+        return Ok(());
+    }
+
+    // Handle import-palette flag early (before requiring input/output paths)
+    if let Some(import_args) = &args.import_palette {
+        if import_args.len() != 2 {
+            return Err(anyhow::anyhow!(
+                "--import-palette requires exactly 2 arguments: source_path output_path"
+            ));
+        }
+
+        let source_path = &import_args[0];
+        let output_path = &import_args[1];
+
+        let toml_loader = rustbucket::palette::TomlPaletteLoader::new();
+        let palettes = toml_loader.load_file_palettes(source_path)?;
+        let names: Vec<&str> = palettes.iter().map(|p| p.name.as_str()).collect();
+        let palette = match &args.import_scheme {
+            Some(scheme) => palettes
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(scheme))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Scheme '{}' not found in {}.\nAvailable schemes: {}",
+                        scheme,
+                        source_path.display(),
+                        names.join(", ")
+                    )
+                })?,
+            None if palettes.len() == 1 => &palettes[0],
+            None => {
+                return Err(anyhow::anyhow!(
+                "{} holds {} color schemes; pick one with --import-scheme.\nAvailable schemes: {}",
+                source_path.display(),
+                palettes.len(),
+                names.join(", ")
+            ))
+            }
+        };
+        toml_loader.save_palette(palette, output_path)?;
+
+        if !args.quiet_mode {
+            println!(
+                "Imported {} colors from {} into: {}",
+                palette.colors.len(),
+                source_path.display(),
+                output_path.display()
+            );
+            println!();
+            println!("Copy it into your palette directory, then use it with:");
+            println!("  rtbt -i input.png -o output.png -p {}", palette.name);
+        }
+        return Ok(());
+    }
+
+    // Handle save-recipe flag early (before requiring input/output paths)
+    if let Some(recipe_path) = &args.save_recipe {
+        let recipe = Recipe::from_config(&config);
+        recipe.save(recipe_path)?;
+
+        if !args.quiet_mode {
+            println!("Recipe written to: {}", recipe_path.display());
+            println!();
+            println!("Edit the stages, then use it with:");
+            println!(
+                "  rtbt -i input.png -o output.png --recipe {}",
+                recipe_path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    // Handle extract-palette flag early (only needs the input image)
+    if let Some(palette_path) = &args.extract_palette {
+        let input_path = config
+            .input_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("--extract-palette requires an input image"))?;
+        let name = args.extract_name.clone().unwrap_or_else(|| {
+            palette_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("extracted")
+                .to_string()
+        });
+
+        let img = rustbucket::image::load_image(input_path)?;
+        let mut extractor = PaletteExtractor::new(args.extract_colors as usize)
+            .with_method(args.extract_method)
+            .with_ignore_transparent(!args.extract_keep_transparent);
+        if let Some(distance) = args.extract_min_distance {
+            extractor = extractor.with_min_distance(distance);
+        }
+        let palette = extractor.extract(&img, &name)?;
+
+        let toml_loader = rustbucket::palette::TomlPaletteLoader::new();
+        toml_loader.save_palette(&palette, palette_path)?;
+
+        if !args.quiet_mode {
+            println!(
+                "Extracted {} colors from {} into: {}",
+                palette.colors.len(),
+                input_path.display(),
+                palette_path.display()
+            );
+            for color in &palette.colors {
+                println!("  {:<14} {}", color.name, color.hex);
+            }
+            println!();
+            println!("Copy it into your palette directory, then use it with:");
+            println!("  rtbt -i input.png -o output.png -p {}", name);
+        }
+        return Ok(());
+    }
+
+    // Handle theme flag early (palette or input image colors only)
+    if let Some(theme_path) = &args.theme {
+        let format = args
+            .theme_format
+            .or_else(|| ThemeFormat::from_path(theme_path))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Cannot tell the theme format from {}; pass --theme-format",
+                    theme_path.display()
+                )
+            })?;
+        let name = args.theme_name.clone().unwrap_or_else(|| {
+            theme_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.trim_start_matches('.'))
+                .filter(|s| !s.is_empty())
+                .unwrap_or("rtbt")
+                .to_string()
+        });
+
+        let source = match &config.input_path {
+            Some(input_path) => {
+                let img = rustbucket::image::load_image(input_path)?;
+                PaletteExtractor::new(16)
+                    .with_method(ExtractMethod::Kmeans)
+                    .extract(&img, &name)?
+            }
+            None => {
+                let mut palette_manager = palette_manager(&args);
+                palette_manager.load_all_palettes()?;
+                let palette = palette_manager
+                    .get_palette(&config.palette)
+                    .ok_or_else(|| anyhow::anyhow!("Palette '{}' not found", config.palette))?;
+                let mut palette = palette.clone();
+                if !config.colors.is_empty() {
+                    palette.colors = palette
+                        .select_colors(&config.colors)
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                }
+                palette
+            }
+        };
+
+        let theme = ThemeGenerator::new()
+            .with_variant(args.theme_variant)
+            .with_min_contrast(args.theme_min_contrast)
+            .generate(&source, &name)?;
+        std::fs::write(theme_path, theme.render(format)?)?;
+
+        if !args.quiet_mode {
+            println!(
+                "{} {:?} theme written to: {}",
+                if theme.dark { "Dark" } else { "Light" },
+                format,
+                theme_path.display()
+            );
+            println!(
+                "  Contrast: foreground {:.1}:1, accents at least {:.1}:1",
+                rustbucket::palette::contrast_ratio(theme.foreground, theme.background),
+                theme.accent_contrast()
+            );
+            for adjustment in &theme.adjustments {
+                println!("  Adjusted {}", adjustment);
+            }
+        }
+        return Ok(());
+    }
+
+    // Handle contact-sheet flag: one input rendered with many palettes
+    if let Some(sheet_path) = &args.contact_sheet {
+        let input_path = config
+            .input_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("--contact-sheet requires an input image (-i)"))?;
+
+        let mut palette_manager = palette_manager(&args);
+        palette_manager.load_all_palettes()?;
+
+        let palettes: Vec<&rustbucket::Palette> = if args.contact_palettes.is_empty() {
+            let all: Vec<&rustbucket::Palette> = palette_manager
+                .get_palette_names()
+                .iter()
+                .filter_map(|name| palette_manager.get_palette(name))
+                .collect();
+            let (builtin, user): (Vec<_>, Vec<_>) = all
+                .into_iter()
+                .partition(|p| rustbucket::palette::is_builtin(p));
+            builtin.into_iter().chain(user).collect()
         } else {
-            return Err(anyhow::anyhow!("Failed to load palette '{}'", palette_name));
+            args.contact_palettes
+                .iter()
+                .map(|name| {
+                    palette_manager.get_palette(name.trim()).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Palette '{}' not found.\nAvailable palettes: {}",
+                            name,
+                            palette_manager.get_palette_names().join(", ")
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?
+        };
+
+        let img = rustbucket::image::load_image(input_path)?;
+        // Color selections name one palette's colors, so every tile uses
+        // its whole palette
+        let mut tile_config = config.clone();
+        tile_config.colors.clear();
+
+        let start = std::time::Instant::now();
+        let sheet = rustbucket::ContactSheet::new(rustbucket::ImageProcessor::new(tile_config))
+            .with_columns(args.contact_columns)
+            .with_tile_width(args.contact_tile_width)
+            .render(&img, &palettes)?;
+        sheet.save(sheet_path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to write contact sheet {}: {}",
+                sheet_path.display(),
+                e
+            )
+        })?;
+
+        if !args.quiet_mode {
+            println!(
+                "Contact sheet with {} palettes written to: {} ({:.2}s)",
+                palettes.len(),
+                sheet_path.display(),
+                start.elapsed().as_secs_f64()
+            );
         }
+        return Ok(());
+    }
+
+    // Handle rank-palettes flag: report how well every palette fits the input
+    if args.rank_palettes {
+        let input_path = config
+            .input_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("--rank-palettes requires an input image (-i)"))?;
+
+        let mut palette_manager = palette_manager(&args);
+        palette_manager.load_all_palettes()?;
+        let names = palette_manager.get_palette_names();
+        let palettes: Vec<&rustbucket::Palette> = names
+            .iter()
+            .filter_map(|name| palette_manager.get_palette(name))
+            .collect();
+
+        let img = rustbucket::image::load_image(input_path)?;
+        let fits = palette_ranker(&args).rank(&img, &palettes)?;
+
+        println!("Palette fit for {}:", input_path.display());
+        println!();
+        println!(
+            "  {:>4}  {:<24} {:>9}  {:>10}",
+            "Rank", "Palette", "Mean ΔE", "Uncovered"
+        );
+        for (rank, fit) in fits.iter().enumerate() {
+            println!(
+                "  {:>4}  {:<24} {:>9.2}  {:>9.1}%",
+                rank + 1,
+                fit.name,
+                fit.mean_delta_e,
+                fit.coverage_error * 100.0
+            );
+        }
+        return Ok(());
     }
 
     // Get input path (required for normal operation)
@@ -148,13 +522,39 @@ fn main() -> Result<()> {
     info!("Loading input image: {}", input_path.display());
     info!("Set output image name: {}", config.output_path.display());
 
-    let mut palette_manager = PaletteManager::new();
+    let mut palette_manager = palette_manager(&args);
+    // Decoded by `-p auto` and reused for the conversion
+    let mut loaded_image = None;
 
     match palette_manager.load_all_palettes() {
         Ok(()) => {
             let palette_names = palette_manager.get_palette_names();
             info!("Available palettes: {}", palette_names.join(", "));
 
+            // `-p auto` picks the best fit unless a palette is actually named auto
+            if config.palette.eq_ignore_ascii_case("auto")
+                && !palette_manager.palette_exists("auto")
+            {
+                let palettes: Vec<&rustbucket::Palette> = palette_names
+                    .iter()
+                    .filter_map(|name| palette_manager.get_palette(name))
+                    .collect();
+                let img = rustbucket::image::load_image(input_path)?;
+                let fits = palette_ranker(&args).rank(&img, &palettes)?;
+                let best = &fits[0];
+
+                if !config.quiet_mode {
+                    println!(
+                        "Best-fit palette: {} (mean ΔE {:.2}, {:.1}% uncovered)",
+                        best.name,
+                        best.mean_delta_e,
+                        best.coverage_error * 100.0
+                    );
+                }
+                config.palette = best.name.clone();
+                loaded_image = Some(img);
+            }
+
             if !palette_manager.palette_exists(&config.palette) {
                 warn!(
                     "Palette '{}' not found, using default 'nord'",
@@ -173,10 +573,21 @@ fn main() -> Result<()> {
                 );
 
                 if !config.colors.is_empty() {
-                    let available_colors: Vec<String> =
-                        palette.colors.iter().map(|c| c.name.clone()).collect();
+                    let available_colors: Vec<String> = palette
+                        .colors
+                        .iter()
+                        .map(|c| c.name.clone())
+                        .chain(palette.aliases.keys().cloned())
+                        .chain(palette.groups.keys().map(|g| format!("@{}", g)))
+                        .collect();
                     for color in &config.colors {
-                        if !available_colors.contains(color) {
+                        // Trimmed the same way as `Palette::select_colors`
+                        let color = color.trim();
+                        let found = match color.strip_prefix('@') {
+                            Some(group) => palette.group(group).is_some(),
+                            None => palette.find_color(color).is_some(),
+                        };
+                        if !found {
                             warn!("Color '{}' not found in palette '{}'", color, palette.name);
                             info!("Available colors: {}", available_colors.join(", "));
                         }
@@ -191,7 +602,7 @@ fn main() -> Result<()> {
     }
 
     if config.enable_blur {
-        info!("Blur enabled");
+        info!("Blur enabled ({:?})", config.blur_kind);
     }
 
     if config.disable_avg_pixels {
@@ -234,11 +645,99 @@ fn main() -> Result<()> {
                 config.colors.join(", ")
             }
         );
+        if let Some(recipe_path) = &config.recipe {
+            println!("  Recipe: {}", recipe_path.display());
+        }
+        if config.denoise {
+            println!(
+                "  Denoise: {:?}, threshold {}, strength {}, radius {}",
+                config.denoise_mode,
+                config.denoise_threshold,
+                config.denoise_strength,
+                config.denoise_radius
+            );
+        }
+        if !config.adjustments.is_identity() {
+            println!("  Adjustments: {:?}", config.adjustments);
+        }
+        if config.bilateral {
+            println!(
+                "  Bilateral: sigma space {}, sigma color {}",
+                config.bilateral_sigma_space, config.bilateral_sigma_color
+            );
+        }
+        if config.kuwahara {
+            println!(
+                "  Kuwahara: {:?}, radius {}",
+                config.kuwahara_kind, config.kuwahara_radius
+            );
+        }
         println!("  Blur: {}", config.enable_blur);
+        if config.tone_remap {
+            println!("  Tone remap strength: {}", config.tone_remap_strength);
+        }
+        if config.pre_blur {
+            println!("  Pre-conversion blur: true");
+        }
+        if config.enable_blur || config.pre_blur {
+            let blur_config = rustbucket::ImageProcessor::new(config.clone()).blur_config();
+            println!(
+                "  Blur settings: {:?}, sigma {}, radius {}",
+                blur_config.kind, blur_config.sigma, blur_config.radius
+            );
+        }
+        if config.blur_resnap {
+            println!("  Re-snap to palette: {:?} dithering", config.dithering);
+        }
+        if config.halftone {
+            println!(
+                "  Halftone: {:?} dots, {} px cells at {} degrees, background {}",
+                config.halftone_shape,
+                config.halftone_cell_size,
+                config.halftone_angle,
+                config
+                    .halftone_background
+                    .as_deref()
+                    .unwrap_or("first color")
+            );
+        }
+        if config.recolor {
+            println!(
+                "  Recolor: {} clusters, lightness weight {}, shading {}",
+                config.recolor_clusters, config.recolor_lightness_weight, config.recolor_shading
+            );
+        }
+        if config.despeckle {
+            println!(
+                "  Despeckle: {:?}, min region {}",
+                config.despeckle_mode, config.despeckle_min_region
+            );
+        }
+        if config.outline {
+            println!(
+                "  Outline: {:?}, color {}, threshold {}, thickness {}",
+                config.outline_detector,
+                config.outline_color.as_deref().unwrap_or("darkest"),
+                config.outline_threshold,
+                config.outline_thickness
+            );
+        }
+        if !config.retro.is_empty() {
+            println!(
+                "  Retro: {:?}, strength {}, size {}, re-snap {}",
+                config.retro, config.retro_strength, config.retro_size, config.retro_resnap
+            );
+        }
         println!("  Avg optimization: {}", !config.disable_avg_pixels);
         if let Some((w, h)) = config.pixels_area {
             println!("  Pixel area: {}x{}", w, h);
         }
+        if config.cell_shape != CellShape::Square {
+            println!("  Cell shape: {:?}", config.cell_shape);
+        }
+        if let Some(color) = &config.grout_color {
+            println!("  Grout: {}, {} px", color, config.grout_width);
+        }
         println!();
     }
 
@@ -287,8 +786,23 @@ fn main() -> Result<()> {
         info!("Starting image conversion...");
         let start_time = std::time::Instant::now();
 
-        match ImagePipeline::process_file(input_path, &config.output_path, palette, &config) {
-            Ok(()) => {
+        let result = match loaded_image {
+            Some(img) => ImagePipeline::process_loaded_with_diagnostics(
+                img,
+                &config.output_path,
+                palette,
+                &config,
+            ),
+            None => ImagePipeline::process_file_with_diagnostics(
+                input_path,
+                &config.output_path,
+                palette,
+                &config,
+            ),
+        };
+
+        match result {
+            Ok(diagnostics) => {
                 let duration = start_time.elapsed();
                 info!(
                     "Image processing completed in {:.2}s",
@@ -299,6 +813,9 @@ fn main() -> Result<()> {
                     println!("Image converted successfully!");
                     println!("   Output saved to: {}", config.output_path.display());
                     println!("   Processing time: {:.2}s", duration.as_secs_f32());
+                    if let Some(report) = &diagnostics.tone_remap {
+                        println!("   Tone remap: {}", report);
+                    }
                 }
             }
             Err(e) => {
@@ -0,0 +1,297 @@
+use crate::{Color, Palette, RustBucketError};
+use serde_yaml::Value;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::parser::rgb_to_hex;
+use super::terminal::{parse_terminal_color, TerminalFormat};
+
+/// Loads base16 and base24 scheme YAML files as palettes named
+/// `base16-<file>` or `base24-<file>`, with colors `base00` to `base0F`
+/// (and `base10` to `base17` for base24)
+pub struct Base16Loader {
+    search_paths: Vec<PathBuf>,
+}
+
+impl Base16Loader {
+    pub fn new() -> Self {
+        Self {
+            search_paths: Self::get_default_search_paths(),
+        }
+    }
+
+    pub fn with_paths(paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths: paths,
+        }
+    }
+
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            search_paths: vec![path.as_ref().to_path_buf()],
+        }
+    }
+
+    /// `RTBT_BASE16_DIR`, then `~/.config/rtbt/base16/`
+    fn get_default_search_paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        if let Ok(custom_path) = env::var("RTBT_BASE16_DIR") {
+            paths.push(PathBuf::from(custom_path));
+        }
+
+        if let Some(config_dir) = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        {
+            paths.push(config_dir.join("rtbt").join("base16"));
+        }
+
+        paths
+    }
+
+    /// Load every scheme in the search paths and their direct subdirectories,
+    /// so a checkout of the tinted-theming `schemes` repository works as is
+    pub fn load_schemes(&self) -> crate::Result<Vec<Palette>> {
+        let mut palettes: Vec<Palette> = Vec::new();
+
+        for search_path in &self.search_paths {
+            if !search_path.is_dir() {
+                log::debug!("Base16 directory not found: {}", search_path.display());
+                continue;
+            }
+
+            for path in Self::scheme_files(search_path)? {
+                match load_base16_scheme(&path) {
+                    Ok(palette) if palettes.iter().any(|p| p.name == palette.name) => {
+                        log::debug!(
+                            "Skipping duplicate scheme '{}' from {}",
+                            palette.name,
+                            path.display()
+                        );
+                    }
+                    Ok(palette) => palettes.push(palette),
+                    Err(e) => log::warn!("Failed to load scheme {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        log::info!("Loaded {} base16/base24 schemes", palettes.len());
+        Ok(palettes)
+    }
+
+    fn scheme_files(dir: &Path) -> crate::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).map_err(RustBucketError::IoError)? {
+            let path = entry.map_err(RustBucketError::IoError)?.path();
+            if path.is_dir() {
+                for entry in fs::read_dir(&path).map_err(RustBucketError::IoError)? {
+                    let path = entry.map_err(RustBucketError::IoError)?.path();
+                    if is_yaml(&path) {
+                        files.push(path);
+                    }
+                }
+            } else if is_yaml(&path) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl Default for Base16Loader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.is_file()
+        && matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml") | Some("yml")
+        )
+}
+
+/// Load one base16 or base24 scheme file
+pub fn load_base16_scheme<P: AsRef<Path>>(path: P) -> crate::Result<Palette> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(RustBucketError::IoError)?;
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("scheme");
+
+    parse_base16_scheme(&content, stem)
+        .map_err(|e| RustBucketError::PaletteParseError(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse a scheme in either the legacy layout (top-level `scheme` and
+/// `baseXX` keys) or the current one (`system`, `name` and a `palette` map)
+pub fn parse_base16_scheme(content: &str, file_stem: &str) -> crate::Result<Palette> {
+    let yaml: Value = serde_yaml::from_str(content)
+        .map_err(|e| RustBucketError::PaletteParseError(format!("Invalid scheme YAML: {}", e)))?;
+    let colors_map = yaml.get("palette").unwrap_or(&yaml);
+
+    let mut colors = Vec::new();
+    for index in 0..0x18 {
+        let key = format!("base{:02X}", index);
+        let value = colors_map
+            .get(&key)
+            .or_else(|| colors_map.get(key.to_lowercase()))
+            .and_then(|v| v.as_str());
+        let Some(value) = value else {
+            continue;
+        };
+        let rgb = parse_terminal_color(value).ok_or_else(|| {
+            RustBucketError::PaletteParseError(format!("Invalid color '{}' for {}", value, key))
+        })?;
+        colors.push(Color {
+            name: key,
+            hex: rgb_to_hex(rgb[0], rgb[1], rgb[2]),
+        });
+    }
+
+    if colors.len() < 16 {
+        return Err(RustBucketError::PaletteParseError(format!(
+            "Expected base00 to base0F, found {} colors",
+            colors.len()
+        )));
+    }
+
+    let system = yaml
+        .get("system")
+        .and_then(|s| s.as_str())
+        .unwrap_or(if colors.len() > 16 {
+            "base24"
+        } else {
+            "base16"
+        });
+    let name = if file_stem.starts_with(&format!("{}-", system)) {
+        file_stem.to_string()
+    } else {
+        format!("{}-{}", system, file_stem)
+    };
+
+    Ok(Palette {
+        path: PathBuf::from(format!("user://{}", name)),
+        name,
+        colors,
+        ..Default::default()
+    })
+}
+
+/// pywal's cache file, `$XDG_CACHE_HOME/wal/colors.json` or
+/// `~/.cache/wal/colors.json`
+pub fn default_pywal_cache() -> Option<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|cache| cache.join("wal").join("colors.json"))
+}
+
+/// The current pywal colors as a palette named `pywal`
+pub fn load_pywal_palette<P: AsRef<Path>>(path: P) -> crate::Result<Palette> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(RustBucketError::IoError)?;
+    TerminalFormat::Pywal
+        .parse(&content, "pywal")?
+        .pop()
+        .ok_or_else(|| {
+            RustBucketError::PaletteParseError(format!("No colors in {}", path.display()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn scheme(prefix: &str, count: usize) -> String {
+        let mut yaml = String::from("scheme: \"Test\"\nauthor: \"Someone\"\n");
+        for index in 0..count {
+            yaml.push_str(&format!(
+                "base{:02X}: \"{}{:02x}\"\n",
+                index,
+                prefix,
+                index * 10
+            ));
+        }
+        yaml
+    }
+
+    #[test]
+    fn test_parse_legacy_base16() {
+        let palette = parse_base16_scheme(&scheme("1122", 16), "tomorrow-night").unwrap();
+        assert_eq!(palette.name, "base16-tomorrow-night");
+        assert_eq!(palette.colors.len(), 16);
+        assert_eq!(palette.colors[0].name, "base00");
+        assert_eq!(palette.colors[15].name, "base0F");
+        assert_eq!(palette.colors[15].hex, "#112296");
+
+        // Prefixed file names aren't prefixed twice
+        let palette = parse_base16_scheme(&scheme("1122", 16), "base16-ocean").unwrap();
+        assert_eq!(palette.name, "base16-ocean");
+    }
+
+    #[test]
+    fn test_parse_base24_palette_map() {
+        let mut yaml =
+            String::from("system: \"base24\"\nname: \"Test\"\nvariant: \"dark\"\npalette:\n");
+        for index in 0..24 {
+            yaml.push_str(&format!("  base{:02X}: \"#0000{:02x}\"\n", index, index));
+        }
+
+        let palette = parse_base16_scheme(&yaml, "dracula").unwrap();
+        assert_eq!(palette.name, "base24-dracula");
+        assert_eq!(palette.colors.len(), 24);
+        assert_eq!(palette.colors[23].name, "base17");
+        assert_eq!(palette.colors[23].hex, "#000017");
+    }
+
+    #[test]
+    fn test_parse_incomplete_scheme() {
+        assert!(parse_base16_scheme(&scheme("1122", 8), "short").is_err());
+        assert!(parse_base16_scheme("base00: \"zzzzzz\"\n", "bad").is_err());
+        assert!(parse_base16_scheme("not: [valid", "bad").is_err());
+    }
+
+    #[test]
+    fn test_load_schemes_from_directory_tree() {
+        let temp_dir = tempdir().unwrap();
+        let base16_dir = temp_dir.path().join("base16");
+        fs::create_dir(&base16_dir).unwrap();
+        fs::write(base16_dir.join("ocean.yaml"), scheme("2233", 16)).unwrap();
+        fs::write(temp_dir.path().join("forest.yml"), scheme("3344", 16)).unwrap();
+        fs::write(temp_dir.path().join("broken.yaml"), "base00: nope\n").unwrap();
+        fs::write(temp_dir.path().join("README.md"), "# schemes\n").unwrap();
+
+        let palettes = Base16Loader::with_path(temp_dir.path())
+            .load_schemes()
+            .unwrap();
+        let names: Vec<&str> = palettes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["base16-ocean", "base16-forest"]);
+
+        let missing = Base16Loader::with_path(temp_dir.path().join("missing"));
+        assert!(missing.load_schemes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_pywal_palette() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("colors.json");
+        fs::write(
+            &path,
+            r##"{ "special": { "background": "#101010" }, "colors": { "color1": "#aa0000" } }"##,
+        )
+        .unwrap();
+
+        let palette = load_pywal_palette(&path).unwrap();
+        assert_eq!(palette.name, "pywal");
+        assert_eq!(palette.colors[0].name, "background");
+        assert_eq!(palette.colors[1].hex, "#AA0000");
+        assert!(load_pywal_palette(temp_dir.path().join("missing.json")).is_err());
+    }
+}
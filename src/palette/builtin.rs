@@ -1,4 +1,5 @@
 use crate::{Color, Palette};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// Built-in palette definitions
@@ -149,6 +150,35 @@ impl BuiltinPalettes {
                     hex: "#B48EAD".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("polar_night", &["nord0", "nord1", "nord2", "nord3"]),
+                ("snow_storm", &["nord4", "nord5", "nord6"]),
+                ("frost", &["nord7", "nord8", "nord9", "nord10"]),
+                (
+                    "aurora",
+                    &["nord11", "nord12", "nord13", "nord14", "nord15"],
+                ),
+                ("background", &["nord0", "nord1", "nord2", "nord3"]),
+                ("foreground", &["nord4", "nord5", "nord6"]),
+                (
+                    "accent",
+                    &[
+                        "nord7", "nord8", "nord9", "nord10", "nord11", "nord12", "nord13",
+                        "nord14", "nord15",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "nord0"),
+                ("foreground", "nord4"),
+                ("cyan", "nord8"),
+                ("blue", "nord10"),
+                ("red", "nord11"),
+                ("orange", "nord12"),
+                ("yellow", "nord13"),
+                ("green", "nord14"),
+                ("purple", "nord15"),
+            ]),
         }
     }
 
@@ -202,6 +232,15 @@ impl BuiltinPalettes {
                     hex: "#F1FA8C".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background", "current_line"]),
+                ("foreground", &["foreground", "comment"]),
+                (
+                    "accent",
+                    &["cyan", "green", "orange", "pink", "purple", "red", "yellow"],
+                ),
+            ]),
+            aliases: aliases(&[("selection", "current_line"), ("magenta", "pink")]),
         }
     }
 
@@ -279,6 +318,23 @@ impl BuiltinPalettes {
                     hex: "#FE8019".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["dark0", "dark1", "dark2", "dark3", "dark4"]),
+                (
+                    "foreground",
+                    &["light0", "light1", "light2", "light3", "light4"],
+                ),
+                (
+                    "accent",
+                    &["red", "green", "yellow", "blue", "purple", "aqua", "orange"],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "dark0"),
+                ("foreground", "light1"),
+                ("cyan", "aqua"),
+                ("magenta", "purple"),
+            ]),
         }
     }
 
@@ -324,6 +380,15 @@ impl BuiltinPalettes {
                     hex: "#AE81FF".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background"]),
+                ("foreground", &["foreground", "comment"]),
+                (
+                    "accent",
+                    &["red", "orange", "yellow", "green", "blue", "purple"],
+                ),
+            ]),
+            aliases: aliases(&[("pink", "red"), ("cyan", "blue"), ("magenta", "purple")]),
         }
     }
 
@@ -397,6 +462,23 @@ impl BuiltinPalettes {
                     hex: "#859900".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["base03", "base02"]),
+                ("content", &["base01", "base00", "base0", "base1"]),
+                ("foreground", &["base0", "base1"]),
+                ("light_background", &["base2", "base3"]),
+                (
+                    "accent",
+                    &[
+                        "yellow", "orange", "red", "magenta", "violet", "blue", "cyan", "green",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "base03"),
+                ("foreground", "base0"),
+                ("purple", "violet"),
+            ]),
         }
     }
 
@@ -470,6 +552,36 @@ impl BuiltinPalettes {
                     hex: "#1E1E2E".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["base"]),
+                ("foreground", &["text"]),
+                (
+                    "accent",
+                    &[
+                        "rosewater",
+                        "flamingo",
+                        "pink",
+                        "mauve",
+                        "red",
+                        "maroon",
+                        "peach",
+                        "yellow",
+                        "green",
+                        "teal",
+                        "sky",
+                        "sapphire",
+                        "blue",
+                        "lavender",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "base"),
+                ("foreground", "text"),
+                ("purple", "mauve"),
+                ("orange", "peach"),
+                ("cyan", "sky"),
+            ]),
         }
     }
 
@@ -595,6 +707,23 @@ impl BuiltinPalettes {
                     hex: "#DB4B4B".to_string(),
                 },
             ],
+            groups: groups(&[
+                (
+                    "background",
+                    &["bg", "bg_dark", "bg_highlight", "terminal_black"],
+                ),
+                ("foreground", &["fg", "fg_dark"]),
+                ("muted", &["fg_gutter", "dark3", "comment", "dark5"]),
+                (
+                    "accent",
+                    &[
+                        "blue0", "blue", "cyan", "blue1", "blue2", "blue5", "blue6", "blue7",
+                        "magenta", "magenta2", "purple", "orange", "yellow", "green", "green1",
+                        "green2", "teal", "red", "red1",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[("background", "bg"), ("foreground", "fg")]),
         }
     }
 
@@ -669,6 +798,30 @@ impl BuiltinPalettes {
                     hex: "#AB7967".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["base00", "base01", "base02", "base03"]),
+                ("foreground", &["base04", "base05", "base06", "base07"]),
+                (
+                    "accent",
+                    &[
+                        "base08", "base09", "base0A", "base0B", "base0C", "base0D", "base0E",
+                        "base0F",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "base00"),
+                ("foreground", "base05"),
+                ("red", "base08"),
+                ("orange", "base09"),
+                ("yellow", "base0A"),
+                ("green", "base0B"),
+                ("cyan", "base0C"),
+                ("blue", "base0D"),
+                ("purple", "base0E"),
+                ("magenta", "base0E"),
+                ("brown", "base0F"),
+            ]),
         }
     }
 
@@ -718,6 +871,17 @@ impl BuiltinPalettes {
                     hex: "#EEFFFF".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background"]),
+                ("foreground", &["foreground", "white"]),
+                (
+                    "accent",
+                    &[
+                        "cursor", "red", "green", "yellow", "blue", "magenta", "cyan",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[("purple", "magenta")]),
         }
     }
 
@@ -759,6 +923,19 @@ impl BuiltinPalettes {
                     hex: "#ABB2BF".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["black"]),
+                ("foreground", &["white"]),
+                (
+                    "accent",
+                    &["red", "green", "yellow", "blue", "magenta", "cyan"],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "black"),
+                ("foreground", "white"),
+                ("purple", "magenta"),
+            ]),
         }
     }
 
@@ -832,6 +1009,58 @@ impl BuiltinPalettes {
                     hex: "#FFFFFF".to_string(),
                 },
             ],
+            groups: groups(&[
+                (
+                    "normal",
+                    &[
+                        "black",
+                        "dark_red",
+                        "dark_green",
+                        "dark_yellow",
+                        "dark_blue",
+                        "dark_magenta",
+                        "dark_cyan",
+                        "gray",
+                    ],
+                ),
+                (
+                    "bright",
+                    &[
+                        "dark_gray",
+                        "red",
+                        "green",
+                        "yellow",
+                        "blue",
+                        "magenta",
+                        "cyan",
+                        "white",
+                    ],
+                ),
+                ("background", &["black"]),
+                ("foreground", &["gray", "white"]),
+                (
+                    "accent",
+                    &[
+                        "dark_red",
+                        "dark_green",
+                        "dark_yellow",
+                        "dark_blue",
+                        "dark_magenta",
+                        "dark_cyan",
+                        "red",
+                        "green",
+                        "yellow",
+                        "blue",
+                        "magenta",
+                        "cyan",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "black"),
+                ("foreground", "gray"),
+                ("purple", "magenta"),
+            ]),
         }
     }
 
@@ -905,6 +1134,30 @@ impl BuiltinPalettes {
                     hex: "#4E5166".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["base00", "base01", "base02", "base03"]),
+                ("foreground", &["base04", "base05", "base06", "base07"]),
+                (
+                    "accent",
+                    &[
+                        "base08", "base09", "base0A", "base0B", "base0C", "base0D", "base0E",
+                        "base0F",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "base00"),
+                ("foreground", "base05"),
+                ("red", "base08"),
+                ("orange", "base09"),
+                ("yellow", "base0A"),
+                ("green", "base0B"),
+                ("cyan", "base0C"),
+                ("blue", "base0D"),
+                ("purple", "base0E"),
+                ("magenta", "base0E"),
+                ("brown", "base0F"),
+            ]),
         }
     }
 
@@ -954,6 +1207,15 @@ impl BuiltinPalettes {
                     hex: "#CBE3E7".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background", "black"]),
+                ("foreground", &["foreground", "white"]),
+                (
+                    "accent",
+                    &["red", "green", "yellow", "blue", "magenta", "cyan"],
+                ),
+            ]),
+            aliases: aliases(&[("purple", "magenta")]),
         }
     }
 
@@ -995,6 +1257,19 @@ impl BuiltinPalettes {
                     hex: "#F8F8F2".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["black"]),
+                ("foreground", &["white"]),
+                (
+                    "accent",
+                    &["red", "green", "yellow", "blue", "magenta", "cyan"],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "black"),
+                ("foreground", "white"),
+                ("purple", "magenta"),
+            ]),
         }
     }
 
@@ -1052,6 +1327,19 @@ impl BuiltinPalettes {
                     hex: "#B39DF3".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["bg0", "bg1", "bg2", "bg3", "bg4"]),
+                ("foreground", &["fg"]),
+                (
+                    "accent",
+                    &["red", "orange", "yellow", "green", "blue", "purple"],
+                ),
+            ]),
+            aliases: aliases(&[
+                ("background", "bg0"),
+                ("foreground", "fg"),
+                ("magenta", "purple"),
+            ]),
         }
     }
 
@@ -1101,6 +1389,15 @@ impl BuiltinPalettes {
                     hex: "#D4D4D6".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background", "black"]),
+                ("foreground", &["foreground", "white"]),
+                (
+                    "accent",
+                    &["red", "green", "yellow", "blue", "magenta", "cyan"],
+                ),
+            ]),
+            aliases: aliases(&[("purple", "magenta")]),
         }
     }
 
@@ -1150,10 +1447,40 @@ impl BuiltinPalettes {
                     hex: "#66D9EF".to_string(),
                 },
             ],
+            groups: groups(&[
+                ("background", &["background"]),
+                ("foreground", &["foreground"]),
+                (
+                    "accent",
+                    &[
+                        "pink", "purple", "cyan", "green", "yellow", "orange", "red", "blue",
+                    ],
+                ),
+            ]),
+            aliases: aliases(&[("magenta", "pink")]),
         }
     }
 }
 
+fn groups(groups: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+    groups
+        .iter()
+        .map(|(name, members)| {
+            (
+                name.to_string(),
+                members.iter().map(|m| m.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+fn aliases(aliases: &[(&str, &str)]) -> BTreeMap<String, String> {
+    aliases
+        .iter()
+        .map(|(alias, name)| (alias.to_string(), name.to_string()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1197,4 +1524,37 @@ mod tests {
     fn test_unknown_palette() {
         assert!(BuiltinPalettes::get_palette("unknown").is_none());
     }
+
+    #[test]
+    fn test_builtin_groups_and_aliases() {
+        for palette in BuiltinPalettes::get_all() {
+            let has = |name: &str| palette.colors.iter().any(|c| c.name == name);
+            for group in ["background", "foreground", "accent"] {
+                assert!(
+                    palette.group(group).is_some(),
+                    "{} @{}",
+                    palette.name,
+                    group
+                );
+            }
+            for (group, members) in &palette.groups {
+                for member in members {
+                    assert!(has(member), "{} @{}: {}", palette.name, group, member);
+                }
+            }
+            for (alias, target) in &palette.aliases {
+                assert!(has(target), "{} alias {}", palette.name, alias);
+                assert!(
+                    !has(alias),
+                    "{} alias {} shadows a color",
+                    palette.name,
+                    alias
+                );
+            }
+        }
+
+        let nord = BuiltinPalettes::get_palette("nord").unwrap();
+        assert_eq!(nord.group("frost").unwrap().len(), 4);
+        assert_eq!(nord.find_color("red").unwrap().name, "nord11");
+    }
 }
@@ -0,0 +1,435 @@
+use crate::{Color, Palette, RustBucketError};
+use std::collections::{BTreeMap, HashMap};
+
+/// How a user palette is derived from other palettes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Composition {
+    /// Parent palettes, merged in order; later parents override colors of
+    /// the same name
+    pub extends: Vec<String>,
+    /// Keep only these inherited colors
+    pub include: Option<Vec<String>>,
+    /// Drop these inherited colors
+    pub exclude: Vec<String>,
+}
+
+impl Composition {
+    pub fn is_empty(&self) -> bool {
+        self.extends.is_empty() && self.include.is_none() && self.exclude.is_empty()
+    }
+}
+
+/// A user palette before its parents are resolved. The palette's own colors
+/// override inherited colors of the same name and are appended otherwise.
+#[derive(Debug, Clone)]
+pub struct PaletteDefinition {
+    pub palette: Palette,
+    pub composition: Composition,
+}
+
+impl From<Palette> for PaletteDefinition {
+    fn from(palette: Palette) -> Self {
+        Self {
+            palette,
+            composition: Composition::default(),
+        }
+    }
+}
+
+/// Resolve every definition against `base` (keyed by lowercase name) and the
+/// other definitions. Results are in the same order as `definitions`.
+pub fn resolve_definitions(
+    base: &HashMap<String, Palette>,
+    definitions: &[PaletteDefinition],
+) -> Vec<crate::Result<Palette>> {
+    let mut resolver = Resolver::new(base, definitions);
+    definitions
+        .iter()
+        .map(|d| resolver.resolve(&d.palette.name.to_lowercase()))
+        .collect()
+}
+
+/// Resolve a single definition against `base`
+pub fn resolve_definition(
+    base: &HashMap<String, Palette>,
+    definition: &PaletteDefinition,
+) -> crate::Result<Palette> {
+    Resolver::new(base, std::slice::from_ref(definition))
+        .resolve(&definition.palette.name.to_lowercase())
+}
+
+struct Resolver<'a> {
+    base: &'a HashMap<String, Palette>,
+    definitions: HashMap<String, &'a PaletteDefinition>,
+    resolved: HashMap<String, Palette>,
+    stack: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(base: &'a HashMap<String, Palette>, definitions: &'a [PaletteDefinition]) -> Self {
+        let mut by_name = HashMap::new();
+        for definition in definitions {
+            // The first definition of a name wins, as when loading
+            by_name
+                .entry(definition.palette.name.to_lowercase())
+                .or_insert(definition);
+        }
+        Self {
+            base,
+            definitions: by_name,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn resolve(&mut self, key: &str) -> crate::Result<Palette> {
+        if let Some(palette) = self.resolved.get(key) {
+            return Ok(palette.clone());
+        }
+        let definition = self.definitions[key];
+
+        if let Some(start) = self.stack.iter().position(|k| k == key) {
+            let mut chain: Vec<&str> = self.stack[start..]
+                .iter()
+                .map(|k| self.definitions[k.as_str()].palette.name.as_str())
+                .collect();
+            chain.push(&definition.palette.name);
+            return Err(RustBucketError::PaletteParseError(format!(
+                "Palette inheritance cycle: {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        self.stack.push(key.to_string());
+        let result = self.compose(definition);
+        self.stack.pop();
+
+        let palette = result?;
+        self.resolved.insert(key.to_string(), palette.clone());
+        Ok(palette)
+    }
+
+    fn parent(&mut self, child: &Palette, name: &str) -> crate::Result<Palette> {
+        let key = name.to_lowercase();
+        // A palette extending its own name builds on the palette it replaces
+        if key != child.name.to_lowercase() && self.definitions.contains_key(&key) {
+            return self.resolve(&key);
+        }
+        self.base.get(&key).cloned().ok_or_else(|| {
+            RustBucketError::PaletteParseError(format!(
+                "Palette '{}' extends unknown palette '{}'",
+                child.name, name
+            ))
+        })
+    }
+
+    fn compose(&mut self, definition: &PaletteDefinition) -> crate::Result<Palette> {
+        let own = &definition.palette;
+        let composition = &definition.composition;
+        if composition.is_empty() {
+            let mut palette = own.clone();
+            prune_references(&mut palette, own);
+            return Ok(palette);
+        }
+
+        let mut colors: Vec<Color> = Vec::new();
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut aliases: BTreeMap<String, String> = BTreeMap::new();
+        for name in &composition.extends {
+            let parent = self.parent(own, name)?;
+            merge(&mut colors, parent.colors);
+            merge_groups(&mut groups, &parent.groups);
+            aliases.extend(parent.aliases);
+        }
+
+        if let Some(include) = &composition.include {
+            let missing: Vec<&str> = include
+                .iter()
+                .filter(|name| !colors.iter().any(|c| c.name.eq_ignore_ascii_case(name)))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                let available: Vec<&str> = colors.iter().map(|c| c.name.as_str()).collect();
+                return Err(RustBucketError::PaletteParseError(format!(
+                    "Palette '{}' includes unknown colors: {} (inherited colors: {})",
+                    own.name,
+                    missing.join(", "),
+                    available.join(", ")
+                )));
+            }
+            colors.retain(|c| include.iter().any(|name| c.name.eq_ignore_ascii_case(name)));
+        }
+
+        for name in &composition.exclude {
+            if !colors.iter().any(|c| c.name.eq_ignore_ascii_case(name)) {
+                log::warn!(
+                    "Palette '{}' excludes '{}', which it does not inherit",
+                    own.name,
+                    name
+                );
+            }
+        }
+        colors.retain(|c| {
+            !composition
+                .exclude
+                .iter()
+                .any(|name| c.name.eq_ignore_ascii_case(name))
+        });
+
+        merge(&mut colors, own.colors.clone());
+
+        if colors.is_empty() {
+            return Err(RustBucketError::PaletteParseError(format!(
+                "Palette '{}' has no colors",
+                own.name
+            )));
+        }
+
+        merge_groups(&mut groups, &own.groups);
+        aliases.extend(own.aliases.clone());
+
+        let mut palette = Palette {
+            name: own.name.clone(),
+            path: own.path.clone(),
+            colors,
+            groups,
+            aliases,
+        };
+        prune_references(&mut palette, own);
+        Ok(palette)
+    }
+}
+
+/// Add members to groups of the same name, keeping the existing order
+fn merge_groups(groups: &mut BTreeMap<String, Vec<String>>, other: &BTreeMap<String, Vec<String>>) {
+    for (name, members) in other {
+        let group = groups.entry(name.clone()).or_default();
+        for member in members {
+            if !group.iter().any(|m| m.eq_ignore_ascii_case(member)) {
+                group.push(member.clone());
+            }
+        }
+    }
+}
+
+/// Drop group members and aliases whose colors are not in the palette, for
+/// example after `exclude`. Only references the palette itself made are
+/// reported.
+fn prune_references(palette: &mut Palette, own: &Palette) {
+    let colors = &palette.colors;
+    let canonical = |name: &str| {
+        colors
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+            .map(|c| c.name.clone())
+    };
+
+    for (group, members) in palette.groups.iter_mut() {
+        let declared = own.groups.get(group);
+        *members = members
+            .iter()
+            .filter_map(|member| {
+                let found = canonical(member);
+                if found.is_none() && declared.is_some_and(|d| d.contains(member)) {
+                    log::warn!(
+                        "Palette '{}' group '{}' names unknown color '{}'",
+                        own.name,
+                        group,
+                        member
+                    );
+                }
+                found
+            })
+            .collect();
+    }
+    palette.groups.retain(|_, members| !members.is_empty());
+
+    let mut aliases = BTreeMap::new();
+    for (alias, target) in &palette.aliases {
+        match canonical(target) {
+            Some(name) => {
+                aliases.insert(alias.clone(), name);
+            }
+            None if own.aliases.get(alias) == Some(target) => log::warn!(
+                "Palette '{}' alias '{}' names unknown color '{}'",
+                own.name,
+                alias,
+                target
+            ),
+            None => {}
+        }
+    }
+    palette.aliases = aliases;
+}
+
+/// Append `overrides`, replacing colors that already exist under the same name
+fn merge(colors: &mut Vec<Color>, overrides: Vec<Color>) {
+    for color in overrides {
+        match colors
+            .iter_mut()
+            .find(|c| c.name.eq_ignore_ascii_case(&color.name))
+        {
+            Some(existing) => existing.hex = color.hex,
+            None => colors.push(color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::BuiltinPalettes;
+    use std::path::PathBuf;
+
+    fn base() -> HashMap<String, Palette> {
+        BuiltinPalettes::get_all()
+            .into_iter()
+            .map(|p| (p.name.to_lowercase(), p))
+            .collect()
+    }
+
+    fn definition(name: &str, extends: &[&str], colors: &[(&str, &str)]) -> PaletteDefinition {
+        PaletteDefinition {
+            palette: Palette {
+                name: name.to_string(),
+                path: PathBuf::from(format!("user://{}", name)),
+                colors: colors
+                    .iter()
+                    .map(|(name, hex)| Color {
+                        name: name.to_string(),
+                        hex: hex.to_string(),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            composition: Composition {
+                extends: extends.iter().map(|s| s.to_string()).collect(),
+                ..Composition::default()
+            },
+        }
+    }
+
+    fn names(palette: &Palette) -> Vec<&str> {
+        palette.colors.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_extends_with_overrides() {
+        let brand = definition(
+            "brand",
+            &["nord"],
+            &[("nord8", "#00AAFF"), ("accent", "#FF00AA")],
+        );
+        let palette = resolve_definition(&base(), &brand).unwrap();
+
+        assert_eq!(palette.name, "brand");
+        assert_eq!(palette.path, PathBuf::from("user://brand"));
+        assert_eq!(palette.colors.len(), 17);
+        // Overrides keep the inherited position, new colors are appended
+        assert_eq!(palette.colors[8].name, "nord8");
+        assert_eq!(palette.colors[8].hex, "#00AAFF");
+        assert_eq!(palette.colors[16].name, "accent");
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let mut frost = definition("frost", &["nord"], &[]);
+        frost.composition.include = Some(
+            ["nord7", "nord8", "nord9", "nord10"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        frost.composition.exclude = vec!["nord10".to_string()];
+        let palette = resolve_definition(&base(), &frost).unwrap();
+        assert_eq!(names(&palette), vec!["nord7", "nord8", "nord9"]);
+
+        frost.composition.include = Some(vec!["nord99".to_string()]);
+        let err = resolve_definition(&base(), &frost).unwrap_err().to_string();
+        assert!(err.contains("nord99"), "{}", err);
+    }
+
+    #[test]
+    fn test_multiple_parents_merge_in_order() {
+        let mix = definition("mix", &["dracula", "gruvbox"], &[]);
+        let palette = resolve_definition(&base(), &mix).unwrap();
+
+        let dracula = BuiltinPalettes::get_palette("dracula").unwrap();
+        let gruvbox = BuiltinPalettes::get_palette("gruvbox").unwrap();
+        let red = palette.colors.iter().find(|c| c.name == "red").unwrap();
+        let gruvbox_red = gruvbox.colors.iter().find(|c| c.name == "red").unwrap();
+        assert_eq!(red.hex, gruvbox_red.hex);
+        assert_eq!(palette.colors.iter().filter(|c| c.name == "red").count(), 1);
+        assert_eq!(palette.colors[0].name, dracula.colors[0].name);
+    }
+
+    #[test]
+    fn test_chains_and_self_extension() {
+        // A user "nord" extending "nord" builds on the built-in, and other
+        // palettes extending "nord" see the user version
+        let definitions = vec![
+            definition("child", &["Nord"], &[("extra", "#123456")]),
+            definition("nord", &["nord"], &[("nord0", "#000000")]),
+        ];
+        let results = resolve_definitions(&base(), &definitions);
+        let child = results[0].as_ref().unwrap();
+        assert_eq!(child.colors[0].hex, "#000000");
+        assert_eq!(child.colors.len(), 17);
+        assert_eq!(results[1].as_ref().unwrap().colors.len(), 16);
+    }
+
+    #[test]
+    fn test_groups_and_aliases_are_inherited() {
+        let mut brand = definition("brand", &["nord"], &[("signal", "#FF3366")]);
+        brand.composition.exclude = vec!["nord12".to_string()];
+        brand
+            .palette
+            .groups
+            .insert("aurora".to_string(), vec!["signal".to_string()]);
+        brand
+            .palette
+            .aliases
+            .insert("red".to_string(), "signal".to_string());
+        brand
+            .palette
+            .aliases
+            .insert("typo".to_string(), "nord99".to_string());
+        let palette = resolve_definition(&base(), &brand).unwrap();
+
+        // Own members are added to the inherited group; excluded colors drop out
+        assert_eq!(
+            palette.group("aurora").unwrap(),
+            ["nord11", "nord13", "nord14", "nord15", "signal"]
+        );
+        assert_eq!(palette.group("frost").unwrap().len(), 4);
+        assert_eq!(palette.find_color("red").unwrap().name, "signal");
+        assert!(!palette.aliases.contains_key("orange"));
+        assert!(!palette.aliases.contains_key("typo"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let definitions = vec![
+            definition("a", &["b"], &[]),
+            definition("b", &["c"], &[]),
+            definition("c", &["a"], &[]),
+            definition("lost", &["no-such-palette"], &[]),
+            definition("empty", &["nord"], &[]),
+        ];
+        let mut empty = definitions[4].clone();
+        empty.composition.exclude = names(&BuiltinPalettes::get_palette("nord").unwrap())
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let results = resolve_definitions(&base(), &definitions);
+        let cycle = results[0].as_ref().unwrap_err().to_string();
+        assert!(cycle.contains("a -> b -> c -> a"), "{}", cycle);
+        let unknown = results[3].as_ref().unwrap_err().to_string();
+        assert!(unknown.contains("unknown palette 'no-such-palette'"));
+        assert!(results[4].is_ok());
+
+        let err = resolve_definition(&base(), &empty).unwrap_err().to_string();
+        assert!(err.contains("has no colors"));
+    }
+}
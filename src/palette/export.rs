@@ -0,0 +1,558 @@
+use crate::{Palette, RustBucketError};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use super::parser::{hex_to_rgb, rgb_to_hex};
+use super::toml_loader::TomlPalette;
+
+/// Writes a palette in one file format
+pub trait PaletteExporter {
+    /// Format name used by `--export-format`
+    fn name(&self) -> &'static str;
+
+    /// File extensions (lowercase, without the dot) this format is chosen for
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Render the palette as file contents
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>>;
+}
+
+/// Exporters looked up by format name or output file extension
+pub struct ExporterRegistry {
+    exporters: Vec<Box<dyn PaletteExporter>>,
+}
+
+impl ExporterRegistry {
+    /// A registry without any exporters
+    pub fn empty() -> Self {
+        Self {
+            exporters: Vec::new(),
+        }
+    }
+
+    /// A registry with all built-in formats
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Box::new(TomlExporter));
+        registry.register(Box::new(GplExporter));
+        registry.register(Box::new(AseExporter));
+        registry.register(Box::new(JsonExporter));
+        registry.register(Box::new(CssExporter));
+        registry.register(Box::new(ScssExporter));
+        registry.register(Box::new(TailwindExporter));
+        registry.register(Box::new(SvgExporter));
+        registry
+    }
+
+    /// Add an exporter; a later exporter replaces one with the same name
+    pub fn register(&mut self, exporter: Box<dyn PaletteExporter>) {
+        self.exporters.retain(|e| e.name() != exporter.name());
+        self.exporters.push(exporter);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn PaletteExporter> {
+        self.exporters
+            .iter()
+            .find(|e| e.name().eq_ignore_ascii_case(name))
+            .map(|e| e.as_ref())
+    }
+
+    /// Pick the exporter for a path by its extension
+    pub fn for_path<P: AsRef<Path>>(&self, path: P) -> Option<&dyn PaletteExporter> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        self.exporters
+            .iter()
+            .find(|e| e.extensions().contains(&extension.as_str()))
+            .map(|e| e.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.exporters.iter().map(|e| e.name()).collect()
+    }
+
+    /// Export to `path`, using `format` or else the path's extension
+    pub fn export_to_file<P: AsRef<Path>>(
+        &self,
+        palette: &Palette,
+        path: P,
+        format: Option<&str>,
+    ) -> crate::Result<()> {
+        let path = path.as_ref();
+        let exporter = match format {
+            Some(name) => self.get(name),
+            None => self.for_path(path),
+        }
+        .ok_or_else(|| {
+            RustBucketError::PaletteParseError(format!(
+                "Unknown export format for {} (available: {})",
+                format.map_or_else(|| path.display().to_string(), str::to_string),
+                self.names().join(", ")
+            ))
+        })?;
+
+        let contents = exporter.export(palette)?;
+        fs::write(path, contents).map_err(RustBucketError::IoError)?;
+        Ok(())
+    }
+}
+
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A color name with its normalized `#RRGGBB` code and RGB values
+type ExportColor<'a> = (&'a str, String, (u8, u8, u8));
+
+fn palette_rgb(palette: &Palette) -> crate::Result<Vec<ExportColor<'_>>> {
+    palette
+        .colors
+        .iter()
+        .map(|color| {
+            let (r, g, b) = hex_to_rgb(&color.hex)?;
+            Ok((color.name.as_str(), rgb_to_hex(r, g, b), (r, g, b)))
+        })
+        .collect()
+}
+
+/// Lowercase kebab-case identifier for CSS, SCSS and Tailwind names
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "color".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub struct TomlExporter;
+
+impl PaletteExporter for TomlExporter {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        toml::to_string_pretty(&TomlPalette::from(palette))
+            .map(String::into_bytes)
+            .map_err(|e| {
+                RustBucketError::PaletteParseError(format!("Failed to serialize palette: {}", e))
+            })
+    }
+}
+
+/// GIMP / Inkscape `.gpl`
+pub struct GplExporter;
+
+impl PaletteExporter for GplExporter {
+    fn name(&self) -> &'static str {
+        "gpl"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["gpl"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let mut out = format!(
+            "GIMP Palette\nName: {}\nColumns: 8\n#\n",
+            palette.name.replace('\n', " ")
+        );
+        for (name, _, (r, g, b)) in palette_rgb(palette)? {
+            let _ = writeln!(out, "{:3} {:3} {:3}\t{}", r, g, b, name);
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// Adobe Swatch Exchange `.ase` with RGB global swatches
+pub struct AseExporter;
+
+impl PaletteExporter for AseExporter {
+    fn name(&self) -> &'static str {
+        "ase"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ase"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let colors = palette_rgb(palette)?;
+        let mut out = b"ASEF".to_vec();
+        out.extend(1u16.to_be_bytes());
+        out.extend(0u16.to_be_bytes());
+        out.extend((colors.len() as u32).to_be_bytes());
+
+        for (name, _, (r, g, b)) in colors {
+            let mut units: Vec<u16> = name.encode_utf16().collect();
+            units.push(0);
+
+            let mut body = Vec::new();
+            body.extend((units.len() as u16).to_be_bytes());
+            for unit in units {
+                body.extend(unit.to_be_bytes());
+            }
+            body.extend(b"RGB ");
+            for channel in [r, g, b] {
+                body.extend((f32::from(channel) / 255.0).to_be_bytes());
+            }
+            // Global color type
+            body.extend(0u16.to_be_bytes());
+
+            out.extend(0x0001u16.to_be_bytes());
+            out.extend((body.len() as u32).to_be_bytes());
+            out.extend(body);
+        }
+        Ok(out)
+    }
+}
+
+pub struct JsonExporter;
+
+impl PaletteExporter for JsonExporter {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let colors = palette_rgb(palette)?;
+        let mut out = format!(
+            "{{\n  \"name\": {},\n  \"colors\": [\n",
+            json_string(&palette.name)
+        );
+        for (index, (name, hex, (r, g, b))) in colors.iter().enumerate() {
+            let separator = if index + 1 < colors.len() { "," } else { "" };
+            let _ = writeln!(
+                out,
+                "    {{ \"name\": {}, \"hex\": \"{}\", \"rgb\": [{}, {}, {}] }}{}",
+                json_string(name),
+                hex,
+                r,
+                g,
+                b,
+                separator
+            );
+        }
+        out.push_str("  ]\n}\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// CSS custom properties on `:root`, e.g. `--nord-red`
+pub struct CssExporter;
+
+impl PaletteExporter for CssExporter {
+    fn name(&self) -> &'static str {
+        "css"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["css"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let prefix = slug(&palette.name);
+        let mut out = format!("/* {} */\n:root {{\n", palette.name.replace("*/", "* /"));
+        for (name, hex, _) in palette_rgb(palette)? {
+            let _ = writeln!(out, "  --{}-{}: {};", prefix, slug(name), hex);
+        }
+        out.push_str("}\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// SCSS variables plus a name-to-color map
+pub struct ScssExporter;
+
+impl PaletteExporter for ScssExporter {
+    fn name(&self) -> &'static str {
+        "scss"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["scss"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let prefix = slug(&palette.name);
+        let colors = palette_rgb(palette)?;
+        let mut out = format!("// {}\n", palette.name.replace('\n', " "));
+        for (name, hex, _) in &colors {
+            let _ = writeln!(out, "${}-{}: {};", prefix, slug(name), hex);
+        }
+        let _ = writeln!(out, "\n${}: (", prefix);
+        for (name, _, _) in &colors {
+            let _ = writeln!(out, "  \"{}\": ${}-{},", slug(name), prefix, slug(name));
+        }
+        out.push_str(");\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// `tailwind.config.js` snippet extending the theme colors
+pub struct TailwindExporter;
+
+impl PaletteExporter for TailwindExporter {
+    fn name(&self) -> &'static str {
+        "tailwind"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["js", "cjs"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let mut out = format!(
+            "// Tailwind CSS colors for the {} palette\nmodule.exports = {{\n  theme: {{\n    extend: {{\n      colors: {{\n        '{}': {{\n",
+            palette.name.replace('\n', " "),
+            slug(&palette.name)
+        );
+        for (name, hex, _) in palette_rgb(palette)? {
+            let _ = writeln!(out, "          '{}': '{}',", slug(name), hex);
+        }
+        out.push_str("        },\n      },\n    },\n  },\n};\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// SVG sheet of labelled swatches, eight per row
+pub struct SvgExporter;
+
+impl SvgExporter {
+    const COLUMNS: usize = 8;
+    const SWATCH: usize = 96;
+    const LABEL: usize = 36;
+    const GAP: usize = 8;
+}
+
+impl PaletteExporter for SvgExporter {
+    fn name(&self) -> &'static str {
+        "svg"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["svg"]
+    }
+
+    fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+        let colors = palette_rgb(palette)?;
+        let columns = colors.len().clamp(1, Self::COLUMNS);
+        let rows = (colors.len() + Self::COLUMNS - 1) / Self::COLUMNS;
+        let cell_height = Self::SWATCH + Self::LABEL;
+        let width = Self::GAP + columns * (Self::SWATCH + Self::GAP);
+        let height = Self::GAP * 2 + 24 + rows * (cell_height + Self::GAP);
+
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n",
+            w = width,
+            h = height
+        );
+        let _ = writeln!(
+            out,
+            "  <rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/>\n  <text x=\"{}\" y=\"{}\" font-size=\"16\" font-weight=\"bold\">{}</text>",
+            Self::GAP,
+            Self::GAP + 16,
+            xml_escape(&palette.name)
+        );
+
+        for (index, (name, hex, _)) in colors.iter().enumerate() {
+            let x = Self::GAP + (index % Self::COLUMNS) * (Self::SWATCH + Self::GAP);
+            let y = Self::GAP * 2 + 24 + (index / Self::COLUMNS) * (cell_height + Self::GAP);
+            let _ = writeln!(
+                out,
+                "  <g>\n    <rect x=\"{x}\" y=\"{y}\" width=\"{s}\" height=\"{s}\" fill=\"{hex}\" stroke=\"#00000033\"/>\n    <text x=\"{x}\" y=\"{ny}\" font-size=\"12\">{name}</text>\n    <text x=\"{x}\" y=\"{hy}\" font-size=\"11\" fill=\"#666666\">{hex}</text>\n  </g>",
+                x = x,
+                y = y,
+                s = Self::SWATCH,
+                hex = hex,
+                ny = y + Self::SWATCH + 15,
+                hy = y + Self::SWATCH + 30,
+                name = xml_escape(name)
+            );
+        }
+        out.push_str("</svg>\n");
+        Ok(out.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::SwatchFormat;
+    use crate::Color;
+    use tempfile::tempdir;
+
+    fn test_palette() -> Palette {
+        Palette {
+            name: "Sea Side".to_string(),
+            path: "user://Sea Side".into(),
+            colors: vec![
+                Color {
+                    name: "Deep Blue".to_string(),
+                    hex: "#1a2b3c".to_string(),
+                },
+                Color {
+                    name: "foam".to_string(),
+                    hex: "#F0F0F0".to_string(),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn export(format: &str) -> String {
+        let registry = ExporterRegistry::new();
+        let bytes = registry
+            .get(format)
+            .unwrap()
+            .export(&test_palette())
+            .unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = ExporterRegistry::new();
+        assert_eq!(
+            registry.names(),
+            vec!["toml", "gpl", "ase", "json", "css", "scss", "tailwind", "svg"]
+        );
+        assert_eq!(registry.for_path("out.SCSS").unwrap().name(), "scss");
+        assert_eq!(
+            registry.for_path("tailwind.config.js").unwrap().name(),
+            "tailwind"
+        );
+        assert!(registry.for_path("out.png").is_none());
+        assert!(registry.get("CSS").is_some());
+        assert!(ExporterRegistry::empty().get("css").is_none());
+    }
+
+    #[test]
+    fn test_register_custom_exporter() {
+        struct Plain;
+        impl PaletteExporter for Plain {
+            fn name(&self) -> &'static str {
+                "plain"
+            }
+            fn extensions(&self) -> &'static [&'static str] {
+                &["txt"]
+            }
+            fn export(&self, palette: &Palette) -> crate::Result<Vec<u8>> {
+                Ok(palette.name.clone().into_bytes())
+            }
+        }
+
+        let mut registry = ExporterRegistry::new();
+        registry.register(Box::new(Plain));
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("palette.txt");
+        registry
+            .export_to_file(&test_palette(), &path, None)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Sea Side");
+
+        let error = registry
+            .export_to_file(&test_palette(), &path, Some("pdf"))
+            .unwrap_err();
+        assert!(error.to_string().contains("plain"));
+    }
+
+    #[test]
+    fn test_gpl_and_ase_round_trip() {
+        let registry = ExporterRegistry::new();
+        for format in [SwatchFormat::Gpl, SwatchFormat::Ase] {
+            let name = format!("{:?}", format).to_lowercase();
+            let bytes = registry
+                .get(&name)
+                .unwrap()
+                .export(&test_palette())
+                .unwrap();
+            let palette = format.parse(&bytes, "Sea Side").unwrap();
+
+            assert_eq!(palette.name, "Sea Side");
+            let colors: Vec<(&str, &str)> = palette
+                .colors
+                .iter()
+                .map(|c| (c.name.as_str(), c.hex.as_str()))
+                .collect();
+            assert_eq!(colors, vec![("Deep Blue", "#1A2B3C"), ("foam", "#F0F0F0")]);
+        }
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let json = export("json");
+        assert!(json.contains("\"name\": \"Sea Side\""));
+        assert!(json
+            .contains("{ \"name\": \"Deep Blue\", \"hex\": \"#1A2B3C\", \"rgb\": [26, 43, 60] },"));
+
+        let css = export("css");
+        assert!(css.contains(":root {"));
+        assert!(css.contains("--sea-side-deep-blue: #1A2B3C;"));
+
+        let scss = export("scss");
+        assert!(scss.contains("$sea-side-foam: #F0F0F0;"));
+        assert!(scss.contains("\"foam\": $sea-side-foam,"));
+
+        let tailwind = export("tailwind");
+        assert!(tailwind.contains("'sea-side': {"));
+        assert!(tailwind.contains("'deep-blue': '#1A2B3C',"));
+
+        let svg = export("svg");
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<g>").count(), 2);
+        assert!(svg.contains("fill=\"#F0F0F0\""));
+    }
+
+    #[test]
+    fn test_slug_and_escaping() {
+        assert_eq!(slug("Dark_Blue 2"), "dark-blue-2");
+        assert_eq!(slug("  --"), "color");
+        assert_eq!(json_string("a\"b\\"), "\"a\\\"b\\\\\"");
+        assert_eq!(xml_escape("<a & b>"), "&lt;a &amp; b&gt;");
+    }
+}
@@ -0,0 +1,620 @@
+use crate::image::Oklab;
+use crate::{Color, Palette, RustBucketError};
+use image::{DynamicImage, Rgb};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::parser::rgb_to_hex;
+
+/// Color quantization algorithm used to extract a palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExtractMethod {
+    /// Recursively split the RGB box with the widest channel range
+    #[default]
+    MedianCut,
+    /// Merge the least used branches of an RGB octree
+    Octree,
+    /// Cluster colors in OKLab with k-means
+    Kmeans,
+}
+
+/// Maximum k-means refinement passes
+const KMEANS_ITERATIONS: usize = 24;
+
+/// Chroma below which a color is named as a gray
+const GRAY_CHROMA: f32 = 0.04;
+
+/// A distinct image color and the number of pixels using it
+#[derive(Debug, Clone, Copy)]
+struct WeightedColor {
+    rgb: Rgb<u8>,
+    weight: u64,
+}
+
+/// Builds a palette from the dominant colors of an image
+pub struct PaletteExtractor {
+    method: ExtractMethod,
+    colors: usize,
+    ignore_transparent: bool,
+    min_distance: Option<f32>,
+}
+
+impl PaletteExtractor {
+    pub fn new(colors: usize) -> Self {
+        Self {
+            method: ExtractMethod::MedianCut,
+            colors: colors.max(1),
+            ignore_transparent: true,
+            min_distance: None,
+        }
+    }
+
+    pub fn with_method(mut self, method: ExtractMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Whether fully transparent pixels are left out (the default)
+    pub fn with_ignore_transparent(mut self, ignore_transparent: bool) -> Self {
+        self.ignore_transparent = ignore_transparent;
+        self
+    }
+
+    /// Drop extracted colors closer than `distance` (OKLab) to a more
+    /// common one
+    pub fn with_min_distance(mut self, distance: f32) -> Self {
+        self.min_distance = Some(distance);
+        self
+    }
+
+    /// Extract up to the target number of colors, ordered from dark to
+    /// light and named after their hue and lightness
+    pub fn extract(&self, img: &DynamicImage, name: &str) -> crate::Result<Palette> {
+        let histogram = self.histogram(img);
+        if histogram.is_empty() {
+            return Err(RustBucketError::PaletteParseError(
+                "Image has no visible pixels to extract colors from".to_string(),
+            ));
+        }
+
+        log::info!(
+            "Extracting {} colors from {} distinct image colors ({:?})",
+            self.colors,
+            histogram.len(),
+            self.method
+        );
+
+        let mut extracted = if histogram.len() <= self.colors {
+            histogram
+        } else {
+            match self.method {
+                ExtractMethod::MedianCut => median_cut(histogram, self.colors),
+                ExtractMethod::Octree => octree(&histogram, self.colors),
+                ExtractMethod::Kmeans => kmeans(&histogram, self.colors),
+            }
+        };
+
+        if let Some(min_distance) = self.min_distance {
+            extracted = remove_near_duplicates(extracted, min_distance);
+        }
+
+        extracted.sort_by(|a, b| {
+            Oklab::from_rgb(a.rgb)
+                .l
+                .total_cmp(&Oklab::from_rgb(b.rgb).l)
+        });
+
+        let names = color_names(extracted.iter().map(|c| c.rgb));
+        Ok(Palette {
+            name: name.to_string(),
+            path: PathBuf::from(format!("user://{}", name)),
+            colors: extracted
+                .iter()
+                .zip(names)
+                .map(|(color, name)| Color {
+                    name,
+                    hex: rgb_to_hex(color.rgb[0], color.rgb[1], color.rgb[2]),
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    fn histogram(&self, img: &DynamicImage) -> Vec<WeightedColor> {
+        let mut counts: HashMap<Rgb<u8>, u64> = HashMap::new();
+        for pixel in img.to_rgba8().pixels() {
+            if self.ignore_transparent && pixel[3] == 0 {
+                continue;
+            }
+            *counts
+                .entry(Rgb([pixel[0], pixel[1], pixel[2]]))
+                .or_insert(0) += 1;
+        }
+
+        let mut histogram: Vec<WeightedColor> = counts
+            .into_iter()
+            .map(|(rgb, weight)| WeightedColor { rgb, weight })
+            .collect();
+        // HashMap order is random; keep results reproducible
+        histogram.sort_by_key(|c| (std::cmp::Reverse(c.weight), c.rgb.0));
+        histogram
+    }
+}
+
+/// Weighted mean of a set of colors
+fn mean_color(colors: &[WeightedColor]) -> WeightedColor {
+    let mut sums = [0u64; 3];
+    let mut weight = 0u64;
+    for color in colors {
+        for (c, sum) in sums.iter_mut().enumerate() {
+            *sum += u64::from(color.rgb[c]) * color.weight;
+        }
+        weight += color.weight;
+    }
+
+    let weight = weight.max(1);
+    WeightedColor {
+        rgb: Rgb(sums.map(|sum| ((sum + weight / 2) / weight) as u8)),
+        weight,
+    }
+}
+
+fn median_cut(colors: Vec<WeightedColor>, target: usize) -> Vec<WeightedColor> {
+    // Widest channel and its range for a box
+    fn widest_channel(colors: &[WeightedColor]) -> (usize, u8) {
+        (0..3)
+            .map(|c| {
+                let min = colors.iter().map(|color| color.rgb[c]).min().unwrap_or(0);
+                let max = colors.iter().map(|color| color.rgb[c]).max().unwrap_or(0);
+                (c, max - min)
+            })
+            .max_by_key(|&(_, range)| range)
+            .unwrap_or((0, 0))
+    }
+
+    let mut boxes = vec![colors];
+    while boxes.len() < target {
+        // Split the box with the widest range, favouring heavily used ones
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                let weight: u64 = colors.iter().map(|c| c.weight).sum();
+                (i, channel, u64::from(range) * (weight as f64).sqrt() as u64)
+            })
+            .max_by_key(|&(_, _, score)| score)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|c| c.rgb[channel]);
+
+        // Split at the weighted median, keeping both halves non-empty
+        let half: u64 = colors.iter().map(|c| c.weight).sum::<u64>() / 2;
+        let mut running = 0;
+        let mut split = 1;
+        for (i, color) in colors.iter().enumerate() {
+            running += color.weight;
+            if running >= half {
+                split = (i + 1).clamp(1, colors.len() - 1);
+                break;
+            }
+        }
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| mean_color(colors)).collect()
+}
+
+/// Octree depth; leaves at this level hold individual colors
+const OCTREE_DEPTH: usize = 8;
+
+#[derive(Default)]
+struct OctreeNode {
+    children: [Option<usize>; 8],
+    sums: [u64; 3],
+    weight: u64,
+    /// Weight of the whole subtree
+    total: u64,
+    leaf: bool,
+}
+
+fn octree(colors: &[WeightedColor], target: usize) -> Vec<WeightedColor> {
+    let mut nodes = vec![OctreeNode::default()];
+    // Internal nodes per level, candidates for reduction
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); OCTREE_DEPTH];
+    let mut leaves = 0usize;
+
+    for color in colors {
+        let mut node = 0;
+        nodes[0].total += color.weight;
+        for level in 0..OCTREE_DEPTH {
+            let shift = 7 - level;
+            let branch = (((color.rgb[0] >> shift) & 1) << 2
+                | ((color.rgb[1] >> shift) & 1) << 1
+                | ((color.rgb[2] >> shift) & 1)) as usize;
+
+            node = match nodes[node].children[branch] {
+                Some(child) => child,
+                None => {
+                    nodes.push(OctreeNode::default());
+                    let child = nodes.len() - 1;
+                    nodes[node].children[branch] = Some(child);
+                    if level + 1 == OCTREE_DEPTH {
+                        nodes[child].leaf = true;
+                        leaves += 1;
+                    } else {
+                        levels[level + 1].push(child);
+                    }
+                    child
+                }
+            };
+            nodes[node].total += color.weight;
+        }
+
+        let leaf = &mut nodes[node];
+        for (c, sum) in leaf.sums.iter_mut().enumerate() {
+            *sum += u64::from(color.rgb[c]) * color.weight;
+        }
+        leaf.weight += color.weight;
+    }
+    levels[0].push(0);
+    for level in &mut levels {
+        level.sort_by_key(|&node| std::cmp::Reverse(nodes[node].total));
+    }
+
+    // Fold the least used node of the deepest level into a single leaf
+    // until few enough leaves remain
+    while leaves > target {
+        let Some(level) = (0..OCTREE_DEPTH).rev().find(|&l| !levels[l].is_empty()) else {
+            break;
+        };
+
+        let node = levels[level].pop().expect("level is not empty");
+
+        let mut merged = 0;
+        for branch in 0..8 {
+            let Some(child) = nodes[node].children[branch].take() else {
+                continue;
+            };
+            let (sums, weight) = (nodes[child].sums, nodes[child].weight);
+            nodes[child].leaf = false;
+            for (c, sum) in nodes[node].sums.iter_mut().enumerate() {
+                *sum += sums[c];
+            }
+            nodes[node].weight += weight;
+            merged += 1;
+        }
+        nodes[node].leaf = true;
+        leaves = leaves + 1 - merged;
+    }
+
+    nodes
+        .iter()
+        .filter(|node| node.leaf && node.weight > 0)
+        .map(|node| WeightedColor {
+            rgb: Rgb(node
+                .sums
+                .map(|sum| ((sum + node.weight / 2) / node.weight) as u8)),
+            weight: node.weight,
+        })
+        .collect()
+}
+
+fn kmeans(colors: &[WeightedColor], target: usize) -> Vec<WeightedColor> {
+    let points: Vec<Oklab> = colors.iter().map(|c| Oklab::from_rgb(c.rgb)).collect();
+    let weights: Vec<f32> = colors.iter().map(|c| c.weight as f32).collect();
+
+    // Deterministic k-means++ style seeding: start from the most common
+    // color, then repeatedly take the color with the largest weighted
+    // distance to its nearest center
+    let mut centers = vec![points[0]];
+    let mut nearest: Vec<f32> = points.iter().map(|p| p.distance(&points[0])).collect();
+    while centers.len() < target {
+        let Some((index, _)) = nearest
+            .iter()
+            .zip(&weights)
+            .map(|(distance, weight)| distance * distance * weight.sqrt())
+            .enumerate()
+            .filter(|(_, score)| *score > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            break;
+        };
+        let center = points[index];
+        for (distance, point) in nearest.iter_mut().zip(&points) {
+            *distance = distance.min(point.distance(&center));
+        }
+        centers.push(center);
+    }
+
+    let mut assignment = vec![0usize; points.len()];
+    for iteration in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (point, assigned) in points.iter().zip(assignment.iter_mut()) {
+            let closest = centers
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| point.distance(a).total_cmp(&point.distance(b)))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            if closest != *assigned {
+                *assigned = closest;
+                changed = true;
+            }
+        }
+        if !changed && iteration > 0 {
+            break;
+        }
+
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0.0f32); centers.len()];
+        for ((point, weight), &cluster) in points.iter().zip(&weights).zip(&assignment) {
+            let sum = &mut sums[cluster];
+            sum.0 += point.l * weight;
+            sum.1 += point.a * weight;
+            sum.2 += point.b * weight;
+            sum.3 += weight;
+        }
+        for (center, (l, a, b, weight)) in centers.iter_mut().zip(sums) {
+            if weight > 0.0 {
+                *center = Oklab {
+                    l: l / weight,
+                    a: a / weight,
+                    b: b / weight,
+                };
+            }
+        }
+    }
+
+    let mut cluster_weights = vec![0u64; centers.len()];
+    for (color, &cluster) in colors.iter().zip(&assignment) {
+        cluster_weights[cluster] += color.weight;
+    }
+
+    centers
+        .into_iter()
+        .zip(cluster_weights)
+        .filter(|&(_, weight)| weight > 0)
+        .map(|(center, weight)| WeightedColor {
+            rgb: center.to_rgb(),
+            weight,
+        })
+        .collect()
+}
+
+/// Keep the most common colors, dropping any closer than `min_distance` in
+/// OKLab to one already kept
+fn remove_near_duplicates(mut colors: Vec<WeightedColor>, min_distance: f32) -> Vec<WeightedColor> {
+    colors.sort_by_key(|c| std::cmp::Reverse(c.weight));
+
+    let mut kept: Vec<(WeightedColor, Oklab)> = Vec::new();
+    for color in colors {
+        let lab = Oklab::from_rgb(color.rgb);
+        if kept
+            .iter()
+            .all(|(_, other)| lab.distance(other) >= min_distance)
+        {
+            kept.push((color, lab));
+        }
+    }
+    kept.into_iter().map(|(color, _)| color).collect()
+}
+
+/// Descriptive snake_case names like `dark_blue` or `light_gray`, with a
+/// numeric suffix when several colors share a name
+pub(super) fn color_names(colors: impl Iterator<Item = Rgb<u8>>) -> Vec<String> {
+    let base: Vec<String> = colors.map(describe_color).collect();
+
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for name in &base {
+        *totals.entry(name).or_insert(0) += 1;
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    base.iter()
+        .map(|name| {
+            if totals[name.as_str()] == 1 {
+                return name.clone();
+            }
+            let index = seen.entry(name).or_insert(0);
+            *index += 1;
+            format!("{}_{}", name, index)
+        })
+        .collect()
+}
+
+fn describe_color(rgb: Rgb<u8>) -> String {
+    let lab = Oklab::from_rgb(rgb);
+    let chroma = (lab.a * lab.a + lab.b * lab.b).sqrt();
+
+    if chroma < GRAY_CHROMA {
+        return match lab.l {
+            l if l < 0.2 => "black",
+            l if l < 0.45 => "dark_gray",
+            l if l < 0.75 => "gray",
+            l if l < 0.92 => "light_gray",
+            _ => "white",
+        }
+        .to_string();
+    }
+
+    // Reference OKLab hue angles in degrees
+    const HUES: [(&str, f32); 9] = [
+        ("red", 25.0),
+        ("orange", 60.0),
+        ("yellow", 100.0),
+        ("green", 140.0),
+        ("teal", 180.0),
+        ("cyan", 215.0),
+        ("blue", 260.0),
+        ("purple", 305.0),
+        ("pink", 345.0),
+    ];
+    let hue = lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0);
+    let hue_distance = |reference: f32| {
+        let d = (hue - reference).abs();
+        d.min(360.0 - d)
+    };
+    let (hue_name, _) = HUES
+        .iter()
+        .min_by(|a, b| hue_distance(a.1).total_cmp(&hue_distance(b.1)))
+        .expect("hue table is not empty");
+
+    match lab.l {
+        l if l < 0.45 => format!("dark_{}", hue_name),
+        l if l > 0.8 => format!("light_{}", hue_name),
+        _ => hue_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgba, RgbaImage};
+
+    /// Four flat quadrants of noisy red, green, blue and white
+    fn quadrant_image() -> DynamicImage {
+        let img: RgbaImage = ImageBuffer::from_fn(32, 32, |x, y| {
+            let noise = ((x * 7 + y * 13) % 9) as u8;
+            match (x < 16, y < 16) {
+                (true, true) => Rgba([200 + noise, 20, 20, 255]),
+                (false, true) => Rgba([20, 180 + noise, 30, 255]),
+                (true, false) => Rgba([20, 30, 190 + noise, 255]),
+                (false, false) => Rgba([240 + noise, 240 + noise, 240, 255]),
+            }
+        });
+        DynamicImage::ImageRgba8(img)
+    }
+
+    fn rgb_colors(palette: &Palette) -> Vec<Rgb<u8>> {
+        palette
+            .colors
+            .iter()
+            .map(|c| crate::image::hex_to_rgb(&c.hex).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_methods_find_dominant_colors() {
+        let expected = [
+            Rgb([204, 20, 20]),
+            Rgb([20, 184, 30]),
+            Rgb([20, 30, 194]),
+            Rgb([244, 244, 240]),
+        ];
+
+        for method in [
+            ExtractMethod::MedianCut,
+            ExtractMethod::Octree,
+            ExtractMethod::Kmeans,
+        ] {
+            let palette = PaletteExtractor::new(4)
+                .with_method(method)
+                .extract(&quadrant_image(), "test")
+                .unwrap();
+            let colors = rgb_colors(&palette);
+            assert_eq!(colors.len(), 4, "{:?}", method);
+
+            for target in expected {
+                let closest = colors
+                    .iter()
+                    .map(|c| crate::image::rgb_distance(c, &target))
+                    .fold(f64::INFINITY, f64::min);
+                assert!(closest < 20.0, "{:?} missed {:?}", method, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_names() {
+        let palette = PaletteExtractor::new(4)
+            .extract(&quadrant_image(), "quadrants")
+            .unwrap();
+        let names: Vec<&str> = palette.colors.iter().map(|c| c.name.as_str()).collect();
+
+        // Sorted dark to light
+        assert_eq!(names.first(), Some(&"dark_blue"));
+        assert_eq!(names.last(), Some(&"white"));
+        assert!(names.contains(&"red"));
+        assert!(names.contains(&"green"));
+        assert_eq!(palette.name, "quadrants");
+    }
+
+    #[test]
+    fn test_duplicate_names_get_suffixes() {
+        let names =
+            color_names([Rgb([200, 20, 20]), Rgb([210, 25, 25]), Rgb([0, 0, 0])].into_iter());
+        assert_eq!(names, vec!["red_1", "red_2", "black"]);
+    }
+
+    #[test]
+    fn test_transparent_pixels_ignored() {
+        let img: RgbaImage = ImageBuffer::from_fn(8, 8, |x, _y| {
+            if x < 6 {
+                Rgba([255, 0, 255, 0])
+            } else {
+                Rgba([10, 10, 10, 255])
+            }
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let palette = PaletteExtractor::new(4).extract(&img, "test").unwrap();
+        assert_eq!(rgb_colors(&palette), vec![Rgb([10, 10, 10])]);
+
+        let palette = PaletteExtractor::new(4)
+            .with_ignore_transparent(false)
+            .extract(&img, "test")
+            .unwrap();
+        assert_eq!(palette.colors.len(), 2);
+
+        let empty = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0])));
+        assert!(PaletteExtractor::new(4).extract(&empty, "test").is_err());
+    }
+
+    #[test]
+    fn test_near_duplicates_removed() {
+        let img: RgbaImage = ImageBuffer::from_fn(10, 1, |x, _y| match x {
+            0..=5 => Rgba([100, 100, 100, 255]),
+            6..=7 => Rgba([102, 101, 100, 255]),
+            _ => Rgba([250, 250, 250, 255]),
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let palette = PaletteExtractor::new(3).extract(&img, "test").unwrap();
+        assert_eq!(palette.colors.len(), 3);
+
+        let palette = PaletteExtractor::new(3)
+            .with_min_distance(0.02)
+            .extract(&img, "test")
+            .unwrap();
+        assert_eq!(
+            rgb_colors(&palette),
+            vec![Rgb([100, 100, 100]), Rgb([250, 250, 250])]
+        );
+    }
+
+    #[test]
+    fn test_target_larger_than_image_colors() {
+        let img = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(3, 3, Rgb([1, 2, 3])));
+        for method in [
+            ExtractMethod::MedianCut,
+            ExtractMethod::Octree,
+            ExtractMethod::Kmeans,
+        ] {
+            let palette = PaletteExtractor::new(16)
+                .with_method(method)
+                .extract(&img, "test")
+                .unwrap();
+            assert_eq!(palette.colors.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,656 @@
+use crate::{Color, Palette, RustBucketError};
+use image::Rgb;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::extract::color_names;
+use super::parser::rgb_to_hex;
+
+/// Third-party swatch file formats that can be read as palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwatchFormat {
+    /// GIMP / Inkscape `.gpl`
+    Gpl,
+    /// Adobe Swatch Exchange `.ase`
+    Ase,
+    /// Adobe Photoshop color swatches `.aco`
+    Aco,
+    /// JASC / PaintShop Pro `.pal`
+    JascPal,
+    /// Paint.NET `.txt` (one AARRGGBB value per line)
+    PaintNet,
+    /// Plain list of hex codes, as exported by lospec
+    Hex,
+}
+
+impl SwatchFormat {
+    /// Guess the format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gpl" => Some(Self::Gpl),
+            "ase" => Some(Self::Ase),
+            "aco" => Some(Self::Aco),
+            "pal" => Some(Self::JascPal),
+            "txt" => Some(Self::PaintNet),
+            "hex" => Some(Self::Hex),
+            _ => None,
+        }
+    }
+
+    /// Parse swatch file contents. `default_name` names the palette when the
+    /// file doesn't; swatches without names get descriptive generated ones.
+    pub fn parse(&self, data: &[u8], default_name: &str) -> crate::Result<Palette> {
+        let (name, swatches) = match self {
+            Self::Gpl => parse_gpl(&text(data)?)?,
+            Self::Ase => (None, parse_ase(data)?),
+            Self::Aco => (None, parse_aco(data)?),
+            Self::JascPal => (None, parse_jasc_pal(&text(data)?)?),
+            Self::PaintNet => (None, parse_hex_lines(&text(data)?, true)?),
+            Self::Hex => (None, parse_hex_lines(&text(data)?, false)?),
+        };
+
+        if swatches.is_empty() {
+            return Err(RustBucketError::PaletteParseError(format!(
+                "No colors found in {:?} swatch file",
+                self
+            )));
+        }
+
+        Ok(Palette {
+            name: name.unwrap_or_else(|| default_name.to_string()),
+            path: Default::default(),
+            colors: name_swatches(swatches),
+            ..Default::default()
+        })
+    }
+}
+
+/// Load a swatch file in any supported format, named after the file when
+/// the format carries no name
+pub fn load_swatch_file<P: AsRef<Path>>(path: P) -> crate::Result<Palette> {
+    let path = path.as_ref();
+    let format = SwatchFormat::from_path(path).ok_or_else(|| {
+        RustBucketError::PaletteParseError(format!(
+            "Unsupported swatch file format: {}",
+            path.display()
+        ))
+    })?;
+
+    let data = fs::read(path).map_err(RustBucketError::IoError)?;
+    let default_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("imported");
+
+    let mut palette = format
+        .parse(&data, default_name)
+        .map_err(|e| RustBucketError::PaletteParseError(format!("{}: {}", path.display(), e)))?;
+    palette.path = PathBuf::from(format!("user://{}", palette.name));
+    Ok(palette)
+}
+
+/// A color read from a swatch file, with its name if the file has one
+type Swatch = (Rgb<u8>, Option<String>);
+
+fn name_swatches(swatches: Vec<Swatch>) -> Vec<Color> {
+    let generated = color_names(swatches.iter().map(|(rgb, _)| *rgb));
+    let mut used: Vec<String> = Vec::new();
+
+    swatches
+        .into_iter()
+        .zip(generated)
+        .map(|((rgb, name), generated)| {
+            let base = name
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .unwrap_or(generated);
+
+            // Color names must be unique for `-c` selection
+            let mut unique = base.clone();
+            let mut index = 2;
+            while used.contains(&unique) {
+                unique = format!("{}_{}", base, index);
+                index += 1;
+            }
+            used.push(unique.clone());
+
+            Color {
+                name: unique,
+                hex: rgb_to_hex(rgb[0], rgb[1], rgb[2]),
+            }
+        })
+        .collect()
+}
+
+fn text(data: &[u8]) -> crate::Result<String> {
+    String::from_utf8(data.to_vec())
+        .map(|s| s.trim_start_matches('\u{feff}').to_string())
+        .map_err(|_| RustBucketError::PaletteParseError("File is not valid UTF-8".to_string()))
+}
+
+fn parse_error(message: String) -> RustBucketError {
+    RustBucketError::PaletteParseError(message)
+}
+
+fn channel(value: &str, line: usize) -> crate::Result<u8> {
+    value.parse::<u8>().map_err(|_| {
+        parse_error(format!(
+            "Invalid channel value '{}' on line {}",
+            value, line
+        ))
+    })
+}
+
+fn unit_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn parse_gpl(content: &str) -> crate::Result<(Option<String>, Vec<Swatch>)> {
+    let mut lines = content.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err(parse_error("Missing 'GIMP Palette' header".to_string())),
+    }
+
+    let mut name = None;
+    let mut swatches = Vec::new();
+    for (index, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Name:") {
+            name = Some(value.trim().to_string());
+            continue;
+        }
+        if line.starts_with("Columns:") {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let mut rgb = [0u8; 3];
+        for value in rgb.iter_mut() {
+            let part = parts
+                .next()
+                .ok_or_else(|| parse_error(format!("Missing channel on line {}", index + 1)))?;
+            *value = channel(part, index + 1)?;
+        }
+        let color_name = parts.collect::<Vec<_>>().join(" ");
+        // GIMP writes "Untitled" for unnamed swatches
+        let color_name = Some(color_name).filter(|n| !n.is_empty() && n != "Untitled");
+        swatches.push((Rgb(rgb), color_name));
+    }
+
+    Ok((name, swatches))
+}
+
+fn parse_jasc_pal(content: &str) -> crate::Result<Vec<Swatch>> {
+    let mut lines = content.lines().map(str::trim);
+    if lines.next() != Some("JASC-PAL") {
+        return Err(parse_error("Missing 'JASC-PAL' header".to_string()));
+    }
+    // Version line, then the color count
+    lines.next();
+    let count: usize = lines
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| parse_error("Missing color count".to_string()))?;
+
+    let mut swatches = Vec::with_capacity(count);
+    for (index, line) in lines.filter(|l| !l.is_empty()).take(count).enumerate() {
+        let values: Vec<&str> = line.split_whitespace().collect();
+        if values.len() < 3 {
+            return Err(parse_error(format!("Invalid color entry '{}'", line)));
+        }
+        let rgb = [
+            channel(values[0], index + 4)?,
+            channel(values[1], index + 4)?,
+            channel(values[2], index + 4)?,
+        ];
+        swatches.push((Rgb(rgb), None));
+    }
+
+    Ok(swatches)
+}
+
+/// Hex code lists; Paint.NET files hold AARRGGBB values and `;` comments
+fn parse_hex_lines(content: &str, with_alpha: bool) -> crate::Result<Vec<Swatch>> {
+    let mut swatches = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+            continue;
+        }
+
+        let hex = line.trim_start_matches('#');
+        let hex = match (with_alpha, hex.len()) {
+            (true, 8) => &hex[2..],
+            (_, 6) => hex,
+            _ => {
+                return Err(parse_error(format!(
+                    "Invalid hex color '{}' on line {}",
+                    line,
+                    index + 1
+                )))
+            }
+        };
+
+        let rgb = crate::image::hex_to_rgb(hex).map_err(|_| {
+            parse_error(format!(
+                "Invalid hex color '{}' on line {}",
+                line,
+                index + 1
+            ))
+        })?;
+        swatches.push((rgb, None));
+    }
+    Ok(swatches)
+}
+
+/// Cursor over big-endian binary swatch data
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> crate::Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| parse_error("Unexpected end of file".to_string()))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn u16(&mut self) -> crate::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> crate::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f32(&mut self) -> crate::Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    /// UTF-16 string of `length` code units, dropping the null terminator
+    fn utf16(&mut self, length: usize) -> crate::Result<String> {
+        let units = (0..length)
+            .map(|_| self.u16())
+            .collect::<crate::Result<Vec<u16>>>()?;
+        let units: Vec<u16> = units.into_iter().take_while(|&unit| unit != 0).collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+fn cmyk_to_rgb(c: f32, m: f32, y: f32, k: f32) -> Rgb<u8> {
+    Rgb([
+        unit_to_u8((1.0 - c) * (1.0 - k)),
+        unit_to_u8((1.0 - m) * (1.0 - k)),
+        unit_to_u8((1.0 - y) * (1.0 - k)),
+    ])
+}
+
+/// CIELAB (D50, as used by Adobe) to sRGB
+fn lab_to_rgb(l: f32, a: f32, b: f32) -> Rgb<u8> {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            3.0 * (6.0f32 / 29.0).powi(2) * (t - 4.0 / 29.0)
+        }
+    };
+    let (x, y, z) = (0.9642 * inverse(fx), inverse(fy), 0.8249 * inverse(fz));
+
+    // Bradford-adapted D50 XYZ to linear sRGB
+    let r = 3.134_187 * x - 1.617_209 * y - 0.490_694_9 * z;
+    let g = -0.978_749 * x + 1.916_13 * y + 0.033_433_5 * z;
+    let bl = 0.071_945_3 * x - 0.228_991 * y + 1.405_242_7 * z;
+    Rgb([
+        crate::image::linear_to_srgb(r),
+        crate::image::linear_to_srgb(g),
+        crate::image::linear_to_srgb(bl),
+    ])
+}
+
+fn hsb_to_rgb(hue: f32, saturation: f32, brightness: f32) -> Rgb<u8> {
+    let sector = (hue.rem_euclid(360.0)) / 60.0;
+    let chroma = brightness * saturation;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = brightness - chroma;
+    Rgb([unit_to_u8(r + m), unit_to_u8(g + m), unit_to_u8(b + m)])
+}
+
+const ASE_COLOR_ENTRY: u16 = 0x0001;
+
+fn parse_ase(data: &[u8]) -> crate::Result<Vec<Swatch>> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4)? != b"ASEF" {
+        return Err(parse_error("Missing 'ASEF' signature".to_string()));
+    }
+    // Major and minor version
+    reader.u16()?;
+    reader.u16()?;
+    let block_count = reader.u32()?;
+
+    let mut swatches = Vec::new();
+    for _ in 0..block_count {
+        let block_type = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let block = reader.bytes(length)?;
+        // Group start and end blocks only structure the swatches
+        if block_type != ASE_COLOR_ENTRY {
+            continue;
+        }
+
+        let mut entry = Reader::new(block);
+        let name_length = entry.u16()? as usize;
+        let name = entry.utf16(name_length)?;
+        let model = entry.bytes(4)?;
+        let rgb = match model {
+            b"RGB " => Rgb([
+                unit_to_u8(entry.f32()?),
+                unit_to_u8(entry.f32()?),
+                unit_to_u8(entry.f32()?),
+            ]),
+            b"CMYK" => cmyk_to_rgb(entry.f32()?, entry.f32()?, entry.f32()?, entry.f32()?),
+            b"LAB " => lab_to_rgb(entry.f32()? * 100.0, entry.f32()?, entry.f32()?),
+            b"Gray" => {
+                let gray = unit_to_u8(entry.f32()?);
+                Rgb([gray; 3])
+            }
+            other => {
+                log::warn!(
+                    "Skipping ASE swatch '{}' with unsupported color model {:?}",
+                    name,
+                    String::from_utf8_lossy(other)
+                );
+                continue;
+            }
+        };
+        swatches.push((rgb, Some(name)));
+    }
+
+    Ok(swatches)
+}
+
+fn parse_aco(data: &[u8]) -> crate::Result<Vec<Swatch>> {
+    let mut reader = Reader::new(data);
+    let version = reader.u16()?;
+    if version != 1 {
+        return Err(parse_error(format!("Unsupported ACO version {}", version)));
+    }
+
+    let count = reader.u16()?;
+    let mut colors = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        colors.push(read_aco_color(&mut reader)?);
+    }
+
+    // Version 2 repeats the colors with names after the version 1 section
+    let mut names = vec![None; colors.len()];
+    if !reader.is_empty() && reader.u16()? == 2 {
+        let count = reader.u16()? as usize;
+        for index in 0..count {
+            let color = read_aco_color(&mut reader)?;
+            reader.u16()?;
+            let length = reader.u32()? as usize;
+            let name = reader.utf16(length)?;
+            if index < colors.len() {
+                colors[index] = color;
+                names[index] = Some(name);
+            }
+        }
+    }
+
+    Ok(colors
+        .into_iter()
+        .zip(names)
+        .filter_map(|(color, name)| color.map(|rgb| (rgb, name)))
+        .collect())
+}
+
+/// One ACO color record, or `None` for unsupported color spaces
+fn read_aco_color(reader: &mut Reader) -> crate::Result<Option<Rgb<u8>>> {
+    let space = reader.u16()?;
+    let values = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+    let unit = |value: u16| f32::from(value) / 65535.0;
+
+    Ok(match space {
+        0 => Some(Rgb([
+            (values[0] >> 8) as u8,
+            (values[1] >> 8) as u8,
+            (values[2] >> 8) as u8,
+        ])),
+        1 => Some(hsb_to_rgb(
+            f32::from(values[0]) / 182.04,
+            unit(values[1]),
+            unit(values[2]),
+        )),
+        // Photoshop stores CMYK inverted (0 is full ink)
+        2 => Some(cmyk_to_rgb(
+            1.0 - unit(values[0]),
+            1.0 - unit(values[1]),
+            1.0 - unit(values[2]),
+            1.0 - unit(values[3]),
+        )),
+        7 => Some(lab_to_rgb(
+            f32::from(values[0]) / 100.0,
+            f32::from(values[1] as i16) / 100.0,
+            f32::from(values[2] as i16) / 100.0,
+        )),
+        8 => {
+            let gray = 255 - (f32::from(values[0]) / 10000.0 * 255.0).round() as u8;
+            Some(Rgb([gray; 3]))
+        }
+        other => {
+            log::warn!("Skipping ACO color with unsupported color space {}", other);
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn hexes(palette: &Palette) -> Vec<&str> {
+        palette.colors.iter().map(|c| c.hex.as_str()).collect()
+    }
+
+    fn names(palette: &Palette) -> Vec<&str> {
+        palette.colors.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    fn utf16_name(name: &str) -> Vec<u8> {
+        let mut units: Vec<u16> = name.encode_utf16().collect();
+        units.push(0);
+        units.iter().flat_map(|u| u.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(SwatchFormat::from_path("a.GPL"), Some(SwatchFormat::Gpl));
+        assert_eq!(
+            SwatchFormat::from_path("a.pal"),
+            Some(SwatchFormat::JascPal)
+        );
+        assert_eq!(
+            SwatchFormat::from_path("a.txt"),
+            Some(SwatchFormat::PaintNet)
+        );
+        assert_eq!(SwatchFormat::from_path("a.toml"), None);
+        assert_eq!(SwatchFormat::from_path("noext"), None);
+    }
+
+    #[test]
+    fn test_parse_gpl() {
+        let content = "GIMP Palette\nName: Sunset\nColumns: 4\n# comment\n255   0   0\tCoral Red\n  0 128 255\tUntitled\n  0 128 255\n";
+        let palette = SwatchFormat::Gpl.parse(content.as_bytes(), "file").unwrap();
+
+        assert_eq!(palette.name, "Sunset");
+        assert_eq!(hexes(&palette), vec!["#FF0000", "#0080FF", "#0080FF"]);
+        assert_eq!(palette.colors[0].name, "Coral Red");
+        // Unnamed swatches get unique generated names
+        assert_ne!(palette.colors[1].name, palette.colors[2].name);
+
+        assert!(SwatchFormat::Gpl.parse(b"255 0 0\n", "x").is_err());
+        assert!(SwatchFormat::Gpl
+            .parse(b"GIMP Palette\n300 0 0 bad\n", "x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_jasc_pal() {
+        let content = "JASC-PAL\r\n0100\r\n2\r\n0 0 0\r\n255 255 255\r\n";
+        let palette = SwatchFormat::JascPal
+            .parse(content.as_bytes(), "mono")
+            .unwrap();
+        assert_eq!(palette.name, "mono");
+        assert_eq!(hexes(&palette), vec!["#000000", "#FFFFFF"]);
+        assert_eq!(names(&palette), vec!["black", "white"]);
+    }
+
+    #[test]
+    fn test_parse_hex_lists() {
+        let paint_net = "; paint.net Palette File\n; comment\nFF112233\nff445566\n";
+        let palette = SwatchFormat::PaintNet
+            .parse(paint_net.as_bytes(), "pdn")
+            .unwrap();
+        assert_eq!(hexes(&palette), vec!["#112233", "#445566"]);
+
+        let lospec = "112233\n#445566\n\n";
+        let palette = SwatchFormat::Hex
+            .parse(lospec.as_bytes(), "lospec")
+            .unwrap();
+        assert_eq!(hexes(&palette), vec!["#112233", "#445566"]);
+
+        assert!(SwatchFormat::Hex.parse(b"12345\n", "x").is_err());
+        assert!(SwatchFormat::Hex.parse(b"\n", "x").is_err());
+    }
+
+    #[test]
+    fn test_parse_ase() {
+        fn color_block(name: &str, model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+            let mut body = Vec::new();
+            body.extend(((name.encode_utf16().count() + 1) as u16).to_be_bytes());
+            body.extend(utf16_name(name));
+            body.extend(model);
+            for value in values {
+                body.extend(value.to_be_bytes());
+            }
+            body.extend(0u16.to_be_bytes());
+
+            let mut block = ASE_COLOR_ENTRY.to_be_bytes().to_vec();
+            block.extend((body.len() as u32).to_be_bytes());
+            block.extend(body);
+            block
+        }
+
+        let mut data = b"ASEF".to_vec();
+        data.extend(1u16.to_be_bytes());
+        data.extend(0u16.to_be_bytes());
+        data.extend(4u32.to_be_bytes());
+        // Empty group start block
+        data.extend(0xC001u16.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        data.extend(color_block("Brand Red", b"RGB ", &[1.0, 0.0, 0.0]));
+        data.extend(color_block("Ink", b"CMYK", &[0.0, 0.0, 0.0, 1.0]));
+        data.extend(color_block("Mid", b"Gray", &[0.5]));
+
+        let palette = SwatchFormat::Ase.parse(&data, "brand").unwrap();
+        assert_eq!(palette.name, "brand");
+        assert_eq!(names(&palette), vec!["Brand Red", "Ink", "Mid"]);
+        assert_eq!(hexes(&palette), vec!["#FF0000", "#000000", "#808080"]);
+
+        assert!(SwatchFormat::Ase.parse(b"ASEF\0\x01", "x").is_err());
+        assert!(SwatchFormat::Ase.parse(b"GIMP", "x").is_err());
+    }
+
+    #[test]
+    fn test_parse_aco() {
+        let rgb = |r: u16, g: u16, b: u16| {
+            let mut record = 0u16.to_be_bytes().to_vec();
+            for value in [r, g, b, 0] {
+                record.extend(value.to_be_bytes());
+            }
+            record
+        };
+
+        let mut data = Vec::new();
+        data.extend(1u16.to_be_bytes());
+        data.extend(2u16.to_be_bytes());
+        data.extend(rgb(0xFFFF, 0, 0));
+        data.extend(rgb(0, 0x8000, 0xFFFF));
+
+        let palette = SwatchFormat::Aco.parse(&data, "v1").unwrap();
+        assert_eq!(hexes(&palette), vec!["#FF0000", "#0080FF"]);
+
+        // Version 2 section with names
+        data.extend(2u16.to_be_bytes());
+        data.extend(2u16.to_be_bytes());
+        for (record, name) in [
+            (rgb(0xFFFF, 0, 0), "Alert"),
+            (rgb(0, 0x8000, 0xFFFF), "Sky"),
+        ] {
+            data.extend(record);
+            data.extend(0u16.to_be_bytes());
+            data.extend(((name.len() + 1) as u32).to_be_bytes());
+            data.extend(utf16_name(name));
+        }
+
+        let palette = SwatchFormat::Aco.parse(&data, "v2").unwrap();
+        assert_eq!(names(&palette), vec!["Alert", "Sky"]);
+    }
+
+    #[test]
+    fn test_color_space_conversions() {
+        assert_eq!(hsb_to_rgb(120.0, 1.0, 1.0), Rgb([0, 255, 0]));
+        assert_eq!(cmyk_to_rgb(0.0, 1.0, 1.0, 0.0), Rgb([255, 0, 0]));
+
+        let white = lab_to_rgb(100.0, 0.0, 0.0);
+        assert!(white.0.iter().all(|&c| c >= 253));
+        assert_eq!(lab_to_rgb(0.0, 0.0, 0.0), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_load_swatch_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("lospec-8.hex");
+        fs::write(&path, "000000\nffffff\n").unwrap();
+
+        let palette = load_swatch_file(&path).unwrap();
+        assert_eq!(palette.name, "lospec-8");
+        assert_eq!(palette.path, PathBuf::from("user://lospec-8"));
+
+        assert!(load_swatch_file(temp_dir.path().join("missing.gpl")).is_err());
+        assert!(load_swatch_file(temp_dir.path().join("palette.toml")).is_err());
+    }
+}
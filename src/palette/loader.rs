@@ -106,6 +106,7 @@ impl PaletteLoader {
             name: name.to_string(),
             path: palette_dir.to_path_buf(),
             colors,
+            ..Default::default()
         })
     }
 
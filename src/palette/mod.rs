@@ -1,43 +1,107 @@
+mod base16;
 mod builtin;
+mod compose;
+mod export;
+mod extract;
+mod formats;
 mod loader;
 mod parser;
+mod preview;
+mod terminal;
+mod theme;
 mod toml_loader;
 
+pub use base16::{
+    default_pywal_cache, load_base16_scheme, load_pywal_palette, parse_base16_scheme, Base16Loader,
+};
 pub use builtin::BuiltinPalettes;
+pub use compose::{resolve_definition, resolve_definitions, Composition, PaletteDefinition};
+pub use export::{
+    AseExporter, CssExporter, ExporterRegistry, GplExporter, JsonExporter, PaletteExporter,
+    ScssExporter, SvgExporter, TailwindExporter, TomlExporter,
+};
+pub use extract::{ExtractMethod, PaletteExtractor};
+pub use formats::{load_swatch_file, SwatchFormat};
 pub use loader::PaletteLoader;
 pub use parser::{hex_to_rgb, parse_hex_color, rgb_to_hex};
+pub use preview::{ansi_swatches, is_builtin, SwatchSheet};
+pub(crate) use preview::{draw_text, GLYPH_HEIGHT};
+pub use terminal::{load_terminal_scheme, parse_terminal_color, TerminalFormat};
+pub use theme::{
+    contrast_ratio, relative_luminance, TerminalTheme, ThemeFormat, ThemeGenerator, ThemeVariant,
+};
 pub use toml_loader::{TomlPalette, TomlPaletteLoader};
 
 use crate::Palette;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub struct PaletteManager {
     palettes: HashMap<String, Palette>,
+    base16_loader: Base16Loader,
+    pywal_cache: Option<PathBuf>,
 }
 
 impl PaletteManager {
     pub fn new() -> Self {
         Self {
             palettes: HashMap::new(),
+            base16_loader: Base16Loader::new(),
+            pywal_cache: default_pywal_cache(),
         }
     }
 
-    /// Load all palettes (built-in and TOML)
+    /// Look for base16/base24 schemes in these directories instead of the
+    /// defaults
+    pub fn with_base16_dirs(mut self, paths: Vec<PathBuf>) -> Self {
+        self.base16_loader = Base16Loader::with_paths(paths);
+        self
+    }
+
+    /// Read the `pywal` palette from this colors.json, or disable it with `None`
+    pub fn with_pywal_cache(mut self, path: Option<PathBuf>) -> Self {
+        self.pywal_cache = path;
+        self
+    }
+
+    /// Load all palettes (built-in, base16, pywal and TOML)
     pub fn load_all_palettes(&mut self) -> crate::Result<()> {
+        self.palettes.clear();
+
         // Start with built-in palettes
         let builtin_palettes = BuiltinPalettes::get_all();
         for palette in builtin_palettes {
             self.palettes.insert(palette.name.to_lowercase(), palette);
         }
 
-        // Load TOML palettes (user-defined)
-        let toml_loader = TomlPaletteLoader::new();
-        let toml_palettes = toml_loader.load_palettes()?;
-        for palette in toml_palettes {
-            // TOML palettes can override built-in ones
+        for palette in self.base16_loader.load_schemes()? {
             self.palettes.insert(palette.name.to_lowercase(), palette);
         }
 
+        // The pywal cache is re-read on every run so it tracks the active theme
+        if let Some(cache) = self.pywal_cache.as_ref().filter(|path| path.is_file()) {
+            match load_pywal_palette(cache) {
+                Ok(palette) => {
+                    self.palettes.insert(palette.name.to_lowercase(), palette);
+                }
+                Err(e) => log::warn!("Failed to load pywal colors: {}", e),
+            }
+        }
+
+        // Load TOML palettes (user-defined), which may extend any palette
+        // loaded so far or each other
+        let definitions = TomlPaletteLoader::new().load_definitions()?;
+        let resolved = resolve_definitions(&self.palettes, &definitions);
+        for (definition, result) in definitions.iter().zip(resolved) {
+            match result {
+                // TOML palettes can override built-in and scheme palettes
+                Ok(palette) => {
+                    self.palettes.insert(palette.name.to_lowercase(), palette);
+                }
+                Err(e) => log::warn!("Skipping palette '{}': {}", definition.palette.name, e),
+            }
+        }
+
         log::info!("Loaded {} total palettes", self.palettes.len());
         Ok(())
     }
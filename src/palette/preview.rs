@@ -0,0 +1,449 @@
+use crate::Palette;
+use image::{Rgb, RgbImage};
+
+use super::parser::hex_to_rgb;
+
+/// One line per color: a truecolor block followed by the name and hex.
+/// With `truecolor` off (e.g. `NO_COLOR` is set) the blocks are left out.
+pub fn ansi_swatches(palette: &Palette, truecolor: bool) -> String {
+    let name_width = palette
+        .colors
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut out = format!("{} ({} colors)\n", palette.name, palette.colors.len());
+    for color in &palette.colors {
+        let block = match hex_to_rgb(&color.hex) {
+            Ok((r, g, b)) if truecolor => format!("\x1b[48;2;{};{};{}m      \x1b[0m  ", r, g, b),
+            _ => String::new(),
+        };
+        out.push_str(&format!(
+            "  {}{:<width$}  {}\n",
+            block,
+            color.name,
+            color.hex.to_uppercase(),
+            width = name_width
+        ));
+    }
+
+    if !palette.groups.is_empty() {
+        out.push_str("groups (-c @group)\n");
+        for (group, members) in &palette.groups {
+            out.push_str(&format!("  @{}  {}\n", group, members.join(", ")));
+        }
+    }
+    if !palette.aliases.is_empty() {
+        out.push_str("aliases\n");
+        for (alias, name) in &palette.aliases {
+            out.push_str(&format!("  {} -> {}\n", alias, name));
+        }
+    }
+    out
+}
+
+/// Whether a palette was compiled into the binary rather than loaded from disk
+pub fn is_builtin(palette: &Palette) -> bool {
+    palette.path.to_string_lossy().starts_with("builtin://")
+}
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const TEXT: Rgb<u8> = Rgb([30, 30, 30]);
+const MUTED: Rgb<u8> = Rgb([102, 102, 102]);
+const BORDER: Rgb<u8> = Rgb([200, 200, 200]);
+
+/// PNG swatch sheet: a titled section per palette source, each palette a
+/// labelled row of tiles with the color name and hex under every swatch
+pub struct SwatchSheet {
+    columns: u32,
+    tile_width: u32,
+    tile_height: u32,
+}
+
+impl SwatchSheet {
+    pub fn new() -> Self {
+        Self {
+            columns: 8,
+            tile_width: 96,
+            tile_height: 48,
+        }
+    }
+
+    /// Swatches per row before wrapping
+    pub fn with_columns(mut self, columns: u32) -> Self {
+        self.columns = columns.max(1);
+        self
+    }
+
+    /// Swatch tile size in pixels; labels are truncated to the width
+    pub fn with_tile_size(mut self, width: u32, height: u32) -> Self {
+        self.tile_width = width.max(GLYPH_ADVANCE * 4);
+        self.tile_height = height.max(8);
+        self
+    }
+
+    /// Render palettes grouped into sections, e.g. `("Built-in", ...)`.
+    /// Empty sections are skipped.
+    pub fn render(&self, sections: &[(&str, Vec<&Palette>)]) -> RgbImage {
+        const MARGIN: u32 = 16;
+        const GAP: u32 = 8;
+        const TITLE_HEIGHT: u32 = 2 * GLYPH_HEIGHT + 12;
+        const NAME_HEIGHT: u32 = GLYPH_HEIGHT + 8;
+        const LABEL_HEIGHT: u32 = 2 * (GLYPH_HEIGHT + 3) + 4;
+
+        let sections: Vec<&(&str, Vec<&Palette>)> =
+            sections.iter().filter(|(_, p)| !p.is_empty()).collect();
+
+        let widest = sections
+            .iter()
+            .flat_map(|(_, palettes)| palettes.iter())
+            .map(|p| (p.colors.len() as u32).clamp(1, self.columns))
+            .max()
+            .unwrap_or(1);
+        let width = 2 * MARGIN + widest * (self.tile_width + GAP) - GAP;
+
+        let row_height = self.tile_height + LABEL_HEIGHT + GAP;
+        let palette_height = |palette: &Palette| {
+            let rows = ((palette.colors.len() as u32).max(1) + self.columns - 1) / self.columns;
+            NAME_HEIGHT + rows * row_height + GAP
+        };
+        let height = 2 * MARGIN
+            + sections
+                .iter()
+                .map(|(_, palettes)| {
+                    TITLE_HEIGHT + palettes.iter().map(|p| palette_height(p)).sum::<u32>()
+                })
+                .sum::<u32>();
+
+        let mut sheet = RgbImage::from_pixel(width, height, BACKGROUND);
+        let max_title_chars = ((width - 2 * MARGIN) / (2 * GLYPH_ADVANCE)) as usize;
+        let max_label_chars = (self.tile_width / GLYPH_ADVANCE) as usize;
+
+        let mut y = MARGIN;
+        for (title, palettes) in sections {
+            let title = format!("{} palettes ({})", title, palettes.len());
+            draw_text(&mut sheet, MARGIN, y, &title, max_title_chars, 2, TEXT);
+            y += TITLE_HEIGHT;
+
+            for palette in palettes {
+                let name = format!("{} ({} colors)", palette.name, palette.colors.len());
+                draw_text(&mut sheet, MARGIN, y, &name, 2 * max_title_chars, 1, TEXT);
+                y += NAME_HEIGHT;
+
+                for (index, color) in palette.colors.iter().enumerate() {
+                    let index = index as u32;
+                    let x = MARGIN + (index % self.columns) * (self.tile_width + GAP);
+                    let tile_y = y + (index / self.columns) * row_height;
+                    let fill = hex_to_rgb(&color.hex)
+                        .map(|(r, g, b)| Rgb([r, g, b]))
+                        .unwrap_or(BACKGROUND);
+
+                    fill_rect(
+                        &mut sheet,
+                        x,
+                        tile_y,
+                        self.tile_width,
+                        self.tile_height,
+                        BORDER,
+                    );
+                    fill_rect(
+                        &mut sheet,
+                        x + 1,
+                        tile_y + 1,
+                        self.tile_width - 2,
+                        self.tile_height - 2,
+                        fill,
+                    );
+
+                    let label_y = tile_y + self.tile_height + 4;
+                    draw_text(
+                        &mut sheet,
+                        x,
+                        label_y,
+                        &color.name,
+                        max_label_chars,
+                        1,
+                        TEXT,
+                    );
+                    draw_text(
+                        &mut sheet,
+                        x,
+                        label_y + GLYPH_HEIGHT + 3,
+                        &color.hex.to_uppercase(),
+                        max_label_chars,
+                        1,
+                        MUTED,
+                    );
+                }
+
+                y += palette_height(palette) - NAME_HEIGHT;
+            }
+        }
+
+        sheet
+    }
+
+    /// Render a single palette under its source heading
+    pub fn render_palette(&self, palette: &Palette) -> RgbImage {
+        let source = if is_builtin(palette) {
+            "Built-in"
+        } else {
+            "User"
+        };
+        self.render(&[(source, vec![palette])])
+    }
+}
+
+impl Default for SwatchSheet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+const GLYPH_WIDTH: u32 = 5;
+pub(crate) const GLYPH_HEIGHT: u32 = 7;
+const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// Draw `text` with the built-in 5x7 font, cut to `max_chars` (ending in
+/// `..` when truncated). Characters outside printable ASCII draw as `?`.
+pub(crate) fn draw_text(
+    image: &mut RgbImage,
+    x: u32,
+    y: u32,
+    text: &str,
+    max_chars: usize,
+    scale: u32,
+    color: Rgb<u8>,
+) {
+    let mut chars: Vec<char> = text.chars().collect();
+    if chars.len() > max_chars {
+        chars.truncate(max_chars.saturating_sub(2));
+        chars.extend(['.', '.']);
+    }
+
+    for (index, c) in chars.into_iter().enumerate() {
+        let code = c as u32;
+        let glyph = if (0x20..0x7F).contains(&code) {
+            &FONT[(code - 0x20) as usize]
+        } else {
+            &FONT[(b'?' - 0x20) as usize]
+        };
+        let gx = x + index as u32 * GLYPH_ADVANCE * scale;
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> col) != 0 {
+                    fill_rect(
+                        image,
+                        gx + col * scale,
+                        y + row as u32 * scale,
+                        scale,
+                        scale,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// 5x7 glyphs for ASCII 0x20..=0x7E, one byte per row, bit 4 leftmost
+const FONT: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // !
+    [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00], // "
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // #
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // $
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // %
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // &
+    [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // )
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // *
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ,
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // .
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // /
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // 0
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // 1
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // 2
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // 3
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // 4
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // 5
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // 6
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // 7
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // 8
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ;
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // <
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // =
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // >
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E], // @
+    [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11], // A
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // B
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // C
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C], // D
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // E
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // F
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // G
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // H
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // L
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // N
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // O
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // P
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // Q
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // R
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // S
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // W
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // X
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // Y
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // Z
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // [
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // \
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ]
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // _
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F], // a
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E], // b
+    [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E], // c
+    [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F], // d
+    [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E], // e
+    [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08], // f
+    [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E], // g
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // h
+    [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E], // i
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C], // j
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // k
+    [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // l
+    [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11], // m
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // n
+    [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E], // o
+    [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10], // p
+    [0x00, 0x00, 0x0D, 0x13, 0x0F, 0x01, 0x01], // q
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // r
+    [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E], // s
+    [0x08, 0x08, 0x1C, 0x08, 0x08, 0x09, 0x06], // t
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0D], // u
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04], // v
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0A], // w
+    [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11], // x
+    [0x00, 0x00, 0x11, 0x11, 0x0F, 0x01, 0x0E], // y
+    [0x00, 0x00, 0x1F, 0x02, 0x04, 0x08, 0x1F], // z
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // {
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // |
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // }
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+    use std::path::PathBuf;
+
+    fn palette(name: &str, path: &str, hexes: &[&str]) -> Palette {
+        Palette {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            colors: hexes
+                .iter()
+                .enumerate()
+                .map(|(i, hex)| Color {
+                    name: format!("color{}", i),
+                    hex: hex.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ansi_swatches() {
+        let p = palette("test", "builtin://test", &["#ff0000", "#00FF00"]);
+
+        let colored = ansi_swatches(&p, true);
+        assert!(colored.starts_with("test (2 colors)\n"));
+        assert!(colored.contains("\x1b[48;2;255;0;0m"));
+        assert!(colored.contains("color0  #FF0000"));
+
+        let plain = ansi_swatches(&p, false);
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(plain.lines().nth(2), Some("  color1  #00FF00"));
+
+        let mut grouped = p.clone();
+        grouped
+            .groups
+            .insert("warm".to_string(), vec!["color0".to_string()]);
+        grouped
+            .aliases
+            .insert("red".to_string(), "color0".to_string());
+        let plain = ansi_swatches(&grouped, false);
+        assert!(plain.contains("  @warm  color0\n"));
+        assert!(plain.ends_with("  red -> color0\n"));
+    }
+
+    #[test]
+    fn test_is_builtin() {
+        assert!(is_builtin(&palette("a", "builtin://a", &[])));
+        assert!(!is_builtin(&palette("a", "user://a", &[])));
+        assert!(!is_builtin(&palette("a", "/home/me/a.toml", &[])));
+    }
+
+    #[test]
+    fn test_render_palette_draws_swatches() {
+        let p = palette("test", "user://test", &["#FF0000", "#0000FF", "#00FF00"]);
+        let sheet = SwatchSheet::new().with_columns(2).render_palette(&p);
+
+        // Two columns wide, two rows of tiles tall
+        assert_eq!(sheet.width(), 2 * 16 + 2 * 96 + 8);
+        let red = sheet.pixels().filter(|px| **px == Rgb([255, 0, 0])).count();
+        let green = sheet.pixels().filter(|px| **px == Rgb([0, 255, 0])).count();
+        assert_eq!(red, 94 * 46);
+        assert_eq!(green, 94 * 46);
+        assert!(sheet.pixels().any(|px| *px == TEXT));
+    }
+
+    #[test]
+    fn test_render_sections_skips_empty_groups() {
+        let a = palette("a", "builtin://a", &["#111111"; 12]);
+        let b = palette("b", "user://b", &["#222222"; 3]);
+
+        let both = SwatchSheet::new().render(&[("Built-in", vec![&a]), ("User", vec![&b])]);
+        let only_a = SwatchSheet::new().render(&[("Built-in", vec![&a]), ("User", vec![])]);
+        assert_eq!(both.width(), only_a.width());
+        assert!(both.height() > only_a.height());
+    }
+
+    #[test]
+    fn test_draw_text_truncates() {
+        let mut image = RgbImage::from_pixel(60, 10, BACKGROUND);
+        draw_text(&mut image, 0, 0, "abcdefghijklmnop", 4, 1, TEXT);
+        // Nothing drawn past the fourth glyph
+        assert!(
+            (4 * GLYPH_ADVANCE..60).all(|x| (0..10).all(|y| image.get_pixel(x, y).eq(&BACKGROUND)))
+        );
+        // The last two glyphs are dots on the bottom rows only
+        assert!((2 * GLYPH_ADVANCE..4 * GLYPH_ADVANCE)
+            .all(|x| (0..5).all(|y| image.get_pixel(x, y).eq(&BACKGROUND))));
+    }
+}
@@ -0,0 +1,795 @@
+use crate::{Color, Palette, RustBucketError};
+use image::Rgb;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::parser::rgb_to_hex;
+
+/// Names of the eight normal ANSI colors, in index order
+pub(super) const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Terminal emulator color scheme formats that can be read as palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalFormat {
+    /// Alacritty `colors` section, YAML or TOML
+    Alacritty,
+    /// kitty.conf or a kitty theme file
+    Kitty,
+    /// X resources (`*.color0: #...`)
+    Xresources,
+    /// iTerm2 `.itermcolors` property list
+    Iterm2,
+    /// Windows Terminal scheme object or settings.json with `schemes`
+    WindowsTerminal,
+    /// foot.ini `[colors]` section
+    Foot,
+    /// WezTerm TOML color scheme
+    Wezterm,
+    /// pywal's generated `colors.json`
+    Pywal,
+}
+
+impl TerminalFormat {
+    /// Detect the format from the file name, looking at the contents where the
+    /// extension is ambiguous
+    pub fn detect<P: AsRef<Path>>(path: P, content: &str) -> Option<Self> {
+        let path = path.as_ref();
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "itermcolors" => return Some(Self::Iterm2),
+            "yml" | "yaml" => return Some(Self::Alacritty),
+            "json" if content.contains("\"special\"") => return Some(Self::Pywal),
+            "json" => return Some(Self::WindowsTerminal),
+            "ini" => return Some(Self::Foot),
+            "toml" => {
+                return if content.contains("[colors.primary]")
+                    || content.contains("[colors.normal]")
+                {
+                    Some(Self::Alacritty)
+                } else if content.contains("ansi") && content.contains("brights") {
+                    Some(Self::Wezterm)
+                } else {
+                    None
+                };
+            }
+            _ => {}
+        }
+
+        if file_name.contains("xresources") || file_name.contains("xdefaults") {
+            return Some(Self::Xresources);
+        }
+        if extension == "conf" || file_name.contains("kitty") {
+            return Some(Self::Kitty);
+        }
+
+        let keys: Vec<&str> = content
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .collect();
+        if keys.contains(&"color0") {
+            Some(Self::Kitty)
+        } else if keys
+            .iter()
+            .any(|key| key.ends_with("color0:") || key.ends_with("background:"))
+        {
+            Some(Self::Xresources)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a file could hold a terminal scheme, judged by name alone
+    pub fn is_candidate<P: AsRef<Path>>(path: P) -> bool {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        matches!(
+            extension.as_str(),
+            "itermcolors" | "yml" | "yaml" | "json" | "ini" | "conf"
+        ) || name.contains("xresources")
+            || name.contains("xdefaults")
+    }
+
+    /// Parse scheme contents into palettes. Windows Terminal settings can
+    /// hold several schemes; every other format yields one palette.
+    pub fn parse(&self, content: &str, default_name: &str) -> crate::Result<Vec<Palette>> {
+        let schemes = match self {
+            Self::Alacritty => vec![parse_alacritty(content, default_name)?],
+            Self::Kitty => vec![parse_kitty(content, default_name)],
+            Self::Xresources => vec![parse_xresources(content, default_name)],
+            Self::Iterm2 => vec![parse_iterm2(content, default_name)?],
+            Self::WindowsTerminal => parse_windows_terminal(content, default_name)?,
+            Self::Foot => vec![parse_foot(content, default_name)],
+            Self::Wezterm => vec![parse_wezterm(content, default_name)?],
+            Self::Pywal => vec![parse_pywal(content, default_name)?],
+        };
+
+        schemes
+            .into_iter()
+            .map(|scheme| scheme.into_palette(*self))
+            .collect()
+    }
+}
+
+/// Load every color scheme in a terminal config file
+pub fn load_terminal_scheme<P: AsRef<Path>>(path: P) -> crate::Result<Vec<Palette>> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(RustBucketError::IoError)?;
+    parse_terminal_scheme(path, &content)
+}
+
+/// Parse the already read `content` of the scheme file at `path`
+pub(super) fn parse_terminal_scheme(path: &Path, content: &str) -> crate::Result<Vec<Palette>> {
+    let format = TerminalFormat::detect(path, content).ok_or_else(|| {
+        RustBucketError::PaletteParseError(format!(
+            "Unrecognized terminal color scheme: {}",
+            path.display()
+        ))
+    })?;
+
+    let default_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.trim_start_matches('.'))
+        .filter(|s| !s.is_empty())
+        .unwrap_or("terminal");
+
+    format
+        .parse(content, default_name)
+        .map_err(|e| RustBucketError::PaletteParseError(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse `#RRGGBB`, `0xRRGGBB`, bare `RRGGBB`, `#RGB` and X11 `rgb:RR/GG/BB`
+pub fn parse_terminal_color(value: &str) -> Option<Rgb<u8>> {
+    let value = value.trim().trim_matches(['"', '\'']);
+
+    if let Some(rgb) = value.strip_prefix("rgb:") {
+        let parts: Vec<&str> = rgb.split('/').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        let mut channels = [0u8; 3];
+        for (channel, part) in channels.iter_mut().zip(parts) {
+            // X11 allows 1 to 4 hex digits per channel
+            let digits = part.len() as u32;
+            if !(1..=4).contains(&digits) {
+                return None;
+            }
+            let raw = u32::from_str_radix(part, 16).ok()?;
+            let max = 16u32.pow(digits) - 1;
+            *channel = ((raw * 255 + max / 2) / max) as u8;
+        }
+        return Some(Rgb(channels));
+    }
+
+    let hex = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        6 => Some(Rgb([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ])),
+        3 => {
+            let expand = |c: &str| channel(c).map(|v| v * 17);
+            Some(Rgb([
+                expand(&hex[0..1])?,
+                expand(&hex[1..2])?,
+                expand(&hex[2..3])?,
+            ]))
+        }
+        _ => None,
+    }
+}
+
+/// Colors collected by role before they become a palette
+struct Scheme {
+    name: String,
+    roles: HashMap<String, Rgb<u8>>,
+}
+
+impl Scheme {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            roles: HashMap::new(),
+        }
+    }
+
+    fn set(&mut self, role: &str, value: &str) {
+        match parse_terminal_color(value) {
+            Some(rgb) => {
+                self.roles.insert(role.to_string(), rgb);
+            }
+            None => log::debug!("Ignoring invalid color '{}' for {}", value, role),
+        }
+    }
+
+    /// Set the ANSI color for a `normal`/`bright` color name such as `red`
+    fn set_named(&mut self, name: &str, bright: bool, value: &str) {
+        if let Some(index) = ANSI_NAMES.iter().position(|n| *n == name) {
+            let index = if bright { index + 8 } else { index };
+            self.set(&format!("color{}", index), value);
+        }
+    }
+
+    /// Palette with colors in role order: background, foreground, cursor,
+    /// selection_background, then color0 to color15
+    fn into_palette(self, format: TerminalFormat) -> crate::Result<Palette> {
+        let mut order: Vec<String> = ["background", "foreground", "cursor", "selection_background"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        order.extend((0..16).map(|i| format!("color{}", i)));
+
+        let colors: Vec<Color> = order
+            .into_iter()
+            .filter_map(|role| {
+                self.roles.get(&role).map(|rgb| Color {
+                    hex: rgb_to_hex(rgb[0], rgb[1], rgb[2]),
+                    name: role,
+                })
+            })
+            .collect();
+
+        if colors.is_empty() {
+            return Err(RustBucketError::PaletteParseError(format!(
+                "No colors found in {:?} scheme",
+                format
+            )));
+        }
+
+        Ok(Palette {
+            path: PathBuf::from(format!("user://{}", self.name)),
+            name: self.name,
+            colors,
+            ..Default::default()
+        })
+    }
+}
+
+fn parse_error(message: String) -> RustBucketError {
+    RustBucketError::PaletteParseError(message)
+}
+
+fn value_str(value: &Value) -> Option<&str> {
+    value.as_str()
+}
+
+/// Alacritty's `colors` table, shared by the YAML and TOML config formats
+fn parse_alacritty(content: &str, default_name: &str) -> crate::Result<Scheme> {
+    let config: Value = if content.contains("[colors") {
+        let table: toml::Value = toml::from_str(content)
+            .map_err(|e| parse_error(format!("Invalid alacritty TOML: {}", e)))?;
+        serde_json::to_value(table).map_err(|e| parse_error(e.to_string()))?
+    } else {
+        serde_yaml::from_str(content)
+            .map_err(|e| parse_error(format!("Invalid alacritty YAML: {}", e)))?
+    };
+
+    let colors = config
+        .get("colors")
+        .ok_or_else(|| parse_error("Missing 'colors' section".to_string()))?;
+
+    let mut scheme = Scheme::new(default_name);
+    let field = |section: &str, key: &str| {
+        colors
+            .get(section)
+            .and_then(|s| s.get(key))
+            .and_then(value_str)
+    };
+
+    for (role, section, key) in [
+        ("background", "primary", "background"),
+        ("foreground", "primary", "foreground"),
+        ("cursor", "cursor", "cursor"),
+        ("selection_background", "selection", "background"),
+    ] {
+        if let Some(value) = field(section, key) {
+            scheme.set(role, value);
+        }
+    }
+    for (section, bright) in [("normal", false), ("bright", true)] {
+        for name in ANSI_NAMES {
+            if let Some(value) = field(section, name) {
+                scheme.set_named(name, bright, value);
+            }
+        }
+    }
+
+    Ok(scheme)
+}
+
+/// `key value` lines; a `## name:` comment names the theme
+fn parse_kitty(content: &str, default_name: &str) -> Scheme {
+    let mut scheme = Scheme::new(default_name);
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("## name:") {
+            scheme.name = name.trim().to_string();
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        match key {
+            "background" | "foreground" | "cursor" | "selection_background" => {
+                scheme.set(key, value)
+            }
+            _ if is_ansi_key(key) => scheme.set(key, value),
+            _ => {}
+        }
+    }
+    scheme
+}
+
+fn is_ansi_key(key: &str) -> bool {
+    key.strip_prefix("color")
+        .and_then(|index| index.parse::<u8>().ok())
+        .is_some_and(|index| index < 16)
+}
+
+/// `*.color0: #...` style resources, with `#define` macros expanded
+fn parse_xresources(content: &str, default_name: &str) -> Scheme {
+    let mut scheme = Scheme::new(default_name);
+    let mut defines: HashMap<&str, &str> = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(define) = line.strip_prefix("#define") {
+            let mut parts = define.split_whitespace();
+            if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                defines.insert(name, value);
+            }
+            continue;
+        }
+        if line.starts_with('!') || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((resource, value)) = line.split_once(':') else {
+            continue;
+        };
+        // The last component after `.` or `*` names the resource
+        let key = resource
+            .rsplit(['.', '*'])
+            .next()
+            .unwrap_or(resource)
+            .trim();
+        let value = value.trim();
+        let value = defines.get(value).copied().unwrap_or(value);
+
+        match key {
+            "background" | "foreground" => scheme.set(key, value),
+            "cursorColor" => scheme.set("cursor", value),
+            _ if is_ansi_key(key) => scheme.set(key, value),
+            _ => {}
+        }
+    }
+    scheme
+}
+
+/// iTerm2 property list with `Ansi N Color` dictionaries of float components
+fn parse_iterm2(content: &str, default_name: &str) -> crate::Result<Scheme> {
+    let value = plist::Value::from_reader_xml(content.as_bytes())
+        .map_err(|e| parse_error(format!("Invalid iTerm2 property list: {}", e)))?;
+    let dict = value
+        .as_dictionary()
+        .ok_or_else(|| parse_error("iTerm2 colors must be a dictionary".to_string()))?;
+
+    let mut scheme = Scheme::new(default_name);
+    let mut keys: Vec<(String, String)> = vec![
+        ("Background Color".to_string(), "background".to_string()),
+        ("Foreground Color".to_string(), "foreground".to_string()),
+        ("Cursor Color".to_string(), "cursor".to_string()),
+        (
+            "Selection Color".to_string(),
+            "selection_background".to_string(),
+        ),
+    ];
+    keys.extend((0..16).map(|i| (format!("Ansi {} Color", i), format!("color{}", i))));
+
+    for (key, role) in keys {
+        let Some(color) = dict.get(&key).and_then(|v| v.as_dictionary()) else {
+            continue;
+        };
+        let component = |name: &str| {
+            color
+                .get(name)
+                .and_then(|v| v.as_real())
+                .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+        };
+        if let (Some(r), Some(g), Some(b)) = (
+            component("Red Component"),
+            component("Green Component"),
+            component("Blue Component"),
+        ) {
+            scheme.roles.insert(role, Rgb([r, g, b]));
+        }
+    }
+
+    Ok(scheme)
+}
+
+/// pywal cache: `special` background/foreground/cursor plus `colors`
+fn parse_pywal(content: &str, default_name: &str) -> crate::Result<Scheme> {
+    let json: Value = serde_json::from_str(content)
+        .map_err(|e| parse_error(format!("Invalid pywal colors.json: {}", e)))?;
+
+    let mut scheme = Scheme::new(default_name);
+    for role in ["background", "foreground", "cursor"] {
+        if let Some(value) = json
+            .pointer(&format!("/special/{}", role))
+            .and_then(value_str)
+        {
+            scheme.set(role, value);
+        }
+    }
+    for index in 0..16 {
+        let key = format!("color{}", index);
+        if let Some(value) = json
+            .get("colors")
+            .and_then(|c| c.get(&key))
+            .and_then(value_str)
+        {
+            scheme.set(&key, value);
+        }
+    }
+    Ok(scheme)
+}
+
+/// A single scheme object, or settings.json with a `schemes` array
+fn parse_windows_terminal(content: &str, default_name: &str) -> crate::Result<Vec<Scheme>> {
+    let json: Value = serde_json::from_str(content)
+        .map_err(|e| parse_error(format!("Invalid Windows Terminal JSON: {}", e)))?;
+
+    let objects: Vec<&Value> = match json.get("schemes").and_then(|s| s.as_array()) {
+        Some(schemes) => schemes.iter().collect(),
+        None => vec![&json],
+    };
+
+    let schemes: Vec<Scheme> = objects
+        .into_iter()
+        .map(|object| {
+            let name = object
+                .get("name")
+                .and_then(value_str)
+                .unwrap_or(default_name);
+            let mut scheme = Scheme::new(name);
+            for (key, role) in [
+                ("background", "background"),
+                ("foreground", "foreground"),
+                ("cursorColor", "cursor"),
+                ("selectionBackground", "selection_background"),
+            ] {
+                if let Some(value) = object.get(key).and_then(value_str) {
+                    scheme.set(role, value);
+                }
+            }
+            for (index, name) in ANSI_NAMES.iter().enumerate() {
+                // Windows Terminal calls magenta "purple"
+                let key = if *name == "magenta" { "purple" } else { name };
+                if let Some(value) = object.get(key).and_then(value_str) {
+                    scheme.set(&format!("color{}", index), value);
+                }
+                let bright = format!("bright{}{}", key[..1].to_uppercase(), &key[1..]);
+                if let Some(value) = object.get(&bright).and_then(value_str) {
+                    scheme.set(&format!("color{}", index + 8), value);
+                }
+            }
+            scheme
+        })
+        .collect();
+
+    if schemes.is_empty() {
+        return Err(parse_error("No color schemes found".to_string()));
+    }
+    Ok(schemes)
+}
+
+/// foot.ini `[colors]` section with `regularN`/`brightN` keys
+fn parse_foot(content: &str, default_name: &str) -> Scheme {
+    let mut scheme = Scheme::new(default_name);
+    let mut in_colors = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_colors = line == "[colors]" || line == "[colors-dark]";
+            continue;
+        }
+        if !in_colors || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+
+        if let Some(index) = key
+            .strip_prefix("regular")
+            .and_then(|i| i.parse::<u8>().ok())
+        {
+            if index < 8 {
+                scheme.set(&format!("color{}", index), value);
+            }
+        } else if let Some(index) = key
+            .strip_prefix("bright")
+            .and_then(|i| i.parse::<u8>().ok())
+        {
+            if index < 8 {
+                scheme.set(&format!("color{}", index + 8), value);
+            }
+        } else {
+            match key {
+                "background" | "foreground" => scheme.set(key, value),
+                "selection-background" => scheme.set("selection_background", value),
+                // `cursor=<text> <cursor>`
+                "cursor" => {
+                    if let Some(cursor) = value.split_whitespace().nth(1) {
+                        scheme.set("cursor", cursor);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    scheme
+}
+
+/// WezTerm scheme TOML: `[colors]` with `ansi`/`brights` arrays
+fn parse_wezterm(content: &str, default_name: &str) -> crate::Result<Scheme> {
+    let config: toml::Value =
+        toml::from_str(content).map_err(|e| parse_error(format!("Invalid WezTerm TOML: {}", e)))?;
+    let colors = config
+        .get("colors")
+        .ok_or_else(|| parse_error("Missing 'colors' table".to_string()))?;
+
+    let name = config
+        .get("metadata")
+        .and_then(|m| m.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or(default_name);
+    let mut scheme = Scheme::new(name);
+
+    for (key, role) in [
+        ("background", "background"),
+        ("foreground", "foreground"),
+        ("cursor_bg", "cursor"),
+        ("selection_bg", "selection_background"),
+    ] {
+        if let Some(value) = colors.get(key).and_then(|v| v.as_str()) {
+            scheme.set(role, value);
+        }
+    }
+    for (key, offset) in [("ansi", 0), ("brights", 8)] {
+        let values = colors.get(key).and_then(|v| v.as_array());
+        for (index, value) in values.into_iter().flatten().take(8).enumerate() {
+            if let Some(value) = value.as_str() {
+                scheme.set(&format!("color{}", index + offset), value);
+            }
+        }
+    }
+
+    Ok(scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn color<'a>(palette: &'a Palette, name: &str) -> Option<&'a str> {
+        palette
+            .colors
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.hex.as_str())
+    }
+
+    fn parse_one(format: TerminalFormat, content: &str) -> Palette {
+        let mut palettes = format.parse(content, "scheme").unwrap();
+        assert_eq!(palettes.len(), 1);
+        palettes.remove(0)
+    }
+
+    #[test]
+    fn test_parse_terminal_color() {
+        assert_eq!(parse_terminal_color("#1d1f21"), Some(Rgb([29, 31, 33])));
+        assert_eq!(parse_terminal_color("'0x1D1F21'"), Some(Rgb([29, 31, 33])));
+        assert_eq!(parse_terminal_color("1d1f21"), Some(Rgb([29, 31, 33])));
+        assert_eq!(parse_terminal_color("#f0a"), Some(Rgb([255, 0, 170])));
+        assert_eq!(
+            parse_terminal_color("rgb:ff/80/00"),
+            Some(Rgb([255, 128, 0]))
+        );
+        assert_eq!(
+            parse_terminal_color("rgb:ffff/0/8"),
+            Some(Rgb([255, 0, 136]))
+        );
+        assert_eq!(parse_terminal_color("red"), None);
+        assert_eq!(parse_terminal_color("#12345"), None);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        use TerminalFormat::*;
+        assert_eq!(TerminalFormat::detect("a.itermcolors", ""), Some(Iterm2));
+        assert_eq!(TerminalFormat::detect("a.yml", ""), Some(Alacritty));
+        assert_eq!(TerminalFormat::detect("theme.conf", ""), Some(Kitty));
+        assert_eq!(TerminalFormat::detect(".Xresources", ""), Some(Xresources));
+        assert_eq!(TerminalFormat::detect("foot.ini", ""), Some(Foot));
+        assert_eq!(
+            TerminalFormat::detect("a.toml", "[colors.primary]\n"),
+            Some(Alacritty)
+        );
+        assert_eq!(
+            TerminalFormat::detect("a.toml", "[colors]\nansi = []\nbrights = []\n"),
+            Some(Wezterm)
+        );
+        assert_eq!(
+            TerminalFormat::detect("a.toml", "name = \"x\"\n[[colors]]\n"),
+            None
+        );
+        assert_eq!(
+            TerminalFormat::detect("theme", "color0 #000000\n"),
+            Some(Kitty)
+        );
+        assert_eq!(
+            TerminalFormat::detect("theme", "*.color0: #000000\n"),
+            Some(Xresources)
+        );
+    }
+
+    #[test]
+    fn test_parse_alacritty_yaml_and_toml() {
+        let yaml = "colors:\n  primary:\n    background: '0x1d1f21'\n    foreground: '#c5c8c6'\n  normal:\n    black: '#000000'\n    magenta: '#b294bb'\n  bright:\n    white: '#ffffff'\n";
+        let palette = parse_one(TerminalFormat::Alacritty, yaml);
+        assert_eq!(palette.name, "scheme");
+        assert_eq!(palette.colors[0].name, "background");
+        assert_eq!(color(&palette, "background"), Some("#1D1F21"));
+        assert_eq!(color(&palette, "color5"), Some("#B294BB"));
+        assert_eq!(color(&palette, "color15"), Some("#FFFFFF"));
+
+        let toml =
+            "[colors.primary]\nbackground = \"#1d1f21\"\n[colors.bright]\nred = \"#ff3334\"\n";
+        let palette = parse_one(TerminalFormat::Alacritty, toml);
+        assert_eq!(color(&palette, "background"), Some("#1D1F21"));
+        assert_eq!(color(&palette, "color9"), Some("#FF3334"));
+    }
+
+    #[test]
+    fn test_parse_kitty_and_xresources() {
+        let kitty = "## name: Tokyo Night\nbackground #1a1b26\nforeground   #c0caf5\n# color0 #ffffff\ncolor0 #15161e\ncolor15 #c0caf5\ncolor16 #ff9e64\nfont_size 12\n";
+        let palette = parse_one(TerminalFormat::Kitty, kitty);
+        assert_eq!(palette.name, "Tokyo Night");
+        assert_eq!(color(&palette, "color0"), Some("#15161E"));
+        assert_eq!(color(&palette, "color16"), None);
+        assert_eq!(palette.colors.len(), 4);
+
+        let xresources = "! comment\n#define bg #282828\n*.background: bg\n*foreground: #ebdbb2\nURxvt*color1: rgb:cc/24/1d\n*.cursorColor: #ebdbb2\n";
+        let palette = parse_one(TerminalFormat::Xresources, xresources);
+        assert_eq!(color(&palette, "background"), Some("#282828"));
+        assert_eq!(color(&palette, "color1"), Some("#CC241D"));
+        assert_eq!(color(&palette, "cursor"), Some("#EBDBB2"));
+    }
+
+    #[test]
+    fn test_parse_iterm2() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Ansi 1 Color</key>
+    <dict>
+        <key>Color Space</key><string>sRGB</string>
+        <key>Blue Component</key><real>0.0</real>
+        <key>Green Component</key><real>0.5</real>
+        <key>Red Component</key><real>1</real>
+    </dict>
+    <key>Background Color</key>
+    <dict>
+        <key>Blue Component</key><real>0.0</real>
+        <key>Green Component</key><real>0.0</real>
+        <key>Red Component</key><real>0.0</real>
+    </dict>
+</dict>
+</plist>"#;
+        let palette = parse_one(TerminalFormat::Iterm2, plist);
+        assert_eq!(color(&palette, "color1"), Some("#FF8000"));
+        assert_eq!(color(&palette, "background"), Some("#000000"));
+        assert!(TerminalFormat::Iterm2.parse("<plist>", "x").is_err());
+    }
+
+    #[test]
+    fn test_parse_windows_terminal() {
+        let single = r##"{ "name": "Campbell", "background": "#0C0C0C", "purple": "#881798", "brightPurple": "#B4009E", "cursorColor": "#FFFFFF" }"##;
+        let palette = parse_one(TerminalFormat::WindowsTerminal, single);
+        assert_eq!(palette.name, "Campbell");
+        assert_eq!(color(&palette, "color5"), Some("#881798"));
+        assert_eq!(color(&palette, "color13"), Some("#B4009E"));
+        assert_eq!(color(&palette, "cursor"), Some("#FFFFFF"));
+
+        let settings = r##"{ "profiles": {}, "schemes": [ { "name": "One", "black": "#000000" }, { "name": "Two", "white": "#FFFFFF" } ] }"##;
+        let palettes = TerminalFormat::WindowsTerminal
+            .parse(settings, "settings")
+            .unwrap();
+        let names: Vec<&str> = palettes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn test_parse_pywal() {
+        let json = r##"{ "wallpaper": "/tmp/wall.png", "alpha": "100", "special": { "background": "#0f1419", "foreground": "#c5c8c6", "cursor": "#c5c8c6" }, "colors": { "color0": "#0f1419", "color4": "#4E6A8D" } }"##;
+        assert_eq!(
+            TerminalFormat::detect("colors.json", json),
+            Some(TerminalFormat::Pywal)
+        );
+        let palette = parse_one(TerminalFormat::Pywal, json);
+        assert_eq!(color(&palette, "background"), Some("#0F1419"));
+        assert_eq!(color(&palette, "cursor"), Some("#C5C8C6"));
+        assert_eq!(color(&palette, "color4"), Some("#4E6A8D"));
+    }
+
+    #[test]
+    fn test_parse_foot_and_wezterm() {
+        let foot = "[main]\nfont=monospace\n[colors]\nbackground=002b36\nregular3=b58900\nbright0=586e75\ncursor=002b36 93a1a1\n";
+        let palette = parse_one(TerminalFormat::Foot, foot);
+        assert_eq!(color(&palette, "background"), Some("#002B36"));
+        assert_eq!(color(&palette, "color3"), Some("#B58900"));
+        assert_eq!(color(&palette, "color8"), Some("#586E75"));
+        assert_eq!(color(&palette, "cursor"), Some("#93A1A1"));
+
+        let wezterm = "[colors]\nbackground = \"#282a36\"\nansi = [\"#21222c\", \"#ff5555\"]\nbrights = [\"#6272a4\"]\n[metadata]\nname = \"Dracula\"\n";
+        let palette = parse_one(TerminalFormat::Wezterm, wezterm);
+        assert_eq!(palette.name, "Dracula");
+        assert_eq!(color(&palette, "color1"), Some("#FF5555"));
+        assert_eq!(color(&palette, "color8"), Some("#6272A4"));
+    }
+
+    #[test]
+    fn test_load_terminal_scheme() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".Xresources");
+        fs::write(&path, "*.color0: #000000\n").unwrap();
+
+        let palettes = load_terminal_scheme(&path).unwrap();
+        assert_eq!(palettes[0].name, "Xresources");
+        assert_eq!(palettes[0].path, PathBuf::from("user://Xresources"));
+
+        let empty = temp_dir.path().join("empty.conf");
+        fs::write(&empty, "font_size 12\n").unwrap();
+        assert!(load_terminal_scheme(&empty).is_err());
+    }
+}
@@ -0,0 +1,688 @@
+use crate::image::{srgb_to_linear, Oklab};
+use crate::{Color, Palette, RustBucketError};
+use image::Rgb;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::parser::{hex_to_rgb, rgb_to_hex};
+use super::terminal::ANSI_NAMES;
+use super::toml_loader::TomlPalette;
+
+/// Reference OKLab hue angles (degrees) for ANSI colors 1 to 6
+const ANSI_HUES: [(usize, f32); 6] = [
+    (1, 22.0),
+    (2, 142.0),
+    (3, 90.0),
+    (4, 264.0),
+    (5, 328.0),
+    (6, 195.0),
+];
+
+/// Palette colors below this OKLab chroma count as neutral
+const NEUTRAL_CHROMA: f32 = 0.04;
+
+/// Minimum contrast of ANSI accents against the background
+const ACCENT_CONTRAST: f32 = 3.0;
+
+/// Theme file formats that can be generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeFormat {
+    /// Alacritty TOML `[colors]` tables
+    #[default]
+    Alacritty,
+    /// kitty.conf color settings
+    Kitty,
+    /// X resources
+    Xresources,
+    /// Windows Terminal color scheme JSON
+    WindowsTerminal,
+    /// base16 scheme YAML
+    Base16,
+    /// rustbucket TOML palette with role color names
+    Toml,
+}
+
+impl ThemeFormat {
+    /// Guess the format from the output file name
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        if name.contains("xresources") || name.contains("xdefaults") {
+            return Some(Self::Xresources);
+        }
+        match extension.as_str() {
+            "conf" => Some(Self::Kitty),
+            "json" => Some(Self::WindowsTerminal),
+            "yaml" | "yml" => Some(Self::Base16),
+            "toml" if name.contains("alacritty") => Some(Self::Alacritty),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the theme has a dark or a light background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeVariant {
+    /// Dark unless most neutral palette colors are light
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+/// A complete terminal color scheme
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalTheme {
+    pub name: String,
+    pub dark: bool,
+    pub background: Rgb<u8>,
+    pub foreground: Rgb<u8>,
+    pub cursor: Rgb<u8>,
+    pub selection: Rgb<u8>,
+    pub ansi: [Rgb<u8>; 16],
+    /// Colors that were changed to meet the contrast targets
+    pub adjustments: Vec<String>,
+}
+
+/// Assigns palette colors to terminal roles by lightness and hue
+#[derive(Debug, Clone)]
+pub struct ThemeGenerator {
+    variant: ThemeVariant,
+    min_contrast: f32,
+}
+
+impl ThemeGenerator {
+    pub fn new() -> Self {
+        Self {
+            variant: ThemeVariant::Auto,
+            min_contrast: 4.5,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: ThemeVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Minimum WCAG contrast ratio between foreground and background
+    pub fn with_min_contrast(mut self, min_contrast: f32) -> Self {
+        self.min_contrast = min_contrast.clamp(1.0, 21.0);
+        self
+    }
+
+    /// Build a theme. Colors already named after terminal roles
+    /// (`background`, `foreground`, `cursor`, `selection_background`,
+    /// `color0` to `color15`) keep their role.
+    pub fn generate(&self, palette: &Palette, name: &str) -> crate::Result<TerminalTheme> {
+        let colors: Vec<(String, Rgb<u8>)> = palette
+            .colors
+            .iter()
+            .map(|c| hex_to_rgb(&c.hex).map(|(r, g, b)| (c.name.to_lowercase(), Rgb([r, g, b]))))
+            .collect::<crate::Result<_>>()?;
+        if colors.len() < 2 {
+            return Err(RustBucketError::PaletteParseError(format!(
+                "Palette '{}' needs at least 2 colors for a theme",
+                palette.name
+            )));
+        }
+
+        let role = |name: &str| colors.iter().find(|(n, _)| n == name).map(|(_, rgb)| *rgb);
+        let labs: Vec<Oklab> = colors
+            .iter()
+            .map(|(_, rgb)| Oklab::from_rgb(*rgb))
+            .collect();
+
+        let dark = match (self.variant, role("background")) {
+            (ThemeVariant::Dark, _) => true,
+            (ThemeVariant::Light, _) => false,
+            (ThemeVariant::Auto, Some(bg)) => Oklab::from_rgb(bg).l < 0.5,
+            (ThemeVariant::Auto, None) => {
+                // Accents say little about the theme; judge by the neutrals
+                let mut lightness: Vec<f32> = labs
+                    .iter()
+                    .filter(|lab| chroma(lab) < NEUTRAL_CHROMA)
+                    .map(|lab| lab.l)
+                    .collect();
+                if lightness.is_empty() {
+                    lightness = labs.iter().map(|lab| lab.l).collect();
+                }
+                lightness.sort_by(f32::total_cmp);
+                lightness[(lightness.len() - 1) / 2] < 0.5
+            }
+        };
+
+        // Extremes by lightness for the background and foreground
+        let by_lightness = |darkest: bool| {
+            let index = (0..labs.len())
+                .min_by(|&a, &b| {
+                    let (a, b) = (labs[a].l, labs[b].l);
+                    if darkest {
+                        a.total_cmp(&b)
+                    } else {
+                        b.total_cmp(&a)
+                    }
+                })
+                .expect("palette has colors");
+            colors[index].1
+        };
+        let background = role("background").unwrap_or_else(|| by_lightness(dark));
+        let mut foreground = role("foreground").unwrap_or_else(|| by_lightness(!dark));
+
+        let mut adjustments = Vec::new();
+        let adjusted = ensure_contrast(foreground, background, self.min_contrast);
+        if adjusted != foreground {
+            adjustments.push(format!(
+                "foreground {} -> {} for {:.1}:1 contrast",
+                hex(foreground),
+                hex(adjusted),
+                self.min_contrast
+            ));
+            foreground = adjusted;
+        }
+
+        let mut ansi = [Rgb([0, 0, 0]); 16];
+        let (low, high) = if dark {
+            (background, foreground)
+        } else {
+            (foreground, background)
+        };
+        ansi[0] = mix(low, high, 0.12);
+        ansi[7] = mix(high, low, 0.15);
+        ansi[8] = mix(background, foreground, 0.4);
+        ansi[15] = high;
+
+        // Chromatic palette colors, matched to the nearest ANSI hue
+        let chromatic: Vec<Oklab> = labs
+            .iter()
+            .copied()
+            .filter(|lab| chroma(lab) >= NEUTRAL_CHROMA)
+            .collect();
+        let (fallback_l, fallback_c) = if chromatic.is_empty() {
+            (if dark { 0.7 } else { 0.55 }, 0.12)
+        } else {
+            let n = chromatic.len() as f32;
+            (
+                chromatic.iter().map(|lab| lab.l).sum::<f32>() / n,
+                chromatic.iter().map(chroma).sum::<f32>() / n,
+            )
+        };
+
+        // Assign each palette color to at most one hue, best matches first.
+        // Saturated colors beat tinted neutrals of a similar hue.
+        let mut candidates: Vec<(f32, usize, usize)> = Vec::new();
+        for (slot, (_, hue)) in ANSI_HUES.iter().enumerate() {
+            for (color, lab) in chromatic.iter().enumerate() {
+                let distance = hue_distance(hue_degrees(lab), *hue);
+                if distance <= 45.0 {
+                    candidates.push((distance - chroma(lab) * 150.0, slot, color));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut matches: [Option<Oklab>; 6] = [None; 6];
+        let mut used = vec![false; chromatic.len()];
+        for (_, slot, color) in candidates {
+            if matches[slot].is_none() && !used[color] {
+                matches[slot] = Some(chromatic[color]);
+                used[color] = true;
+            }
+        }
+
+        for ((index, hue), nearest) in ANSI_HUES.into_iter().zip(matches) {
+            // Synthesize missing hues in the palette's own lightness and chroma
+            let lab = nearest.unwrap_or_else(|| {
+                let radians = hue.to_radians();
+                Oklab {
+                    l: fallback_l,
+                    a: fallback_c * radians.cos(),
+                    b: fallback_c * radians.sin(),
+                }
+            });
+            ansi[index] = lab.to_rgb();
+
+            let step = if dark { 0.08 } else { -0.08 };
+            ansi[index + 8] = Oklab {
+                l: (lab.l + step).clamp(0.0, 1.0),
+                a: lab.a * 1.05,
+                b: lab.b * 1.05,
+            }
+            .to_rgb();
+        }
+
+        // Palette colors named after ANSI roles win over generated ones
+        for (index, color) in ansi.iter_mut().enumerate() {
+            if let Some(rgb) = role(&format!("color{}", index)) {
+                *color = rgb;
+            }
+        }
+
+        for index in (1..7).chain(9..15) {
+            let adjusted = ensure_contrast(ansi[index], background, ACCENT_CONTRAST);
+            if adjusted != ansi[index] {
+                adjustments.push(format!(
+                    "color{} {} -> {} for {:.1}:1 contrast",
+                    index,
+                    hex(ansi[index]),
+                    hex(adjusted),
+                    ACCENT_CONTRAST
+                ));
+                ansi[index] = adjusted;
+            }
+        }
+
+        Ok(TerminalTheme {
+            name: name.to_string(),
+            dark,
+            background,
+            foreground,
+            cursor: role("cursor").unwrap_or(foreground),
+            selection: role("selection_background")
+                .unwrap_or_else(|| mix(background, foreground, 0.2)),
+            ansi,
+            adjustments,
+        })
+    }
+}
+
+impl Default for ThemeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hex(rgb: Rgb<u8>) -> String {
+    rgb_to_hex(rgb[0], rgb[1], rgb[2])
+}
+
+fn chroma(lab: &Oklab) -> f32 {
+    (lab.a * lab.a + lab.b * lab.b).sqrt()
+}
+
+fn hue_degrees(lab: &Oklab) -> f32 {
+    lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0)
+}
+
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let d = (a - b).abs();
+    d.min(360.0 - d)
+}
+
+/// Linear blend in OKLab from `from` (t = 0) to `to` (t = 1)
+fn mix(from: Rgb<u8>, to: Rgb<u8>, t: f32) -> Rgb<u8> {
+    let (a, b) = (Oklab::from_rgb(from), Oklab::from_rgb(to));
+    Oklab {
+        l: a.l + (b.l - a.l) * t,
+        a: a.a + (b.a - a.a) * t,
+        b: a.b + (b.b - a.b) * t,
+    }
+    .to_rgb()
+}
+
+/// WCAG relative luminance
+pub fn relative_luminance(rgb: Rgb<u8>) -> f32 {
+    0.2126 * srgb_to_linear(rgb[0])
+        + 0.7152 * srgb_to_linear(rgb[1])
+        + 0.0722 * srgb_to_linear(rgb[2])
+}
+
+/// WCAG contrast ratio, from 1.0 to 21.0
+pub fn contrast_ratio(a: Rgb<u8>, b: Rgb<u8>) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Move `color` away from `background` in OKLab lightness, keeping its hue,
+/// until the contrast ratio reaches `target`
+fn ensure_contrast(color: Rgb<u8>, background: Rgb<u8>, target: f32) -> Rgb<u8> {
+    if contrast_ratio(color, background) >= target {
+        return color;
+    }
+
+    let lab = Oklab::from_rgb(color);
+    let lighter = Oklab::from_rgb(background).l < 0.5;
+    let mut best = color;
+    for step in 1..=50 {
+        let delta = step as f32 * 0.02;
+        let l = if lighter {
+            lab.l + delta
+        } else {
+            lab.l - delta
+        };
+        // Fade chroma near the ends of the lightness range to stay in gamut
+        let fade = if lighter { 1.0 - l } else { l }.clamp(0.0, 1.0).min(0.3) / 0.3;
+        best = Oklab {
+            l: l.clamp(0.0, 1.0),
+            a: lab.a * fade,
+            b: lab.b * fade,
+        }
+        .to_rgb();
+        if contrast_ratio(best, background) >= target {
+            break;
+        }
+    }
+    best
+}
+
+impl TerminalTheme {
+    /// Minimum contrast of the ANSI accents (colors 1-6 and 9-14)
+    pub fn accent_contrast(&self) -> f32 {
+        (1..7)
+            .chain(9..15)
+            .map(|i| contrast_ratio(self.ansi[i], self.background))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// The theme as a palette with role color names
+    pub fn to_palette(&self) -> Palette {
+        let mut colors = vec![
+            ("background".to_string(), self.background),
+            ("foreground".to_string(), self.foreground),
+            ("cursor".to_string(), self.cursor),
+            ("selection_background".to_string(), self.selection),
+        ];
+        colors.extend(
+            self.ansi
+                .iter()
+                .enumerate()
+                .map(|(i, rgb)| (format!("color{}", i), *rgb)),
+        );
+
+        Palette {
+            name: self.name.clone(),
+            path: format!("user://{}", self.name).into(),
+            colors: colors
+                .into_iter()
+                .map(|(name, rgb)| Color {
+                    name,
+                    hex: hex(rgb),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Base16 slots: base00-07 shade from background to foreground, base08-0F
+    /// are the accents
+    pub fn base16(&self) -> [Rgb<u8>; 16] {
+        let shade = |t: f32| mix(self.background, self.foreground, t);
+        [
+            self.background,
+            shade(0.08),
+            self.selection,
+            self.ansi[8],
+            shade(0.6),
+            self.foreground,
+            shade(1.08),
+            self.ansi[15],
+            self.ansi[1],
+            mix(self.ansi[1], self.ansi[3], 0.5),
+            self.ansi[3],
+            self.ansi[2],
+            self.ansi[6],
+            self.ansi[4],
+            self.ansi[5],
+            mix(self.ansi[1], self.background, 0.4),
+        ]
+    }
+
+    pub fn render(&self, format: ThemeFormat) -> crate::Result<String> {
+        Ok(match format {
+            ThemeFormat::Alacritty => self.render_alacritty(),
+            ThemeFormat::Kitty => self.render_kitty(),
+            ThemeFormat::Xresources => self.render_xresources(),
+            ThemeFormat::WindowsTerminal => self.render_windows_terminal(),
+            ThemeFormat::Base16 => self.render_base16(),
+            ThemeFormat::Toml => toml::to_string_pretty(&TomlPalette::from(&self.to_palette()))
+                .map_err(|e| {
+                    RustBucketError::PaletteParseError(format!("Failed to serialize theme: {}", e))
+                })?,
+        })
+    }
+
+    fn render_alacritty(&self) -> String {
+        let mut out = format!("# {} (generated by rtbt)\n\n", self.name);
+        let _ = writeln!(
+            out,
+            "[colors.primary]\nbackground = \"{}\"\nforeground = \"{}\"\n",
+            hex(self.background),
+            hex(self.foreground)
+        );
+        let _ = writeln!(
+            out,
+            "[colors.cursor]\ncursor = \"{}\"\ntext = \"{}\"\n",
+            hex(self.cursor),
+            hex(self.background)
+        );
+        let _ = writeln!(
+            out,
+            "[colors.selection]\nbackground = \"{}\"\ntext = \"{}\"",
+            hex(self.selection),
+            hex(self.foreground)
+        );
+        for (section, offset) in [("normal", 0), ("bright", 8)] {
+            let _ = writeln!(out, "\n[colors.{}]", section);
+            for (index, name) in ANSI_NAMES.iter().enumerate() {
+                let _ = writeln!(out, "{} = \"{}\"", name, hex(self.ansi[index + offset]));
+            }
+        }
+        out
+    }
+
+    fn render_kitty(&self) -> String {
+        let mut out = format!("## name: {}\n## author: rtbt\n\n", self.name);
+        for (key, rgb) in [
+            ("background", self.background),
+            ("foreground", self.foreground),
+            ("cursor", self.cursor),
+            ("cursor_text_color", self.background),
+            ("selection_background", self.selection),
+            ("selection_foreground", self.foreground),
+        ] {
+            let _ = writeln!(out, "{:<21}{}", key, hex(rgb));
+        }
+        out.push('\n');
+        for (index, rgb) in self.ansi.iter().enumerate() {
+            let _ = writeln!(out, "{:<21}{}", format!("color{}", index), hex(*rgb));
+        }
+        out
+    }
+
+    fn render_xresources(&self) -> String {
+        let mut out = format!("! {} (generated by rtbt)\n", self.name);
+        let _ = writeln!(out, "*.background: {}", hex(self.background));
+        let _ = writeln!(out, "*.foreground: {}", hex(self.foreground));
+        let _ = writeln!(out, "*.cursorColor: {}", hex(self.cursor));
+        for (index, rgb) in self.ansi.iter().enumerate() {
+            let _ = writeln!(out, "*.color{}: {}", index, hex(*rgb));
+        }
+        out
+    }
+
+    fn render_windows_terminal(&self) -> String {
+        let mut fields = vec![
+            ("name".to_string(), self.name.clone()),
+            ("background".to_string(), hex(self.background)),
+            ("foreground".to_string(), hex(self.foreground)),
+            ("cursorColor".to_string(), hex(self.cursor)),
+            ("selectionBackground".to_string(), hex(self.selection)),
+        ];
+        for (index, name) in ANSI_NAMES.iter().enumerate() {
+            // Windows Terminal calls magenta "purple"
+            let key = if *name == "magenta" { "purple" } else { name };
+            fields.push((key.to_string(), hex(self.ansi[index])));
+            fields.push((
+                format!("bright{}{}", key[..1].to_uppercase(), &key[1..]),
+                hex(self.ansi[index + 8]),
+            ));
+        }
+
+        let body: Vec<String> = fields
+            .into_iter()
+            .map(|(key, value)| format!("  \"{}\": {}", key, serde_json::Value::String(value)))
+            .collect();
+        format!("{{\n{}\n}}\n", body.join(",\n"))
+    }
+
+    fn render_base16(&self) -> String {
+        let mut out = format!(
+            "system: \"base16\"\nname: {}\nauthor: \"rtbt\"\nvariant: \"{}\"\npalette:\n",
+            serde_json::Value::String(self.name.clone()),
+            if self.dark { "dark" } else { "light" }
+        );
+        for (index, rgb) in self.base16().iter().enumerate() {
+            let _ = writeln!(out, "  base{:02X}: \"{}\"", index, hex(*rgb));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::{parse_base16_scheme, BuiltinPalettes, TerminalFormat};
+
+    fn palette(colors: &[(&str, &str)]) -> Palette {
+        Palette {
+            name: "test".to_string(),
+            path: "user://test".into(),
+            colors: colors
+                .iter()
+                .map(|(name, hex)| Color {
+                    name: name.to_string(),
+                    hex: hex.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn nord_theme() -> TerminalTheme {
+        let nord = BuiltinPalettes::get_palette("nord").unwrap();
+        ThemeGenerator::new().generate(&nord, "nord").unwrap()
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio(Rgb([0, 0, 0]), Rgb([255, 255, 255])) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(Rgb([90, 90, 90]), Rgb([90, 90, 90])) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_roles_by_lightness_and_hue() {
+        let theme = nord_theme();
+        assert!(theme.dark);
+        assert_eq!(hex(theme.background), "#2E3440");
+        assert!(contrast_ratio(theme.foreground, theme.background) >= 4.5);
+        assert!(theme.accent_contrast() >= ACCENT_CONTRAST);
+
+        // Nord's red (#BF616A) is the closest palette color to the red hue
+        let red = Oklab::from_rgb(theme.ansi[1]);
+        assert!(hue_distance(hue_degrees(&red), 29.0) < 20.0);
+        // Bright colors are lighter in a dark theme
+        assert!(Oklab::from_rgb(theme.ansi[9]).l > red.l);
+    }
+
+    #[test]
+    fn test_variant_and_missing_hues() {
+        let grays = palette(&[("a", "#101010"), ("b", "#F0F0F0"), ("c", "#808080")]);
+        let theme = ThemeGenerator::new()
+            .with_variant(ThemeVariant::Light)
+            .generate(&grays, "grays")
+            .unwrap();
+        assert!(!theme.dark);
+        assert_eq!(hex(theme.background), "#F0F0F0");
+        // Hues missing from the palette are synthesized
+        let blue = Oklab::from_rgb(theme.ansi[4]);
+        assert!(chroma(&blue) > NEUTRAL_CHROMA);
+        assert!(contrast_ratio(theme.ansi[4], theme.background) >= ACCENT_CONTRAST);
+
+        assert!(ThemeGenerator::new()
+            .generate(&palette(&[("a", "#000000")]), "x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_contrast_adjustment_is_reported() {
+        let low = palette(&[("bg", "#202020"), ("fg", "#404040")]);
+        let theme = ThemeGenerator::new().generate(&low, "low").unwrap();
+        assert!(contrast_ratio(theme.foreground, theme.background) >= 4.5);
+        assert!(theme.adjustments[0].starts_with("foreground #404040"));
+    }
+
+    #[test]
+    fn test_role_names_are_kept() {
+        let roles = palette(&[
+            ("background", "#000000"),
+            ("foreground", "#FFFFFF"),
+            ("color1", "#FF0000"),
+            ("cursor", "#00FF00"),
+        ]);
+        let theme = ThemeGenerator::new().generate(&roles, "roles").unwrap();
+        assert_eq!(hex(theme.ansi[1]), "#FF0000");
+        assert_eq!(hex(theme.cursor), "#00FF00");
+        assert_eq!(theme.to_palette().colors.len(), 20);
+    }
+
+    #[test]
+    fn test_rendered_themes_round_trip() {
+        let theme = nord_theme();
+        let expected = theme.to_palette();
+
+        for (format, terminal) in [
+            (ThemeFormat::Alacritty, TerminalFormat::Alacritty),
+            (ThemeFormat::Kitty, TerminalFormat::Kitty),
+            (ThemeFormat::Xresources, TerminalFormat::Xresources),
+            (
+                ThemeFormat::WindowsTerminal,
+                TerminalFormat::WindowsTerminal,
+            ),
+        ] {
+            let rendered = theme.render(format).unwrap();
+            let parsed = terminal.parse(&rendered, "nord").unwrap().remove(0);
+            for color in &parsed.colors {
+                let original = expected.colors.iter().find(|c| c.name == color.name);
+                assert_eq!(
+                    original.map(|c| c.hex.as_str()),
+                    Some(color.hex.as_str()),
+                    "{:?} {}",
+                    format,
+                    color.name
+                );
+            }
+            assert!(parsed.colors.len() >= 19, "{:?}", format);
+        }
+
+        let base16 =
+            parse_base16_scheme(&theme.render(ThemeFormat::Base16).unwrap(), "nord").unwrap();
+        assert_eq!(base16.colors.len(), 16);
+        assert_eq!(base16.colors[0].hex, "#2E3440");
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ThemeFormat::from_path("theme.conf"),
+            Some(ThemeFormat::Kitty)
+        );
+        assert_eq!(
+            ThemeFormat::from_path("alacritty-nord.toml"),
+            Some(ThemeFormat::Alacritty)
+        );
+        assert_eq!(ThemeFormat::from_path("nord.toml"), Some(ThemeFormat::Toml));
+        assert_eq!(
+            ThemeFormat::from_path(".Xresources"),
+            Some(ThemeFormat::Xresources)
+        );
+        assert_eq!(ThemeFormat::from_path("theme.txt"), None);
+    }
+}
@@ -1,5 +1,10 @@
+use super::builtin::BuiltinPalettes;
+use super::compose::{resolve_definition, resolve_definitions, Composition, PaletteDefinition};
+use super::formats::{load_swatch_file, SwatchFormat};
+use super::terminal::{load_terminal_scheme, parse_terminal_scheme, TerminalFormat};
 use crate::{Color, Palette, RustBucketError};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -10,29 +15,95 @@ pub struct TomlPalette {
     pub name: String,
     pub description: Option<String>,
     pub author: Option<String>,
+    /// Palettes to inherit from, either one name or a list
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub extends: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Own colors; with `extends` these override inherited colors by name
+    #[serde(default)]
     pub colors: Vec<TomlColor>,
+    /// Named sets of color names, selected with `-c @group`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Alternative names for colors
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(name) => vec![name],
+        OneOrMany::Many(names) => names,
+    })
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TomlColor {
     pub name: String,
     pub hex: String,
     pub description: Option<String>,
+    /// Groups this color belongs to, added to the palette's `groups`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 impl From<TomlPalette> for Palette {
     fn from(toml_palette: TomlPalette) -> Self {
+        let mut groups = toml_palette.groups;
+        let mut aliases = toml_palette.aliases;
+        let mut colors = Vec::with_capacity(toml_palette.colors.len());
+        for color in toml_palette.colors {
+            for tag in color.tags {
+                let members = groups.entry(tag).or_default();
+                if !members.contains(&color.name) {
+                    members.push(color.name.clone());
+                }
+            }
+            for alias in color.aliases {
+                aliases.insert(alias, color.name.clone());
+            }
+            colors.push(Color {
+                name: color.name,
+                hex: color.hex,
+            });
+        }
+
         Palette {
             name: toml_palette.name.clone(),
             path: PathBuf::from(format!("user://{}", toml_palette.name)),
-            colors: toml_palette
-                .colors
-                .into_iter()
-                .map(|c| Color {
-                    name: c.name,
-                    hex: c.hex,
-                })
-                .collect(),
+            colors,
+            groups,
+            aliases,
+        }
+    }
+}
+
+impl From<TomlPalette> for PaletteDefinition {
+    fn from(mut toml_palette: TomlPalette) -> Self {
+        let composition = Composition {
+            extends: std::mem::take(&mut toml_palette.extends),
+            include: toml_palette.include.take(),
+            exclude: std::mem::take(&mut toml_palette.exclude),
+        };
+        PaletteDefinition {
+            palette: toml_palette.into(),
+            composition,
         }
     }
 }
@@ -43,6 +114,9 @@ impl From<&Palette> for TomlPalette {
             name: palette.name.clone(),
             description: None,
             author: None,
+            extends: Vec::new(),
+            include: None,
+            exclude: Vec::new(),
             colors: palette
                 .colors
                 .iter()
@@ -50,8 +124,11 @@ impl From<&Palette> for TomlPalette {
                     name: c.name.clone(),
                     hex: c.hex.clone(),
                     description: None,
+                    ..Default::default()
                 })
                 .collect(),
+            groups: palette.groups.clone(),
+            aliases: palette.aliases.clone(),
         }
     }
 }
@@ -104,9 +181,29 @@ impl TomlPaletteLoader {
             .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
     }
 
-    /// Load all TOML palettes from all search paths
+    /// Load all TOML and swatch palettes from all search paths. Palettes
+    /// that extend others are resolved against the built-in palettes only;
+    /// `PaletteManager` also resolves them against scheme palettes.
     pub fn load_palettes(&self) -> crate::Result<Vec<Palette>> {
-        let mut palettes = Vec::new();
+        let definitions = self.load_definitions()?;
+        let base = builtin_palette_map();
+        Ok(definitions
+            .iter()
+            .zip(resolve_definitions(&base, &definitions))
+            .filter_map(|(definition, resolved)| match resolved {
+                Ok(palette) => Some(palette),
+                Err(e) => {
+                    log::warn!("Skipping palette '{}': {}", definition.palette.name, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Load all palette files from all search paths without resolving
+    /// `extends`, `include` or `exclude`
+    pub fn load_definitions(&self) -> crate::Result<Vec<PaletteDefinition>> {
+        let mut palettes: Vec<PaletteDefinition> = Vec::new();
         let mut found_directories = Vec::new();
 
         // Search through all configured paths
@@ -130,30 +227,37 @@ impl TomlPaletteLoader {
                 ))
             })?;
 
+            let mut files = Vec::new();
             for entry in entries {
-                let entry = entry.map_err(RustBucketError::IoError)?;
-                let path = entry.path();
-
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("toml") {
-                    match self.load_toml_palette(&path) {
-                        Ok(palette) => {
-                            // Check if we already have a palette with this name
-                            if palettes.iter().any(|p: &Palette| p.name == palette.name) {
-                                log::debug!(
-                                    "Skipping duplicate palette '{}' from {}",
-                                    palette.name,
-                                    path.display()
-                                );
-                            } else {
-                                log::debug!(
-                                    "Loaded palette '{}' from {}",
-                                    palette.name,
-                                    path.display()
-                                );
-                                palettes.push(palette);
-                            }
-                        }
-                        Err(e) => log::warn!("Failed to load palette {}: {}", path.display(), e),
+                let path = entry.map_err(RustBucketError::IoError)?.path();
+                if path.is_file() && Self::is_palette_file(&path) {
+                    files.push(path);
+                }
+            }
+            // TOML files win over swatch files of the same name
+            files.sort_by_key(|path| (!Self::is_toml(path), path.clone()));
+
+            for path in files {
+                let loaded = match self.load_file_definitions(&path) {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        log::warn!("Failed to load palette {}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+
+                for definition in loaded {
+                    let name = &definition.palette.name;
+                    // Check if we already have a palette with this name
+                    if palettes.iter().any(|d| &d.palette.name == name) {
+                        log::debug!(
+                            "Skipping duplicate palette '{}' from {}",
+                            name,
+                            path.display()
+                        );
+                    } else {
+                        log::debug!("Loaded palette '{}' from {}", name, path.display());
+                        palettes.push(definition);
                     }
                 }
             }
@@ -163,7 +267,7 @@ impl TomlPaletteLoader {
             log::info!("No palette directories found in search paths");
         } else {
             log::info!(
-                "Loaded {} TOML palettes from {} directories",
+                "Loaded {} user palettes from {} directories",
                 palettes.len(),
                 found_directories.len()
             );
@@ -172,14 +276,77 @@ impl TomlPaletteLoader {
         Ok(palettes)
     }
 
-    /// Load a single TOML palette file
+    fn is_toml(path: &Path) -> bool {
+        path.extension().and_then(|s| s.to_str()) == Some("toml")
+    }
+
+    fn is_palette_file(path: &Path) -> bool {
+        Self::is_toml(path)
+            || SwatchFormat::from_path(path).is_some()
+            || TerminalFormat::is_candidate(path)
+    }
+
+    fn load_file_definitions(&self, path: &Path) -> crate::Result<Vec<PaletteDefinition>> {
+        if Self::is_toml(path) {
+            let content = fs::read_to_string(path).map_err(RustBucketError::IoError)?;
+            if TerminalFormat::detect(path, &content).is_none() {
+                return Ok(vec![Self::parse_toml_definition(path, &content)?]);
+            }
+            return Ok(parse_terminal_scheme(path, &content)?
+                .into_iter()
+                .map(PaletteDefinition::from)
+                .collect());
+        }
+        Ok(self
+            .load_file_palettes(path)?
+            .into_iter()
+            .map(PaletteDefinition::from)
+            .collect())
+    }
+
+    /// Load the palettes in a TOML, swatch or terminal color scheme file
+    pub fn load_file_palettes<P: AsRef<Path>>(&self, path: P) -> crate::Result<Vec<Palette>> {
+        let path = path.as_ref();
+        if SwatchFormat::from_path(path).is_some() {
+            return Ok(vec![load_swatch_file(path)?]);
+        }
+        if Self::is_toml(path) {
+            // Alacritty and WezTerm schemes are TOML too
+            let content = fs::read_to_string(path).map_err(RustBucketError::IoError)?;
+            if TerminalFormat::detect(path, &content).is_none() {
+                let definition = Self::parse_toml_definition(path, &content)?;
+                return Ok(vec![resolve_definition(
+                    &builtin_palette_map(),
+                    &definition,
+                )?]);
+            }
+            return parse_terminal_scheme(path, &content);
+        }
+        load_terminal_scheme(path)
+    }
+
+    /// Load a single TOML palette file, resolving any parents against the
+    /// built-in palettes
     pub fn load_toml_palette<P: AsRef<Path>>(&self, path: P) -> crate::Result<Palette> {
+        let definition = self.load_toml_definition(path)?;
+        resolve_definition(&builtin_palette_map(), &definition)
+    }
+
+    /// Load a single TOML palette file without resolving its parents
+    pub fn load_toml_definition<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> crate::Result<PaletteDefinition> {
         let content = fs::read_to_string(path.as_ref()).map_err(RustBucketError::IoError)?;
+        Self::parse_toml_definition(path.as_ref(), &content)
+    }
 
-        let toml_palette: TomlPalette = toml::from_str(&content).map_err(|e| {
+    /// Parse the already read `content` of the TOML palette at `path`
+    fn parse_toml_definition(path: &Path, content: &str) -> crate::Result<PaletteDefinition> {
+        let toml_palette: TomlPalette = toml::from_str(content).map_err(|e| {
             RustBucketError::PaletteParseError(format!(
                 "Failed to parse TOML palette {}: {}",
-                path.as_ref().display(),
+                path.display(),
                 e
             ))
         })?;
@@ -194,6 +361,13 @@ impl TomlPaletteLoader {
             })?;
         }
 
+        if toml_palette.colors.is_empty() && toml_palette.extends.is_empty() {
+            return Err(RustBucketError::PaletteParseError(format!(
+                "Palette '{}' has no colors and extends no other palette",
+                toml_palette.name
+            )));
+        }
+
         Ok(toml_palette.into())
     }
 
@@ -214,48 +388,69 @@ impl TomlPaletteLoader {
             name: "example".to_string(),
             description: Some("An example custom palette for image tinting".to_string()),
             author: Some("Your Name".to_string()),
+            extends: Vec::new(),
+            include: None,
+            exclude: Vec::new(),
             colors: vec![
                 TomlColor {
                     name: "dark_blue".to_string(),
                     hex: "#2E3440".to_string(),
                     description: Some("Deep blue-grey tone".to_string()),
+                    tags: vec!["background".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "light_blue".to_string(),
                     hex: "#88C0D0".to_string(),
                     description: Some("Soft cyan-blue".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "green".to_string(),
                     hex: "#A3BE8C".to_string(),
                     description: Some("Muted green".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "yellow".to_string(),
                     hex: "#EBCB8B".to_string(),
                     description: Some("Warm yellow".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "orange".to_string(),
                     hex: "#D08770".to_string(),
                     description: Some("Soft orange".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "red".to_string(),
                     hex: "#BF616A".to_string(),
                     description: Some("Muted red".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "purple".to_string(),
                     hex: "#B48EAD".to_string(),
                     description: Some("Soft purple".to_string()),
+                    tags: vec!["accent".to_string()],
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "white".to_string(),
                     hex: "#ECEFF4".to_string(),
                     description: Some("Light neutral".to_string()),
+                    tags: vec!["foreground".to_string()],
+                    ..Default::default()
                 },
             ],
+            groups: BTreeMap::new(),
+            aliases: BTreeMap::new(),
         };
 
         let toml_content = toml::to_string_pretty(&example).map_err(|e| {
@@ -268,6 +463,13 @@ impl TomlPaletteLoader {
     }
 }
 
+fn builtin_palette_map() -> HashMap<String, Palette> {
+    BuiltinPalettes::get_all()
+        .into_iter()
+        .map(|p| (p.name.to_lowercase(), p))
+        .collect()
+}
+
 impl Default for TomlPaletteLoader {
     fn default() -> Self {
         Self::new()
@@ -285,18 +487,25 @@ mod tests {
             name: "test".to_string(),
             description: Some("Test palette".to_string()),
             author: Some("Test Author".to_string()),
+            extends: Vec::new(),
+            include: None,
+            exclude: Vec::new(),
             colors: vec![
                 TomlColor {
                     name: "red".to_string(),
                     hex: "#FF0000".to_string(),
                     description: Some("Red color".to_string()),
+                    ..Default::default()
                 },
                 TomlColor {
                     name: "blue".to_string(),
                     hex: "#0000FF".to_string(),
                     description: None,
+                    ..Default::default()
                 },
             ],
+            groups: BTreeMap::new(),
+            aliases: BTreeMap::new(),
         };
 
         let palette: Palette = toml_palette.into();
@@ -321,6 +530,7 @@ mod tests {
                     hex: "#0000FF".to_string(),
                 },
             ],
+            ..Default::default()
         };
 
         let toml_palette = TomlPalette::from(&palette);
@@ -422,6 +632,64 @@ hex = "#0000FF"
         assert_eq!(palettes[0].colors[0].hex, "#FF0000");
     }
 
+    #[test]
+    fn test_load_swatch_files_from_search_path() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("sunset.gpl"),
+            "GIMP Palette\nName: Sunset\n255 94 77\tCoral\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("lospec.hex"), "000000\nffffff\n").unwrap();
+        fs::write(temp_dir.path().join("broken.pal"), "not a palette\n").unwrap();
+        fs::write(temp_dir.path().join("notes.md"), "ignored\n").unwrap();
+
+        // A TOML palette shadows a swatch file with the same name
+        fs::write(temp_dir.path().join("a.hex"), "FF0000\n").unwrap();
+        fs::write(
+            temp_dir.path().join("z.toml"),
+            "name = \"a\"\n[[colors]]\nname = \"blue\"\nhex = \"#0000FF\"\n",
+        )
+        .unwrap();
+
+        let loader = TomlPaletteLoader::with_path(temp_dir.path());
+        let palettes = loader.load_palettes().unwrap();
+
+        assert_eq!(palettes.len(), 3);
+        let sunset = palettes.iter().find(|p| p.name == "Sunset").unwrap();
+        assert_eq!(sunset.colors[0].name, "Coral");
+        assert_eq!(sunset.colors[0].hex, "#FF5E4D");
+        assert!(palettes.iter().any(|p| p.name == "lospec"));
+        let shadowed = palettes.iter().find(|p| p.name == "a").unwrap();
+        assert_eq!(shadowed.colors[0].hex, "#0000FF");
+    }
+
+    #[test]
+    fn test_load_terminal_schemes_from_search_path() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("alacritty.toml"),
+            "[colors.primary]\nbackground = \"#1d1f21\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("settings.json"),
+            r##"{ "schemes": [ { "name": "One", "black": "#000000" }, { "name": "Two", "white": "#FFFFFF" } ] }"##,
+        )
+        .unwrap();
+
+        let loader = TomlPaletteLoader::with_path(temp_dir.path());
+        let mut names: Vec<String> = loader
+            .load_palettes()
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["One", "Two", "alacritty"]);
+    }
+
     #[test]
     fn test_create_and_load_example_palette() {
         let temp_dir = tempdir().unwrap();
@@ -457,6 +725,7 @@ hex = "#0000FF"
                     hex: "#33FF57".to_string(),
                 },
             ],
+            ..Default::default()
         };
 
         let loader = TomlPaletteLoader::with_path(temp_dir.path());
@@ -469,4 +738,117 @@ hex = "#0000FF"
         assert_eq!(loaded_palette.colors.len(), original_palette.colors.len());
         assert_eq!(loaded_palette.colors[0].hex, original_palette.colors[0].hex);
     }
+
+    #[test]
+    fn test_extends_accepts_one_or_many() {
+        let one: TomlPalette = toml::from_str("name = \"a\"\nextends = \"nord\"\n").unwrap();
+        assert_eq!(one.extends, vec!["nord"]);
+        assert!(one.colors.is_empty());
+
+        let many: TomlPalette = toml::from_str(
+            "name = \"b\"\nextends = [\"nord\", \"dracula\"]\ninclude = [\"red\"]\n",
+        )
+        .unwrap();
+        assert_eq!(many.extends, vec!["nord", "dracula"]);
+        assert_eq!(many.include, Some(vec!["red".to_string()]));
+
+        // Plain palettes serialize without the composition keys
+        let content = toml::to_string_pretty(&TomlPalette::from(&Palette::from(one))).unwrap();
+        assert!(!content.contains("extends"));
+    }
+
+    #[test]
+    fn test_load_composed_palettes() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("frost.toml"),
+            r##"
+name = "frost"
+extends = "nord"
+include = ["nord7", "nord8", "nord9", "nord10"]
+exclude = ["nord10"]
+
+[[colors]]
+name = "nord8"
+hex = "#00AAFF"
+
+[[colors]]
+name = "ice"
+hex = "#F0FFFF"
+"##,
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("loop.toml"),
+            "name = \"loop\"\nextends = \"loop2\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("loop2.toml"),
+            "name = \"loop2\"\nextends = \"loop\"\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("bare.toml"), "name = \"bare\"\n").unwrap();
+
+        let loader = TomlPaletteLoader::with_path(temp_dir.path());
+        assert_eq!(loader.load_definitions().unwrap().len(), 3);
+
+        // The cycle is skipped rather than failing the whole directory
+        let palettes = loader.load_palettes().unwrap();
+        assert_eq!(palettes.len(), 1);
+        let frost = &palettes[0];
+        let names: Vec<&str> = frost.colors.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["nord7", "nord8", "nord9", "ice"]);
+        assert_eq!(frost.colors[1].hex, "#00AAFF");
+
+        let frost = loader
+            .load_toml_palette(temp_dir.path().join("frost.toml"))
+            .unwrap();
+        assert_eq!(frost.colors.len(), 4);
+        assert!(loader
+            .load_toml_palette(temp_dir.path().join("bare.toml"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_tags_and_aliases() {
+        let toml_palette: TomlPalette = toml::from_str(
+            r##"
+name = "tagged"
+
+[[colors]]
+name = "ink"
+hex = "#101010"
+tags = ["background"]
+
+[[colors]]
+name = "flame"
+hex = "#FF4000"
+tags = ["accent", "warm"]
+aliases = ["red", "orange"]
+
+[groups]
+accent = ["ink"]
+
+[aliases]
+paper = "ink"
+"##,
+        )
+        .unwrap();
+        let palette = Palette::from(toml_palette);
+
+        assert_eq!(palette.group("accent").unwrap(), ["ink", "flame"]);
+        assert_eq!(palette.group("WARM").unwrap(), ["flame"]);
+        assert_eq!(palette.find_color("orange").unwrap().name, "flame");
+        assert_eq!(palette.find_color("paper").unwrap().name, "ink");
+
+        // Groups and aliases survive a save and load
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tagged.toml");
+        let loader = TomlPaletteLoader::with_path(temp_dir.path());
+        loader.save_palette(&palette, &path).unwrap();
+        let loaded = loader.load_toml_palette(&path).unwrap();
+        assert_eq!(loaded.groups, palette.groups);
+        assert_eq!(loaded.aliases, palette.aliases);
+    }
 }
@@ -1,4 +1,4 @@
-use rustbucket::{Config, ImagePipeline, PaletteManager};
+use rustbucket::{Config, ImagePipeline, PaletteManager, Recipe};
 use tempfile::tempdir;
 
 fn create_test_image(
@@ -47,6 +47,7 @@ fn test_full_pipeline_integration() -> Result<(), Box<dyn std::error::Error>> {
         pixels_area: None,
         quiet_mode: true,
         benchmark: false,
+        ..Default::default()
     };
 
     // Process the image
@@ -89,6 +90,7 @@ fn test_pipeline_with_blur() -> Result<(), Box<dyn std::error::Error>> {
         pixels_area: None,
         quiet_mode: true,
         benchmark: false,
+        ..Default::default()
     };
 
     ImagePipeline::process_file(&input_path, &output_path, palette, &config)?;
@@ -97,6 +99,82 @@ fn test_pipeline_with_blur() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_pipeline_with_loaded_image() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let output_path = temp_dir.path().join("test_loaded_output.png");
+
+    let mut palette_manager = PaletteManager::new();
+    palette_manager.load_all_palettes()?;
+    let palette = palette_manager
+        .get_palette("nord")
+        .ok_or("Nord palette not found")?;
+
+    let config = Config {
+        output_path: output_path.clone(),
+        quiet_mode: true,
+        ..Default::default()
+    };
+
+    ImagePipeline::process_loaded_with_diagnostics(
+        create_test_image(40, 30)?,
+        &output_path,
+        palette,
+        &config,
+    )?;
+    let output_image = image::open(&output_path)?;
+    assert_eq!((output_image.width(), output_image.height()), (40, 30));
+
+    Ok(())
+}
+
+#[test]
+fn test_pipeline_with_recipe() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let input_path = temp_dir.path().join("test_recipe_input.png");
+    let output_path = temp_dir.path().join("test_recipe_output.png");
+    let recipe_path = temp_dir.path().join("recipe.toml");
+
+    let test_image = create_test_image(50, 50)?;
+    test_image.save(&input_path)?;
+
+    // Save the flag-equivalent recipe, then run it back through the pipeline
+    let flags = Config {
+        denoise: true,
+        enable_blur: true,
+        blur_resnap: true,
+        ..Default::default()
+    };
+    Recipe::from_config(&flags).save(&recipe_path)?;
+
+    let mut palette_manager = PaletteManager::new();
+    palette_manager.load_all_palettes()?;
+
+    let palette = palette_manager
+        .get_palette("nord")
+        .ok_or("Nord palette not found")?;
+
+    let config = Config {
+        input_path: Some(input_path.clone()),
+        output_path: output_path.clone(),
+        recipe: Some(recipe_path),
+        quiet_mode: true,
+        ..Default::default()
+    };
+
+    let diagnostics =
+        ImagePipeline::process_file_with_diagnostics(&input_path, &output_path, palette, &config)?;
+    let stages: Vec<&str> = diagnostics
+        .stage_timings
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(stages, vec!["denoise", "convert", "blur", "resnap"]);
+    assert!(output_path.exists());
+
+    Ok(())
+}
+
 #[test]
 fn test_pipeline_with_custom_colors() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = tempdir()?;
@@ -123,6 +201,7 @@ fn test_pipeline_with_custom_colors() -> Result<(), Box<dyn std::error::Error>>
         pixels_area: Some((2, 2)),
         quiet_mode: true,
         benchmark: false,
+        ..Default::default()
     };
 
     ImagePipeline::process_file(&input_path, &output_path, palette, &config)?;
@@ -159,6 +238,7 @@ fn test_benchmark_integration() -> Result<(), Box<dyn std::error::Error>> {
         pixels_area: None,
         quiet_mode: true,
         benchmark: true,
+        ..Default::default()
     };
 
     let benchmark = ImageBenchmark::new(config);
@@ -210,6 +290,7 @@ fn test_multiple_palettes() -> Result<(), Box<dyn std::error::Error>> {
                 pixels_area: None,
                 quiet_mode: true,
                 benchmark: false,
+                ..Default::default()
             };
 
             ImagePipeline::process_file(&input_path, &output_path, palette, &config)?;
@@ -219,3 +300,82 @@ fn test_multiple_palettes() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_extracted_palette_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use rustbucket::palette::{ExtractMethod, PaletteExtractor, TomlPaletteLoader};
+
+    let temp_dir = tempdir()?;
+    let palette_path = temp_dir.path().join("gradient.toml");
+    let input_path = temp_dir.path().join("input.png");
+    let output_path = temp_dir.path().join("output.png");
+
+    let test_image = create_test_image(64, 64)?;
+    test_image.save(&input_path)?;
+
+    let palette = PaletteExtractor::new(5)
+        .with_method(ExtractMethod::Kmeans)
+        .extract(&test_image, "gradient")?;
+    let loader = TomlPaletteLoader::with_path(temp_dir.path());
+    loader.save_palette(&palette, &palette_path)?;
+
+    let palettes = loader.load_palettes()?;
+    assert_eq!(palettes.len(), 1);
+    assert_eq!(palettes[0].name, "gradient");
+    assert_eq!(palettes[0].colors.len(), 5);
+
+    let config = Config {
+        input_path: Some(input_path.clone()),
+        output_path: output_path.clone(),
+        palette: "gradient".to_string(),
+        quiet_mode: true,
+        ..Default::default()
+    };
+    ImagePipeline::process_file(&input_path, &output_path, &palettes[0], &config)?;
+    assert!(output_path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_base16_and_pywal_palettes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let scheme_dir = temp_dir.path().join("schemes");
+    std::fs::create_dir(&scheme_dir)?;
+
+    let mut scheme = String::from("scheme: \"Ocean\"\n");
+    for index in 0..16 {
+        scheme.push_str(&format!("base{:02X}: \"{:02x}3040\"\n", index, index * 16));
+    }
+    std::fs::write(scheme_dir.join("ocean.yaml"), scheme)?;
+
+    let pywal_cache = temp_dir.path().join("colors.json");
+    std::fs::write(
+        &pywal_cache,
+        r##"{ "special": { "background": "#101010" }, "colors": { "color1": "#aa3333" } }"##,
+    )?;
+
+    let mut palette_manager = PaletteManager::new()
+        .with_base16_dirs(vec![scheme_dir])
+        .with_pywal_cache(Some(pywal_cache.clone()));
+    palette_manager.load_all_palettes()?;
+
+    let ocean = palette_manager.get_palette("base16-ocean").unwrap();
+    assert_eq!(ocean.colors.len(), 16);
+    assert_eq!(
+        palette_manager.get_palette("pywal").unwrap().colors.len(),
+        2
+    );
+
+    // The pywal palette follows the cache on the next load
+    std::fs::write(
+        &pywal_cache,
+        r##"{ "special": { "background": "#202020" }, "colors": {} }"##,
+    )?;
+    palette_manager.load_all_palettes()?;
+    let pywal = palette_manager.get_palette("pywal").unwrap();
+    assert_eq!(pywal.colors[0].hex, "#202020");
+    assert_eq!(pywal.colors.len(), 1);
+
+    Ok(())
+}